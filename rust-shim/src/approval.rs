@@ -0,0 +1,381 @@
+//! Following up on `REQUIRE_APPROVAL` decisions.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use tokio::time::Instant;
+
+use crate::{Client, Decision, DecisionRecord, Error};
+
+/// First delay between polls of a pending approval; doubles up to
+/// [`MAX_POLL_INTERVAL`].
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A pending approval, from [`DecisionRecord::into_approval_handle`].
+///
+/// Resolve it with [`await_outcome`](Self::await_outcome), withdraw it with
+/// [`cancel`](Self::cancel), or keep it pending with
+/// [`detach`](Self::detach). A handle dropped while the approval is still
+/// pending cancels it in the background (best-effort, and only inside a
+/// Tokio runtime), so abandoned approvals do not pile up on the sidecar.
+pub struct ApprovalHandle<'a> {
+    client: &'a Client,
+    approval_id: String,
+    record: DecisionRecord,
+    settled: bool,
+}
+
+impl std::fmt::Debug for ApprovalHandle<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalHandle")
+            .field("approval_id", &self.approval_id)
+            .field("record", &self.record)
+            .field("settled", &self.settled)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where and until when a human can act on a pending approval, from
+/// [`DecisionRecord::approval_ticket`], e.g. for a chat message or UI
+/// prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalTicket {
+    /// Id to pass to [`Client::wait_for_approval`].
+    pub approval_id: String,
+    /// Page where the approval can be granted or refused
+    /// (`extra["approval_url"]`).
+    pub url: Option<String>,
+    /// When the sidecar stops waiting for an answer
+    /// (`extra["approval_expires_at"]`, RFC 3339).
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl DecisionRecord {
+    /// The approval id of a `REQUIRE_APPROVAL` decision:
+    /// `extra["approval_id"]`, or the invocation id when the sidecar sends
+    /// none.
+    fn approval_id(&self) -> Option<&str> {
+        if !self.requires_approval() {
+            return None;
+        }
+        Some(
+            self.extra("approval_id")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&self.invocation_id),
+        )
+    }
+
+    /// The [`ApprovalTicket`] for a `REQUIRE_APPROVAL` decision; `None` for
+    /// any other decision.
+    pub fn approval_ticket(&self) -> Option<ApprovalTicket> {
+        let approval_id = self.approval_id()?.to_string();
+        let url = self
+            .extra("approval_url")
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let expires_at = self
+            .extra("approval_expires_at")
+            .and_then(|v| v.as_str())
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|t| t.with_timezone(&Utc));
+        Some(ApprovalTicket {
+            approval_id,
+            url,
+            expires_at,
+        })
+    }
+
+    /// A handle on the pending approval for a `REQUIRE_APPROVAL` decision;
+    /// `None` for any other decision. The approval id is
+    /// `extra["approval_id"]`, or the invocation id when the sidecar sends
+    /// none.
+    pub fn into_approval_handle(self, client: &Client) -> Option<ApprovalHandle<'_>> {
+        let approval_id = self.approval_id()?.to_string();
+        Some(ApprovalHandle {
+            client,
+            approval_id,
+            record: self,
+            settled: false,
+        })
+    }
+}
+
+impl ApprovalHandle<'_> {
+    pub fn approval_id(&self) -> &str {
+        &self.approval_id
+    }
+
+    /// The `REQUIRE_APPROVAL` decision the handle was made from.
+    pub fn record(&self) -> &DecisionRecord {
+        &self.record
+    }
+
+    /// Poll `/v1/approvals/{id}` until the approval is decided, returning
+    /// the final record (normally ALLOW or DENY). Fails with
+    /// [`Error::ApprovalTimeout`] if it is still pending after `timeout`;
+    /// the handle stays usable, so the caller can wait again or cancel.
+    pub async fn await_outcome(&mut self, timeout: Duration) -> Result<DecisionRecord, Error> {
+        let record = self
+            .client
+            .wait_for_approval(&self.approval_id, timeout)
+            .await?;
+        self.settled = true;
+        Ok(record)
+    }
+
+    /// Withdraw the approval request (`DELETE /v1/approvals/{id}`). An
+    /// approval the sidecar no longer knows counts as cancelled.
+    pub async fn cancel(mut self) -> Result<(), Error> {
+        self.settled = true;
+        let resp = self
+            .client
+            .approval_request(reqwest::Method::DELETE, &self.approval_id)
+            .send()
+            .await?;
+        if !resp.status().is_success() && resp.status() != StatusCode::NOT_FOUND {
+            return Err(self.client.status_error(resp).await);
+        }
+        Ok(())
+    }
+
+    /// Give up the handle without cancelling, e.g. after
+    /// [`Client::register_approval_callback`], returning the original
+    /// decision.
+    pub fn detach(mut self) -> DecisionRecord {
+        self.settled = true;
+        std::mem::replace(&mut self.record, DecisionRecord::new("", "", ""))
+    }
+}
+
+impl Drop for ApprovalHandle<'_> {
+    fn drop(&mut self) {
+        if self.settled {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            tracing::debug!(approval_id = %self.approval_id, "approval handle dropped outside a runtime, not cancelling");
+            return;
+        };
+        let req = self
+            .client
+            .approval_request(reqwest::Method::DELETE, &self.approval_id);
+        let approval_id = std::mem::take(&mut self.approval_id);
+        runtime.spawn(async move {
+            if let Err(e) = req.send().await {
+                tracing::debug!(approval_id, error = %e, "cancelling abandoned approval failed");
+            }
+        });
+    }
+}
+
+impl Client {
+    /// Poll `/v1/approvals/{approval_id}` until the approval is decided,
+    /// returning the final record (normally ALLOW or DENY). The approval id
+    /// comes from [`DecisionRecord::approval_ticket`]. Fails with
+    /// [`Error::ApprovalTimeout`] if it is still pending after `timeout`,
+    /// and with [`Error::NotFound`] for an approval the sidecar does not
+    /// know.
+    ///
+    /// Polls start 250 ms apart and back off to every 2 s. Unlike
+    /// [`ApprovalHandle`], giving up never cancels the approval.
+    pub async fn wait_for_approval(
+        &self,
+        approval_id: &str,
+        timeout: Duration,
+    ) -> Result<DecisionRecord, Error> {
+        let deadline = Instant::now() + timeout;
+        let mut interval = MIN_POLL_INTERVAL;
+        loop {
+            let record = self.poll_approval(approval_id).await?;
+            if !matches!(
+                record.decision_typed(),
+                Decision::RequireApproval | Decision::Pending
+            ) {
+                return Ok(record);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::ApprovalTimeout(approval_id.to_string()));
+            }
+            tokio::time::sleep_until(deadline.min(now + interval)).await;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+
+    async fn poll_approval(&self, approval_id: &str) -> Result<DecisionRecord, Error> {
+        let resp = self
+            .approval_request(reqwest::Method::GET, approval_id)
+            .send()
+            .await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(format!("approval {approval_id}")));
+        }
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        self.read_json(resp).await
+    }
+
+    fn approval_request(
+        &self,
+        method: reqwest::Method,
+        approval_id: &str,
+    ) -> reqwest::RequestBuilder {
+        let mut req = self.http.request(
+            method,
+            self.endpoint_segments(&["v1", "approvals", approval_id]),
+        );
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        req
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn approval_required() -> DecisionRecord {
+        let mut record = DecisionRecord::new("inv-1", "REQUIRE_APPROVAL", "SG_REQUIRE_APPROVAL");
+        record.extra.insert("approval_id".into(), "appr-1".into());
+        record
+    }
+
+    fn client_for(server: &MockServer) -> Client {
+        Client::new(Config::builder().sidecar_url(server.uri()).build())
+    }
+
+    #[tokio::test]
+    async fn test_await_outcome_polls_until_decided() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(approval_required()))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let client = client_for(&server);
+
+        let allowed = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        assert!(allowed.into_approval_handle(&client).is_none());
+
+        let mut handle = approval_required().into_approval_handle(&client).unwrap();
+        assert_eq!(handle.approval_id(), "appr-1");
+        let outcome = handle.await_outcome(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(outcome.decision, "ALLOW");
+        drop(handle);
+    }
+
+    #[tokio::test]
+    async fn test_approval_ticket_and_wait_for_approval() {
+        let mut record = approval_required();
+        record
+            .extra
+            .insert("approval_url".into(), "https://sg.example/a/appr-1".into());
+        record
+            .extra
+            .insert("approval_expires_at".into(), "2026-01-01T12:00:00Z".into());
+        let ticket = record.approval_ticket().unwrap();
+        assert_eq!(ticket.approval_id, "appr-1");
+        assert_eq!(ticket.url.as_deref(), Some("https://sg.example/a/appr-1"));
+        assert_eq!(
+            ticket.expires_at,
+            Some("2026-01-01T12:00:00Z".parse().unwrap())
+        );
+        let bare = DecisionRecord::new("inv-2", "REQUIRE_APPROVAL", "SG_REQUIRE_APPROVAL");
+        assert_eq!(bare.approval_ticket().unwrap().approval_id, "inv-2");
+        assert!(DecisionRecord::new("inv-3", "DENY", "SG_DENY")
+            .approval_ticket()
+            .is_none());
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(record))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(DecisionRecord::new("inv-1", "DENY", "SG_DENY")),
+            )
+            .mount(&server)
+            .await;
+        let client = client_for(&server);
+        let outcome = client
+            .wait_for_approval(&ticket.approval_id, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(outcome.decision, "DENY");
+        let err = client
+            .wait_for_approval("appr-2", Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert_eq!(err, Error::NotFound("approval appr-2".into()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_drop_delete_the_approval() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(approval_required()))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/approvals/appr-1"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = client_for(&server);
+
+        let mut handle = approval_required().into_approval_handle(&client).unwrap();
+        let err = handle.await_outcome(Duration::ZERO).await.unwrap_err();
+        assert_eq!(err, Error::ApprovalTimeout("appr-1".into()));
+        handle.cancel().await.unwrap();
+
+        drop(approval_required().into_approval_handle(&client));
+        let detached = approval_required()
+            .into_approval_handle(&client)
+            .unwrap()
+            .detach();
+        assert_eq!(detached.decision, "REQUIRE_APPROVAL");
+
+        // Let the background cancellation run.
+        for _ in 0..50 {
+            let deletes = server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .filter(|r| r.method == wiremock::http::Method::DELETE)
+                .count();
+            if deletes == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
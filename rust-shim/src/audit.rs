@@ -0,0 +1,114 @@
+//! Local append-only JSONL audit log of decisions.
+//!
+//! Lines are handed to a dedicated writer thread over a channel, so `decide`
+//! never blocks on file I/O, and each line is written whole, so concurrent
+//! calls cannot interleave partial records.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::{DecisionRecord, Error};
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub invocation_id: String,
+    pub decision: String,
+    pub decision_code: String,
+    pub degraded: bool,
+}
+
+impl AuditEntry {
+    fn from_record(record: &DecisionRecord) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            invocation_id: record.invocation_id.clone(),
+            decision: record.decision.clone(),
+            decision_code: record.decision_code.clone(),
+            degraded: record.degraded,
+        }
+    }
+}
+
+enum Message {
+    Line(String),
+    Flush(oneshot::Sender<std::io::Result<()>>),
+}
+
+/// Handle to the background audit writer.
+pub(crate) struct AuditLog {
+    tx: mpsc::Sender<Message>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::AuditLog(format!("{}: {e}", path.display())))?;
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("skillgate-audit".into())
+            .spawn(move || run_writer(BufWriter::new(file), rx))
+            .map_err(|e| Error::AuditLog(e.to_string()))?;
+        Ok(Self { tx })
+    }
+
+    pub fn record(&self, record: &DecisionRecord) {
+        match serde_json::to_string(&AuditEntry::from_record(record)) {
+            Ok(line) => {
+                let _ = self.tx.send(Message::Line(line));
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to serialize audit entry"),
+        }
+    }
+
+    pub async fn flush(&self) -> Result<(), Error> {
+        let (ack, done) = oneshot::channel();
+        self.tx
+            .send(Message::Flush(ack))
+            .map_err(|_| Error::AuditLog("audit writer stopped".into()))?;
+        done.await
+            .map_err(|_| Error::AuditLog("audit writer stopped".into()))?
+            .map_err(|e| Error::AuditLog(e.to_string()))
+    }
+}
+
+fn run_writer(mut out: BufWriter<File>, rx: mpsc::Receiver<Message>) {
+    for message in rx {
+        match message {
+            Message::Line(line) => {
+                if let Err(e) = writeln!(out, "{line}") {
+                    tracing::warn!(error = %e, "failed to write audit entry");
+                }
+            }
+            Message::Flush(ack) => {
+                let _ = ack.send(out.flush());
+            }
+        }
+    }
+    let _ = out.flush();
+}
+
+/// Read back every entry of an audit log written via
+/// [`Config::audit_log_path`](crate::Config::audit_log_path).
+pub fn read_audit_log(path: &Path) -> Result<Vec<AuditEntry>, Error> {
+    let file = File::open(path).map_err(|e| Error::AuditLog(format!("{}: {e}", path.display())))?;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| Error::AuditLog(e.to_string()))?;
+        if !line.trim().is_empty() {
+            entries.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(entries)
+}
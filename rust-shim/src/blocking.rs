@@ -0,0 +1,307 @@
+//! A synchronous client, for programs without an async runtime. Requires
+//! the `blocking` feature.
+//!
+//! [`Client`] wraps the async [`crate::Client`] and drives it on a private
+//! single-threaded runtime, so every [`Config`] option, including
+//! `fail_open`, the offline bundle and the degraded audit, behaves exactly
+//! as in the async client. The runtime has no worker threads of its own:
+//! background work such as circuit breaker probes and degraded audit
+//! flushes makes progress while a method is blocking.
+//!
+//! ```no_run
+//! use skillgate::blocking::Client;
+//! use skillgate::prelude::{Config, Tool, ToolInvocation};
+//!
+//! let client = Client::new(Config::from_env());
+//! let invocation = ToolInvocation::builder()
+//!     .tool(Tool::builder("fs.read").build())
+//!     .build();
+//! let decision = client.decide(invocation)?;
+//! # Ok::<(), skillgate::Error>(())
+//! ```
+//!
+//! Methods must not be called from within an async runtime, where they
+//! panic; use the async client there. Streaming, event subscriptions and
+//! [`health_watch`](crate::Client::health_watch) are only available on the
+//! async client.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::runtime::Runtime;
+
+use crate::{
+    BudgetCheck, BudgetStatus, BudgetTracker, CacheStats, CircuitState, Config,
+    DecisionExplanation, DecisionRecord, Error, ExecutionContext, LatencyStats, PreparedInvocation,
+    RegistryListing, ReplayResult, SessionSummary, Tool, ToolInvocation, ToolRequest,
+};
+
+/// Blocking counterpart of [`crate::Client`]. Each method blocks the
+/// calling thread until the async method of the same name completes; see
+/// there for the details.
+pub struct Client {
+    inner: crate::Client,
+    // Dropped after `inner`, so background tasks it starts can still wind
+    // down.
+    runtime: Runtime,
+}
+
+impl Client {
+    /// Create a new client with the given config.
+    ///
+    /// Panics if the config is invalid; see [`Client::try_new`].
+    pub fn new(cfg: Config) -> Self {
+        Self::try_new(cfg).expect("failed to build SkillGate client")
+    }
+
+    /// Create a new client, failing as [`crate::Client::try_new`] does, or
+    /// with [`Error::InvalidConfig`] if the runtime cannot be started.
+    pub fn try_new(cfg: Config) -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::InvalidConfig(format!("cannot start blocking runtime: {e}")))?;
+        let inner = {
+            let _guard = runtime.enter();
+            crate::Client::try_new(cfg)?
+        };
+        Ok(Self { inner, runtime })
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.decide(invocation))
+    }
+
+    pub fn decide_as(
+        &self,
+        invocation: ToolInvocation,
+        slt: &str,
+    ) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.decide_as(invocation, slt))
+    }
+
+    pub fn decide_ref(&self, invocation: &ToolInvocation) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.decide_ref(invocation))
+    }
+
+    pub fn decide_prepared(
+        &self,
+        prepared: &PreparedInvocation,
+        tool: Tool,
+        request: ToolRequest,
+    ) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.decide_prepared(prepared, tool, request))
+    }
+
+    pub fn decide_json(&self, invocation: serde_json::Value) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.decide_json(invocation))
+    }
+
+    pub fn decide_or_deny(&self, invocation: ToolInvocation) -> DecisionRecord {
+        self.block_on(self.inner.decide_or_deny(invocation))
+    }
+
+    pub fn decide_cached_or(
+        &self,
+        invocation: ToolInvocation,
+        fallback: impl FnOnce() -> DecisionRecord,
+    ) -> DecisionRecord {
+        self.block_on(self.inner.decide_cached_or(invocation, fallback))
+    }
+
+    pub fn decide_many(
+        &self,
+        invocations: impl IntoIterator<Item = ToolInvocation>,
+        concurrency: usize,
+    ) -> Vec<Result<DecisionRecord, Error>> {
+        self.block_on(self.inner.decide_many(invocations, concurrency))
+    }
+
+    pub fn decide_batch(
+        &self,
+        invocations: Vec<ToolInvocation>,
+    ) -> Vec<Result<DecisionRecord, Error>> {
+        self.block_on(self.inner.decide_batch(invocations))
+    }
+
+    pub fn decide_batch_partial(
+        &self,
+        invocations: Vec<ToolInvocation>,
+    ) -> Result<Vec<Result<DecisionRecord, Error>>, Error> {
+        self.block_on(self.inner.decide_batch_partial(invocations))
+    }
+
+    pub fn decide_with_context_override(
+        &self,
+        tool: Tool,
+        request: ToolRequest,
+        context: ExecutionContext,
+    ) -> Result<DecisionRecord, Error> {
+        self.block_on(
+            self.inner
+                .decide_with_context_override(tool, request, context),
+        )
+    }
+
+    pub fn wait_for_approval(
+        &self,
+        approval_id: &str,
+        timeout: Duration,
+    ) -> Result<DecisionRecord, Error> {
+        self.block_on(self.inner.wait_for_approval(approval_id, timeout))
+    }
+
+    pub fn check_capability(&self, capability: &str, repo: &str) -> Result<bool, Error> {
+        self.block_on(self.inner.check_capability(capability, repo))
+    }
+
+    pub fn replay(
+        &self,
+        invocation: &ToolInvocation,
+        expected: &DecisionRecord,
+    ) -> Result<ReplayResult, Error> {
+        self.block_on(self.inner.replay(invocation, expected))
+    }
+
+    pub fn explain(&self, invocation_id: &str) -> Result<DecisionExplanation, Error> {
+        self.block_on(self.inner.explain(invocation_id))
+    }
+
+    pub fn session_summary(&self, session_id: &str) -> Result<SessionSummary, Error> {
+        self.block_on(self.inner.session_summary(session_id))
+    }
+
+    pub fn get_budgets(&self, workspace_id: &str) -> Result<HashMap<String, BudgetStatus>, Error> {
+        self.block_on(self.inner.get_budgets(workspace_id))
+    }
+
+    pub fn check_budget(&self, capability: &str, cost: u64) -> Result<BudgetCheck, Error> {
+        self.block_on(self.inner.check_budget(capability, cost))
+    }
+
+    pub fn list_tools(&self) -> Result<RegistryListing, Error> {
+        self.block_on(self.inner.list_tools())
+    }
+
+    pub fn register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool {
+        self.block_on(self.inner.register_tool(tool_name, metadata))
+    }
+
+    pub fn upload_resource(
+        &self,
+        ref_id: &str,
+        content: &[u8],
+        content_type: &str,
+    ) -> Result<(), Error> {
+        self.block_on(self.inner.upload_resource(ref_id, content, content_type))
+    }
+
+    pub fn flush_audit_log(&self) -> Result<(), Error> {
+        self.block_on(self.inner.flush_audit_log())
+    }
+
+    pub fn health(&self) -> Result<(), Error> {
+        self.block_on(self.inner.health())
+    }
+
+    pub fn ping(&self) -> Result<Duration, Error> {
+        self.block_on(self.inner.ping())
+    }
+
+    pub fn warmup(&self) -> Result<(), Error> {
+        self.block_on(self.inner.warmup())
+    }
+
+    pub fn health_all(&self) -> Vec<(String, Result<(), Error>)> {
+        self.block_on(self.inner.health_all())
+    }
+
+    pub fn clock_offset(&self) -> chrono::Duration {
+        self.inner.clock_offset()
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.inner.now()
+    }
+
+    pub fn server_api_version(&self) -> Option<String> {
+        self.inner.server_api_version()
+    }
+
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.inner.latency_stats()
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.inner.cache_stats()
+    }
+
+    pub fn budget_tracker(&self) -> Option<&BudgetTracker> {
+        self.inner.budget_tracker()
+    }
+
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.inner.circuit_state()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn invocation() -> ToolInvocation {
+        ToolInvocation::builder()
+            .invocation_id("inv-1")
+            .tool(Tool::builder("fs.read").build())
+            .build()
+    }
+
+    #[test]
+    fn test_blocking_decide_and_fail_modes() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/v1/decide"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(DecisionRecord::new("inv-1", "DENY", "SG_DENY")),
+                )
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let client = Client::new(Config::builder().sidecar_url(server.uri()).build());
+        let record = client.decide(invocation()).unwrap();
+        assert_eq!(record.decision, "DENY");
+
+        let unreachable = || {
+            Config::builder()
+                .sidecar_url("http://127.0.0.1:19999")
+                .timeout(Duration::from_millis(10))
+        };
+        let err = Client::new(unreachable().build())
+            .decide(invocation())
+            .unwrap_err();
+        assert!(matches!(err.root(), Error::EnforcerUnavailable { .. }));
+
+        let record = Client::new(unreachable().fail_open(true).build())
+            .decide(invocation())
+            .unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        assert!(record.degraded);
+    }
+}
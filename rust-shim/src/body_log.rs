@@ -0,0 +1,125 @@
+//! Debug logging of `decide` request and response bodies.
+
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+
+use crate::REDACTED;
+
+/// What [`Config::log_bodies`](crate::Config::log_bodies) logs at `debug`
+/// level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyLogMode {
+    /// Nothing.
+    #[default]
+    Off,
+    /// Bodies and headers with [`Config::redact_param_keys`](crate::Config::redact_param_keys)
+    /// values, the `Authorization` header and `evidence.signature` replaced
+    /// with `"***"`.
+    Redacted,
+    /// Bodies and headers verbatim, secrets included. Only available with
+    /// the `unsafe-logging` feature.
+    #[cfg(feature = "unsafe-logging")]
+    Full,
+}
+
+impl BodyLogMode {
+    pub(crate) fn enabled(self) -> bool {
+        self != Self::Off && tracing::enabled!(tracing::Level::DEBUG)
+    }
+}
+
+/// Log an outbound `decide` request. `body` is `None` for streamed bodies.
+pub(crate) fn log_request(
+    mode: BodyLogMode,
+    redact_keys: &[String],
+    headers: &HeaderMap,
+    body: Option<&[u8]>,
+) {
+    let mut headers = headers.clone();
+    if mode == BodyLogMode::Redacted && headers.contains_key(AUTHORIZATION) {
+        headers.insert(AUTHORIZATION, REDACTED.parse().expect("valid header value"));
+    }
+    let body = match body {
+        Some(body) => render(mode, redact_keys, body),
+        None => "<streamed>".into(),
+    };
+    tracing::debug!(?headers, %body, "decide request");
+}
+
+/// Log an inbound `decide` response body.
+pub(crate) fn log_response(mode: BodyLogMode, redact_keys: &[String], status: u16, body: &[u8]) {
+    let body = render(mode, redact_keys, body);
+    tracing::debug!(status, %body, "decide response");
+}
+
+fn render(mode: BodyLogMode, redact_keys: &[String], body: &[u8]) -> String {
+    if mode != BodyLogMode::Redacted {
+        return String::from_utf8_lossy(body).into_owned();
+    }
+    match serde_json::from_slice(body) {
+        Ok(mut value) => {
+            redact(&mut value, redact_keys);
+            value.to_string()
+        }
+        // Non-JSON bodies may hold anything; don't echo them.
+        Err(_) => format!("<{} bytes, not JSON>", body.len()),
+    }
+}
+
+/// Replace the values of `redact_keys` (case-insensitively, at any depth)
+/// and `evidence.signature`.
+fn redact(value: &mut serde_json::Value, redact_keys: &[String]) {
+    if let Some(signature) = value
+        .get_mut("evidence")
+        .and_then(|evidence| evidence.get_mut("signature"))
+    {
+        *signature = REDACTED.into();
+    }
+    redact_keys_in(value, redact_keys);
+}
+
+fn redact_keys_in(value: &mut serde_json::Value, redact_keys: &[String]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if redact_keys.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                    *value = REDACTED.into();
+                } else {
+                    redact_keys_in(value, redact_keys);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_keys_in(item, redact_keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_body_hides_secrets() {
+        let body = serde_json::json!({
+            "tool_invocation": {"request": {"params": {"Password": "hunter2", "path": "/tmp"}}},
+            "evidence": {"hash": "abc", "signature": "deadbeef", "key_id": "k1"},
+        });
+        let keys = vec!["password".to_string()];
+        let rendered = render(BodyLogMode::Redacted, &keys, body.to_string().as_bytes());
+        let rendered: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            rendered["tool_invocation"]["request"]["params"],
+            serde_json::json!({"Password": "***", "path": "/tmp"})
+        );
+        assert_eq!(rendered["evidence"]["signature"], "***");
+        assert_eq!(rendered["evidence"]["hash"], "abc");
+
+        assert_eq!(
+            render(BodyLogMode::Redacted, &keys, b"secret"),
+            "<6 bytes, not JSON>"
+        );
+    }
+}
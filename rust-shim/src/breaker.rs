@@ -0,0 +1,156 @@
+//! Circuit breaker in front of the sidecar; see [`CircuitBreakerConfig`].
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings for [`Config::circuit_breaker`](crate::Config::circuit_breaker).
+///
+/// After `failure_threshold` consecutive transport failures (connect
+/// errors, timeouts) the circuit opens: requests are resolved in fail mode
+/// at once, without waiting for a timeout. Once `cooldown` has passed, the
+/// next request starts a health probe on a background task and the circuit
+/// is half-open until it completes; a healthy sidecar closes the circuit,
+/// anything else opens it for another `cooldown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit (minimum 1).
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before recovery is probed.
+    pub cooldown: Duration,
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+        }
+    }
+}
+
+/// State of the circuit breaker, from
+/// [`Client::circuit_state`](crate::Client::circuit_state).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go to the sidecar.
+    Closed,
+    /// Requests are resolved in fail mode without contacting the sidecar.
+    Open,
+    /// A recovery probe is in flight; requests still fail fast.
+    HalfOpen,
+}
+
+/// What a request may do under the breaker.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Admission {
+    Allow,
+    Reject,
+    /// Reject, and start the recovery probe.
+    Probe,
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    cfg: CircuitBreakerConfig,
+    state: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    /// Consecutive failures while closed.
+    failures: u32,
+    opened_at: Instant,
+}
+
+impl CircuitBreaker {
+    pub fn new(cfg: CircuitBreakerConfig) -> Self {
+        Self {
+            cfg,
+            state: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                failures: 0,
+                opened_at: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().unwrap().state
+    }
+
+    pub fn admit(&self) -> Admission {
+        let mut inner = self.state.lock().unwrap();
+        match inner.state {
+            CircuitState::Closed => Admission::Allow,
+            CircuitState::HalfOpen => Admission::Reject,
+            CircuitState::Open if inner.opened_at.elapsed() < self.cfg.cooldown => {
+                Admission::Reject
+            }
+            CircuitState::Open => {
+                inner.state = CircuitState::HalfOpen;
+                Admission::Probe
+            }
+        }
+    }
+
+    /// Record whether the sidecar could be reached, by a request or a probe.
+    pub fn record(&self, reachable: bool) {
+        let mut inner = self.state.lock().unwrap();
+        if reachable {
+            if inner.state != CircuitState::Closed {
+                tracing::info!("sidecar reachable again, closing circuit breaker");
+            }
+            inner.state = CircuitState::Closed;
+            inner.failures = 0;
+            return;
+        }
+        match inner.state {
+            CircuitState::Closed => {
+                inner.failures += 1;
+                if inner.failures < self.cfg.failure_threshold.max(1) {
+                    return;
+                }
+                tracing::warn!(
+                    failures = inner.failures,
+                    cooldown = ?self.cfg.cooldown,
+                    "sidecar unreachable, opening circuit breaker"
+                );
+            }
+            // A request sent before the circuit opened; the cooldown
+            // already runs.
+            CircuitState::Open => return,
+            CircuitState::HalfOpen => tracing::debug!("recovery probe failed, reopening circuit"),
+        }
+        inner.state = CircuitState::Open;
+        inner.opened_at = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_transitions() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig::new(2, Duration::ZERO));
+        breaker.record(false);
+        assert_eq!(breaker.admit(), Admission::Allow);
+        breaker.record(true);
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert_eq!(breaker.admit(), Admission::Probe);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.admit(), Admission::Reject);
+        breaker.record(false);
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert_eq!(breaker.admit(), Admission::Probe);
+        breaker.record(true);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.admit(), Admission::Allow);
+    }
+}
@@ -0,0 +1,129 @@
+//! Client-side mirror of sidecar budgets; see [`BudgetTracker`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{BudgetLowHook, BudgetStatus};
+
+/// The latest budget the sidecar reported for each capability, from
+/// [`Client::budget_tracker`](crate::Client::budget_tracker).
+///
+/// Fed by every decision the sidecar returns and by
+/// [`Client::get_budgets`](crate::Client::get_budgets); degraded and cached
+/// decisions carry no fresh budgets and are skipped. Budgets of every
+/// workspace the client decides for share one map, keyed by capability.
+///
+/// When a capability's remaining share falls below
+/// [`Config::budget_low_water`](crate::Config::budget_low_water), a `warn`
+/// event is logged and
+/// [`Config::on_budget_low`](crate::Config::on_budget_low) is called. It
+/// fires again only after the budget has recovered above the mark, e.g. when
+/// its window resets.
+pub struct BudgetTracker {
+    low_water: f64,
+    on_low: Option<BudgetLowHook>,
+    budgets: Mutex<HashMap<String, BudgetStatus>>,
+}
+
+impl std::fmt::Debug for BudgetTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BudgetTracker")
+            .field("low_water", &self.low_water)
+            .field("on_low", &self.on_low.as_ref().map(|_| "<fn>"))
+            .field("budgets", &self.budgets)
+            .finish()
+    }
+}
+
+impl BudgetTracker {
+    pub(crate) fn new(low_water: f64, on_low: Option<BudgetLowHook>) -> Self {
+        Self {
+            low_water,
+            on_low,
+            budgets: Mutex::default(),
+        }
+    }
+
+    /// Units left of `capability`'s budget; `None` for a capability no
+    /// decision has reported a budget for.
+    pub fn remaining(&self, capability: &str) -> Option<u64> {
+        Some(self.budgets.lock().unwrap().get(capability)?.remaining)
+    }
+
+    pub fn get(&self, capability: &str) -> Option<BudgetStatus> {
+        self.budgets.lock().unwrap().get(capability).cloned()
+    }
+
+    /// Every tracked budget by capability.
+    pub fn snapshot(&self) -> HashMap<String, BudgetStatus> {
+        self.budgets.lock().unwrap().clone()
+    }
+
+    /// Take in budgets from the sidecar, reporting those that just fell
+    /// below the low-water mark.
+    pub(crate) fn observe(&self, budgets: &HashMap<String, BudgetStatus>) {
+        let is_low = |budget: &BudgetStatus| budget.fraction_remaining() < self.low_water;
+        let mut crossed = Vec::new();
+        {
+            let mut tracked = self.budgets.lock().unwrap();
+            for (capability, budget) in budgets {
+                let previous = tracked.insert(capability.clone(), budget.clone());
+                if is_low(budget) && !previous.as_ref().is_some_and(is_low) {
+                    crossed.push((capability, budget));
+                }
+            }
+        }
+        for (capability, budget) in crossed {
+            tracing::warn!(
+                capability,
+                remaining = budget.remaining,
+                limit = budget.limit,
+                "budget below low-water mark"
+            );
+            if let Some(hook) = &self.on_low {
+                hook(capability, budget);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_low_water_fires_once_per_crossing() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let hook: BudgetLowHook = {
+            let fired = fired.clone();
+            Arc::new(move |capability: &str, budget: &BudgetStatus| {
+                fired
+                    .lock()
+                    .unwrap()
+                    .push((capability.to_string(), budget.remaining));
+            })
+        };
+        let tracker = BudgetTracker::new(0.2, Some(hook));
+        let observe = |remaining| {
+            tracker.observe(&HashMap::from([(
+                "fs.write".to_string(),
+                BudgetStatus::new(remaining, 10),
+            )]))
+        };
+
+        assert_eq!(tracker.remaining("fs.write"), None);
+        observe(5);
+        observe(1);
+        observe(0);
+        assert_eq!(tracker.remaining("fs.write"), Some(0));
+        // The window resets, then the budget runs low again.
+        observe(10);
+        observe(1);
+        assert_eq!(
+            *fired.lock().unwrap(),
+            [("fs.write".to_string(), 1), ("fs.write".to_string(), 1)]
+        );
+        assert_eq!(tracker.snapshot().len(), 1);
+    }
+}
@@ -0,0 +1,332 @@
+//! Builders for [`ToolInvocation`] and the models it nests.
+//!
+//! Every field has a default, so only what differs needs setting. A
+//! [`ToolInvocationBuilder`] can be kept per session and cloned for each
+//! call; the invocation id and timestamp are filled in at
+//! [`build`](ToolInvocationBuilder::build) unless set explicitly:
+//!
+//! ```
+//! use skillgate::prelude::*;
+//!
+//! let session = ToolInvocation::builder()
+//!     .actor(Actor::builder().id("agent-1").workspace_id("ws-1").session_id("sess-1").build())
+//!     .agent(Agent::builder().name("my-agent").version("1.0.0").build())
+//!     .context(ExecutionContext::builder().repo("my-repo").environment("dev").build());
+//!
+//! let invocation = session
+//!     .clone()
+//!     .tool(Tool::builder("fs.read").risk_class("low").build())
+//!     .param("path", "README.md")
+//!     .build();
+//! assert_eq!(invocation.tool.capabilities, ["fs.read"]);
+//! ```
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{Actor, Agent, ExecutionContext, Tool, ToolInvocation, ToolRequest, TrustTier};
+
+impl ToolInvocation {
+    /// Start a [`ToolInvocationBuilder`]. The actor and agent default to
+    /// those of [`Actor::builder`] and [`Agent::builder`].
+    pub fn builder() -> ToolInvocationBuilder {
+        ToolInvocationBuilder {
+            invocation_id: None,
+            timestamp: None,
+            actor: Actor::builder().build(),
+            agent: Agent::builder().build(),
+            tool: None,
+            request: ToolRequest::default(),
+            context: ExecutionContext::default(),
+        }
+    }
+}
+
+impl Actor {
+    /// Start an [`ActorBuilder`]; the actor type defaults to `"agent"`.
+    pub fn builder() -> ActorBuilder {
+        ActorBuilder {
+            actor: Actor {
+                type_: "agent".into(),
+                ..Actor::default()
+            },
+        }
+    }
+}
+
+impl Agent {
+    /// Start an [`AgentBuilder`]; the framework defaults to `"custom"` and
+    /// the trust tier to [`TrustTier::Standard`].
+    pub fn builder() -> AgentBuilder {
+        AgentBuilder {
+            agent: Agent {
+                framework: "custom".into(),
+                ..Agent::default()
+            }
+            .with_trust_tier(TrustTier::Standard),
+        }
+    }
+}
+
+impl Tool {
+    /// Start a [`ToolBuilder`] for the tool `name`. The provider defaults to
+    /// `"local"`, the capabilities to `[name]` and the risk class to empty.
+    pub fn builder(name: impl Into<String>) -> ToolBuilder {
+        ToolBuilder {
+            tool: Tool {
+                name: name.into(),
+                provider: "local".into(),
+                capabilities: Vec::new(),
+                risk_class: String::new(),
+            },
+        }
+    }
+}
+
+impl ExecutionContext {
+    /// Start an [`ExecutionContextBuilder`] with every field empty.
+    pub fn builder() -> ExecutionContextBuilder {
+        ExecutionContextBuilder::default()
+    }
+}
+
+/// Builder for [`ToolInvocation`], from [`ToolInvocation::builder`].
+#[derive(Debug, Clone)]
+pub struct ToolInvocationBuilder {
+    invocation_id: Option<String>,
+    timestamp: Option<DateTime<Utc>>,
+    actor: Actor,
+    agent: Agent,
+    tool: Option<Tool>,
+    request: ToolRequest,
+    context: ExecutionContext,
+}
+
+impl ToolInvocationBuilder {
+    /// Default: a random UUID v4, generated at [`build`](Self::build).
+    pub fn invocation_id(mut self, id: impl Into<String>) -> Self {
+        self.invocation_id = Some(id.into());
+        self
+    }
+
+    /// Default: `Utc::now()` at [`build`](Self::build).
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn actor(mut self, actor: Actor) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    pub fn agent(mut self, agent: Agent) -> Self {
+        self.agent = agent;
+        self
+    }
+
+    /// Needed for any real call; defaults to an unnamed tool.
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.tool = Some(tool);
+        self
+    }
+
+    /// Replace the params and resource refs set so far.
+    pub fn request(mut self, request: ToolRequest) -> Self {
+        self.request = request;
+        self
+    }
+
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.request.params.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn resource_ref(mut self, resource_ref: impl Into<String>) -> Self {
+        self.request.resource_refs.push(resource_ref.into());
+        self
+    }
+
+    pub fn context(mut self, context: ExecutionContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn build(self) -> ToolInvocation {
+        ToolInvocation {
+            invocation_id: self
+                .invocation_id
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            timestamp: self.timestamp.unwrap_or_else(Utc::now),
+            actor: self.actor,
+            agent: self.agent,
+            tool: self.tool.unwrap_or_else(|| Tool::builder("").build()),
+            request: self.request,
+            context: self.context,
+        }
+    }
+}
+
+/// Builder for [`Actor`], from [`Actor::builder`].
+#[derive(Debug, Clone)]
+pub struct ActorBuilder {
+    actor: Actor,
+}
+
+impl ActorBuilder {
+    pub fn type_(mut self, type_: impl Into<String>) -> Self {
+        self.actor.type_ = type_.into();
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.actor.id = id.into();
+        self
+    }
+
+    pub fn workspace_id(mut self, workspace_id: impl Into<String>) -> Self {
+        self.actor.workspace_id = workspace_id.into();
+        self
+    }
+
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.actor.session_id = session_id.into();
+        self
+    }
+
+    pub fn build(self) -> Actor {
+        self.actor
+    }
+}
+
+/// Builder for [`Agent`], from [`Agent::builder`].
+#[derive(Debug, Clone)]
+pub struct AgentBuilder {
+    agent: Agent,
+}
+
+impl AgentBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.agent.name = name.into();
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.agent.version = version.into();
+        self
+    }
+
+    pub fn framework(mut self, framework: impl Into<String>) -> Self {
+        self.agent.framework = framework.into();
+        self
+    }
+
+    pub fn trust_tier(mut self, tier: TrustTier) -> Self {
+        self.agent = self.agent.with_trust_tier(tier);
+        self
+    }
+
+    pub fn build(self) -> Agent {
+        self.agent
+    }
+}
+
+/// Builder for [`Tool`], from [`Tool::builder`].
+#[derive(Debug, Clone)]
+pub struct ToolBuilder {
+    tool: Tool,
+}
+
+impl ToolBuilder {
+    pub fn provider(mut self, provider: impl Into<String>) -> Self {
+        self.tool.provider = provider.into();
+        self
+    }
+
+    /// Add a capability. Replaces the default `[name]` rather than adding
+    /// to it.
+    pub fn capability(mut self, capability: impl Into<String>) -> Self {
+        self.tool.capabilities.push(capability.into());
+        self
+    }
+
+    pub fn risk_class(mut self, risk_class: impl Into<String>) -> Self {
+        self.tool.risk_class = risk_class.into();
+        self
+    }
+
+    pub fn build(mut self) -> Tool {
+        if self.tool.capabilities.is_empty() && !self.tool.name.is_empty() {
+            self.tool.capabilities.push(self.tool.name.clone());
+        }
+        self.tool
+    }
+}
+
+/// Builder for [`ExecutionContext`], from [`ExecutionContext::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionContextBuilder {
+    context: ExecutionContext,
+}
+
+impl ExecutionContextBuilder {
+    pub fn repo(mut self, repo: impl Into<String>) -> Self {
+        self.context.repo = repo.into();
+        self
+    }
+
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.context.environment = environment.into();
+        self
+    }
+
+    pub fn data_classification(mut self, data_classification: impl Into<String>) -> Self {
+        self.context.data_classification = data_classification.into();
+        self
+    }
+
+    pub fn network_zone(mut self, network_zone: impl Into<String>) -> Self {
+        self.context.network_zone = network_zone.into();
+        self
+    }
+
+    pub fn build(self) -> ExecutionContext {
+        self.context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builders_fill_defaults() {
+        let session = ToolInvocation::builder()
+            .actor(Actor::builder().id("agent-1").workspace_id("ws-1").build())
+            .context(ExecutionContext::builder().repo("my-repo").build());
+        let first = session
+            .clone()
+            .tool(Tool::builder("fs.read").build())
+            .param("path", "a.txt")
+            .resource_ref("file://a.txt")
+            .build();
+        let second = session.tool(Tool::builder("fs.read").build()).build();
+
+        assert_ne!(first.invocation_id, second.invocation_id);
+        assert!(Uuid::parse_str(&first.invocation_id).is_ok());
+        assert_eq!(first.actor.type_, "agent");
+        assert_eq!(first.agent.trust_tier_typed(), Ok(TrustTier::Standard));
+        assert_eq!(first.tool.provider, "local");
+        assert_eq!(first.tool.capabilities, ["fs.read"]);
+        assert_eq!(first.request.params["path"], "a.txt");
+        assert_eq!(first.request.resource_refs, ["file://a.txt"]);
+        assert!(second.request.params.is_empty());
+
+        let tool = Tool::builder("http.get")
+            .capability("net.egress")
+            .risk_class("high")
+            .build();
+        assert_eq!(tool.capabilities, ["net.egress"]);
+        assert_eq!(tool.risk_class, "high");
+    }
+}
@@ -0,0 +1,145 @@
+//! In-memory decision cache; see [`CacheConfig`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::{Decision, DecisionRecord};
+
+/// Settings for [`Config::cache`](crate::Config::cache).
+///
+/// Decisions are keyed by [`ToolInvocation::fingerprint`](crate::ToolInvocation::fingerprint),
+/// so a call with the same actor, agent, tool, params and context as an
+/// earlier one is answered locally until the entry expires. Only ALLOW
+/// and DENY decisions the sidecar actually made are cached; degraded,
+/// FAIL and approval decisions never are. A decision carrying a new
+/// `policy_version` empties the cache.
+///
+/// A hit returns the cached record unchanged, so its `invocation_id` is that
+/// of the call that filled the entry and its evidence still verifies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// How long an entry is served. A shorter
+    /// [`ttl_seconds`](crate::DecisionRecord::ttl_seconds) on the decision
+    /// itself wins.
+    pub ttl: Duration,
+    /// Entries kept at most; the one closest to expiry is evicted first.
+    pub max_entries: usize,
+}
+
+impl CacheConfig {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self { ttl, max_entries }
+    }
+}
+
+/// Counters from [`Client::cache_stats`](crate::Client::cache_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries currently held, expired ones included until evicted.
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct DecisionCache {
+    cfg: CacheConfig,
+    state: Mutex<State>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Policy version of the cached decisions.
+    policy_version: Option<String>,
+    entries: HashMap<String, (DecisionRecord, Instant)>,
+}
+
+impl DecisionCache {
+    pub fn new(cfg: CacheConfig) -> Self {
+        Self {
+            cfg,
+            state: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached decision for `fingerprint`, unless it has expired locally
+    /// or by its own TTL at `now` (sidecar clock).
+    pub fn get(&self, fingerprint: &str, now: DateTime<Utc>) -> Option<DecisionRecord> {
+        let mut state = self.state.lock().unwrap();
+        let hit = match state.entries.get(fingerprint) {
+            Some((record, until)) if Instant::now() < *until && !record.is_expired(now) => {
+                Some(record.clone())
+            }
+            Some(_) => {
+                state.entries.remove(fingerprint);
+                None
+            }
+            None => None,
+        };
+        let counter = if hit.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        hit
+    }
+
+    /// Take note of a sidecar decision's policy version and cache it, if it
+    /// may be.
+    pub fn insert(&self, fingerprint: String, record: &DecisionRecord) {
+        if record.degraded {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.policy_version.as_deref() != Some(&record.policy_version) {
+            if state.policy_version.is_some() {
+                tracing::debug!(
+                    policy_version = %record.policy_version,
+                    "policy version changed, clearing decision cache"
+                );
+            }
+            state.entries.clear();
+            state.policy_version = Some(record.policy_version.clone());
+        }
+        if !matches!(record.decision_typed(), Decision::Allow | Decision::Deny)
+            || self.cfg.max_entries == 0
+        {
+            return;
+        }
+        let now = Instant::now();
+        if state.entries.len() >= self.cfg.max_entries && !state.entries.contains_key(&fingerprint)
+        {
+            state.entries.retain(|_, (_, until)| now < *until);
+            if state.entries.len() >= self.cfg.max_entries {
+                let soonest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (_, until))| *until)
+                    .map(|(key, _)| key.clone());
+                if let Some(key) = soonest {
+                    state.entries.remove(&key);
+                }
+            }
+        }
+        state
+            .entries
+            .insert(fingerprint, (record.clone(), now + self.cfg.ttl));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.state.lock().unwrap().entries.len(),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Canonical JSON serialization shared with the sidecar.
+//!
+//! The canonical form matches Python's
+//! `json.dumps(value, sort_keys=True, separators=(",", ":"), ensure_ascii=True)`:
+//!
+//! - object keys sorted by code point, no insignificant whitespace;
+//! - strings escape `"`, `\`, the short escapes `\b \f \n \r \t`, and every
+//!   other character outside printable ASCII (`0x20..=0x7E`) as `\uXXXX`,
+//!   using UTF-16 surrogate pairs above the BMP;
+//! - integers are written verbatim and floats as Python's `repr`: the
+//!   shortest round-trip digits, positional for exponents in `-4..16`
+//!   (always with a fractional part, `1.0`), otherwise scientific with a
+//!   signed, at least two-digit exponent (`1e-05`, `1.5e+16`).
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::DecisionRecord;
+
+/// The canonical bytes the sidecar hashes for a decision's evidence.
+///
+/// The scope is the record as serialized by this crate, minus `evidence`
+/// itself and the client-side `server_request_id`; `obligations` is omitted
+/// when empty, as sidecars without obligation support do not send it. Hash
+/// the result with SHA-256 and compare the hex digest to
+/// `evidence.hash`.
+pub fn canonical_decision_bytes(record: &DecisionRecord) -> Vec<u8> {
+    let mut value = serde_json::to_value(record).expect("DecisionRecord serializes to JSON");
+    if let Value::Object(map) = &mut value {
+        map.remove("evidence");
+        map.remove("server_request_id");
+        if record.obligations.is_empty() {
+            map.remove("obligations");
+        }
+    }
+    to_canonical_string(&value).into_bytes()
+}
+
+/// Serialize `value` into its canonical JSON text.
+pub(crate) fn to_canonical_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(value, &mut out);
+    out
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if !n.is_i64() && !n.is_u64() => write_float(f, out),
+            _ => out.push_str(&n.to_string()),
+        },
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_float(f: f64, out: &mut String) {
+    // `{:e}` yields the shortest round-trip digits as `d[.ddd]e<exp>`.
+    let sci = format!("{f:e}");
+    let (mantissa, exp) = sci
+        .split_once('e')
+        .expect("LowerExp output has an exponent");
+    let exp: i32 = exp.parse().expect("LowerExp exponent is an integer");
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(m) => ("-", m),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    out.push_str(sign);
+    if (-4..16).contains(&exp) {
+        if exp < 0 {
+            out.push_str("0.");
+            out.extend(std::iter::repeat_n('0', (-exp - 1) as usize));
+            out.push_str(&digits);
+        } else {
+            let int_len = exp as usize + 1;
+            if digits.len() <= int_len {
+                out.push_str(&digits);
+                out.extend(std::iter::repeat_n('0', int_len - digits.len()));
+                out.push_str(".0");
+            } else {
+                out.push_str(&digits[..int_len]);
+                out.push('.');
+                out.push_str(&digits[int_len..]);
+            }
+        }
+    } else {
+        out.push_str(&digits[..1]);
+        if digits.len() > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let _ = write!(out, "e{}{:02}", if exp < 0 { '-' } else { '+' }, exp.abs());
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            ' '..='~' => out.push(c),
+            _ => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    let _ = write!(out, "\\u{unit:04x}");
+                }
+            }
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_python_canonical_json() {
+        let value =
+            serde_json::json!({"b": [1, 2.5, null], "a": "é\n😀\"", "c": {"z": true, "y": false}});
+        assert_eq!(
+            to_canonical_string(&value),
+            r#"{"a":"\u00e9\n\ud83d\ude00\"","b":[1,2.5,null],"c":{"y":false,"z":true}}"#
+        );
+    }
+
+    #[test]
+    fn test_floats_match_python_repr() {
+        let cases = [
+            (1.0, "1.0"),
+            (-0.0, "-0.0"),
+            (0.1, "0.1"),
+            (123.456, "123.456"),
+            (0.0001, "0.0001"),
+            (1e-5, "1e-05"),
+            (1e16, "1e+16"),
+            (1.5e16, "1.5e+16"),
+            (123456789012345.0, "123456789012345.0"),
+            (-2.5e-300, "-2.5e-300"),
+        ];
+        for (f, expected) in cases {
+            assert_eq!(to_canonical_string(&serde_json::json!(f)), expected, "{f}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_decision_bytes_excludes_evidence() {
+        let mut record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        record.evidence.hash = "abc".into();
+        record.server_request_id = Some("req-1".into());
+        let bytes = canonical_decision_bytes(&record);
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            r#"{"budgets":{},"decision":"ALLOW","decision_code":"SG_ALLOW","degraded":false,"entitlement_version":"unknown","invocation_id":"inv-1","license_mode":"unknown","policy_version":"unknown","reason_codes":[]}"#
+        );
+    }
+}
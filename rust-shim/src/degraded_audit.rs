@@ -0,0 +1,194 @@
+//! Deferred delivery of fail-open decisions to the sidecar's `/v1/audit`.
+//!
+//! A degraded ALLOW (`SG_ALLOW_DEGRADED_AUDIT_ASYNC`) is queued in a bounded
+//! buffer that drops its oldest entry on overflow. A background task drains
+//! the buffer once the sidecar is reachable again, retrying with backoff.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::Client as HttpClient;
+use serde::Serialize;
+use tokio::runtime::Handle;
+
+use crate::{DecisionRecord, ToolInvocation};
+
+/// Settings for [`Config::degraded_audit`](crate::Config::degraded_audit).
+#[derive(Debug, Clone)]
+pub struct DegradedAuditConfig {
+    /// Maximum buffered events; the oldest is dropped when full. Default: 1024.
+    pub capacity: usize,
+    /// Fraction of degraded decisions to audit, in `0.0..=1.0`. Default: 1.0.
+    pub sample_rate: f64,
+    /// Runtime to spawn the flush task on. Default: the caller's runtime.
+    pub runtime: Option<Handle>,
+}
+
+impl Default for DegradedAuditConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            sample_rate: 1.0,
+            runtime: None,
+        }
+    }
+}
+
+/// Counters for degraded-decision audit delivery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DegradedAuditStats {
+    /// Events currently waiting for delivery.
+    pub buffered: u64,
+    /// Events delivered to the sidecar.
+    pub flushed: u64,
+    /// Events lost to buffer overflow.
+    pub dropped: u64,
+    /// Degraded decisions skipped by sampling.
+    pub sampled_out: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AuditEvent {
+    invocation: ToolInvocation,
+    decision: DecisionRecord,
+}
+
+pub(crate) struct DegradedAudit {
+    cfg: DegradedAuditConfig,
+    queue: Mutex<VecDeque<AuditEvent>>,
+    flushing: AtomicBool,
+    flushed: AtomicU64,
+    dropped: AtomicU64,
+    sampled_out: AtomicU64,
+}
+
+/// Everything the detached flush task needs to reach the sidecar.
+pub(crate) struct Sink {
+    pub http: HttpClient,
+    pub url: String,
+    pub auth: Option<String>,
+}
+
+const MAX_BATCH: usize = 100;
+
+impl DegradedAudit {
+    pub fn new(cfg: DegradedAuditConfig) -> Arc<Self> {
+        Arc::new(Self {
+            cfg,
+            queue: Mutex::new(VecDeque::new()),
+            flushing: AtomicBool::new(false),
+            flushed: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            sampled_out: AtomicU64::new(0),
+        })
+    }
+
+    pub fn stats(&self) -> DegradedAuditStats {
+        DegradedAuditStats {
+            buffered: self.queue.lock().unwrap().len() as u64,
+            flushed: self.flushed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            sampled_out: self.sampled_out.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sample and buffer a degraded decision, then make sure a flush task is
+    /// running. Never blocks on the network.
+    pub fn enqueue(
+        self: &Arc<Self>,
+        invocation: &ToolInvocation,
+        decision: &DecisionRecord,
+        sink: impl FnOnce() -> Sink,
+    ) {
+        if self.cfg.sample_rate < 1.0 && !rand::thread_rng().gen_bool(self.cfg.sample_rate.max(0.0))
+        {
+            self.sampled_out.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.push(AuditEvent {
+            invocation: invocation.clone(),
+            decision: decision.clone(),
+        });
+
+        let runtime = self
+            .cfg
+            .runtime
+            .clone()
+            .or_else(|| Handle::try_current().ok());
+        let Some(runtime) = runtime else {
+            tracing::debug!("no tokio runtime available, degraded audit stays buffered");
+            return;
+        };
+        if self.flushing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        runtime.spawn(self.clone().flush(sink()));
+    }
+
+    fn push(&self, event: AuditEvent) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= self.cfg.capacity.max(1) {
+            queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queue.push_back(event);
+    }
+
+    async fn flush(self: Arc<Self>, sink: Sink) {
+        let mut delay = Duration::from_millis(100);
+        loop {
+            let batch: Vec<AuditEvent> = {
+                let queue = self.queue.lock().unwrap();
+                queue.iter().take(MAX_BATCH).cloned().collect()
+            };
+            if batch.is_empty() {
+                self.flushing.store(false, Ordering::Release);
+                // An event may have been queued after the snapshot but before
+                // the flag was cleared; pick it up rather than strand it.
+                if self.queue.lock().unwrap().is_empty()
+                    || self.flushing.swap(true, Ordering::AcqRel)
+                {
+                    return;
+                }
+                continue;
+            }
+
+            let mut req = sink
+                .http
+                .post(&sink.url)
+                .json(&serde_json::json!({ "events": batch }));
+            if let Some(auth) = &sink.auth {
+                req = req.header("Authorization", auth);
+            }
+            match req.send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    let mut queue = self.queue.lock().unwrap();
+                    // Entries dropped by overflow while sending shift the
+                    // front; only remove events that are still queued.
+                    let mut sent = 0;
+                    for event in &batch {
+                        if queue.front().is_some_and(|e| {
+                            e.decision.invocation_id == event.decision.invocation_id
+                        }) {
+                            queue.pop_front();
+                            sent += 1;
+                        }
+                    }
+                    self.flushed.fetch_add(sent, Ordering::Relaxed);
+                    delay = Duration::from_millis(100);
+                }
+                outcome => {
+                    tracing::debug!(
+                        status = ?outcome.as_ref().ok().map(|r| r.status().as_u16()),
+                        "degraded audit delivery failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(Duration::from_secs(5));
+                }
+            }
+        }
+    }
+}
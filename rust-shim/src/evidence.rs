@@ -0,0 +1,554 @@
+//! Verification of decision evidence against the sidecar's signing keys.
+//!
+//! The sidecar hashes [`canonical_decision_bytes`] with SHA-256 into
+//! `evidence.hash` (lowercase hex) and signs that hex string with Ed25519 or
+//! ECDSA P-256; `evidence.signature` is the hex-encoded signature and
+//! `evidence.key_id` names the key. Keys rotate, so a [`Keyring`] holds every
+//! key by id and can refresh itself from the sidecar's JWKS endpoint.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_FIXED};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{canonical_decision_bytes, DecisionRecord, Error, USER_AGENT};
+
+/// Connect timeout of the [`Keyring::from_jwks`] client.
+const JWKS_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Whole-request timeout of the [`Keyring::from_jwks`] client. A refetch
+/// can run inside `decide`, so this also bounds how long an unreachable
+/// JWKS endpoint can hold up a decision.
+const JWKS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A public key decision evidence may be signed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EvidenceKey {
+    Ed25519(VerifyingKey),
+    /// An ECDSA P-256 key as an uncompressed SEC1 point (`04 || x || y`).
+    /// Signatures use SHA-256 and may be DER or fixed-width `r || s`.
+    EcdsaP256(Vec<u8>),
+}
+
+impl EvidenceKey {
+    /// Whether `signature` is this key's signature of `message`.
+    pub(crate) fn verifies(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            EvidenceKey::Ed25519(key) => Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify_strict(message, &signature).is_ok()),
+            EvidenceKey::EcdsaP256(point) => {
+                let algorithm = if signature.len() == 64 {
+                    &ECDSA_P256_SHA256_FIXED
+                } else {
+                    &ECDSA_P256_SHA256_ASN1
+                };
+                UnparsedPublicKey::new(algorithm, point)
+                    .verify(message, signature)
+                    .is_ok()
+            }
+        }
+    }
+}
+
+impl From<VerifyingKey> for EvidenceKey {
+    fn from(key: VerifyingKey) -> Self {
+        EvidenceKey::Ed25519(key)
+    }
+}
+
+/// Default [`Keyring::min_refetch_interval`].
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Public keys by `key_id`.
+#[derive(Debug)]
+pub struct Keyring {
+    keys: RwLock<HashMap<String, EvidenceKey>>,
+    /// Where to refetch keys from on an unknown `key_id`, and the response
+    /// size limit. Set by the first client using a [`Keyring::from_sidecar`]
+    /// keyring.
+    jwks: OnceLock<(reqwest::Client, String, Option<usize>)>,
+    from_sidecar: bool,
+    ttl: Option<Duration>,
+    min_refetch_interval: Duration,
+    /// Last successful fetch, for `ttl`.
+    fetched_at: Mutex<Option<Instant>>,
+    /// Last fetch attempt, successful or not, for `min_refetch_interval`.
+    attempted_at: Mutex<Option<Instant>>,
+}
+
+impl Default for Keyring {
+    fn default() -> Self {
+        Self {
+            keys: RwLock::default(),
+            jwks: OnceLock::new(),
+            from_sidecar: false,
+            ttl: None,
+            min_refetch_interval: MIN_REFETCH_INTERVAL,
+            fetched_at: Mutex::default(),
+            attempted_at: Mutex::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    x: Option<String>,
+    #[serde(default)]
+    y: Option<String>,
+}
+
+impl Keyring {
+    /// An empty keyring with no JWKS source; add keys with
+    /// [`Keyring::insert`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the Ed25519 (`"kty": "OKP", "crv": "Ed25519"`) and P-256
+    /// (`"kty": "EC", "crv": "P-256"`) keys published at `url`. The keyring
+    /// refetches from the same URL when asked to verify a decision signed by
+    /// a key it does not know. Each fetch times out after 5 seconds.
+    pub async fn from_jwks(url: impl Into<String>) -> Result<Self, Error> {
+        let http = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(JWKS_CONNECT_TIMEOUT)
+            .timeout(JWKS_TIMEOUT)
+            .build()?;
+        let keyring = Self {
+            jwks: OnceLock::from((http, url.into(), None)),
+            ..Self::default()
+        };
+        keyring.refresh().await?;
+        Ok(keyring)
+    }
+
+    /// A keyring for [`Config::verify_evidence`](crate::Config::verify_evidence)
+    /// that fetches the sidecar's `/v1/keys` JWKS through the client it is
+    /// configured on, so the sidecar URL, path prefix, TLS and proxy settings
+    /// apply. Keys are fetched on first use and cached for an hour; see
+    /// [`Keyring::ttl`].
+    pub fn from_sidecar() -> Self {
+        Self {
+            from_sidecar: true,
+            ttl: Some(Duration::from_secs(3600)),
+            ..Self::default()
+        }
+    }
+
+    /// Refetch the JWKS before verifying once the keys are older than `ttl`,
+    /// picking up new keys before a decision signed with one arrives. If
+    /// the refetch fails, the cached keys are used.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Shortest time between two refetches triggered by verification, for
+    /// `ttl` expiry and unknown `key_id`s alike, failed attempts included.
+    /// Within it, evidence signed by an unknown key fails with
+    /// [`Error::UnknownKeyId`] without asking the JWKS endpoint again.
+    /// Explicit [`Keyring::refresh`] calls are not limited. Default: 10 s.
+    pub fn min_refetch_interval(mut self, interval: Duration) -> Self {
+        self.min_refetch_interval = interval;
+        self
+    }
+
+    /// Point a [`Keyring::from_sidecar`] keyring at the client's keys
+    /// endpoint, reading at most `limit` bytes per response, unless another
+    /// client already has.
+    pub(crate) fn bind_sidecar(&self, http: &reqwest::Client, url: String, limit: Option<usize>) {
+        if self.from_sidecar {
+            let _ = self.jwks.set((http.clone(), url, limit));
+        }
+    }
+
+    /// Claim a refetch for verification, unless there is no JWKS source or
+    /// one was attempted within `min_refetch_interval`.
+    fn claim_refetch(&self) -> bool {
+        if self.jwks.get().is_none() {
+            return false;
+        }
+        let mut attempted_at = self.attempted_at.lock().unwrap();
+        if attempted_at.is_some_and(|at| at.elapsed() < self.min_refetch_interval) {
+            return false;
+        }
+        *attempted_at = Some(Instant::now());
+        true
+    }
+
+    /// True once the keys are due for a time-based refetch.
+    fn stale(&self) -> bool {
+        let (Some(ttl), Some(_)) = (self.ttl, self.jwks.get()) else {
+            return false;
+        };
+        self.fetched_at
+            .lock()
+            .unwrap()
+            .is_none_or(|at| at.elapsed() >= ttl)
+    }
+
+    /// Add or replace a key.
+    pub fn insert(&self, key_id: impl Into<String>, key: impl Into<EvidenceKey>) {
+        self.keys.write().unwrap().insert(key_id.into(), key.into());
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<EvidenceKey> {
+        self.keys.read().unwrap().get(key_id).cloned()
+    }
+
+    /// Refetch the JWKS, adding new keys. Keys no longer published are
+    /// kept, so evidence signed before a rotation still verifies. A no-op
+    /// for keyrings without a JWKS source.
+    pub async fn refresh(&self) -> Result<(), Error> {
+        let Some((http, url, limit)) = self.jwks.get() else {
+            return Ok(());
+        };
+        *self.attempted_at.lock().unwrap() = Some(Instant::now());
+        let resp = http.get(url).send().await?;
+        if !resp.status().is_success() {
+            return Err(crate::Client::sidecar_error(resp, *limit).await);
+        }
+        let jwks: Jwks = serde_json::from_slice(&crate::Client::read_limited(resp, *limit).await?)?;
+        *self.fetched_at.lock().unwrap() = Some(Instant::now());
+        let mut keys = self.keys.write().unwrap();
+        for jwk in jwks.keys {
+            let Some(kid) = jwk.kid.clone() else {
+                continue;
+            };
+            let key = match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+                ("OKP", Some("Ed25519")) => decode_ed25519(&jwk),
+                ("EC", Some("P-256")) => decode_p256(&jwk),
+                _ => continue,
+            };
+            match key {
+                Some(key) => {
+                    keys.insert(kid, key);
+                }
+                None => tracing::warn!(kid, kty = jwk.kty, "skipping malformed JWK"),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_ed25519(jwk: &Jwk) -> Option<EvidenceKey> {
+    let bytes: [u8; 32] = URL_SAFE_NO_PAD
+        .decode(jwk.x.as_ref()?)
+        .ok()?
+        .try_into()
+        .ok()?;
+    VerifyingKey::from_bytes(&bytes)
+        .ok()
+        .map(EvidenceKey::Ed25519)
+}
+
+fn decode_p256(jwk: &Jwk) -> Option<EvidenceKey> {
+    let coordinate = |c: &Option<String>| {
+        URL_SAFE_NO_PAD
+            .decode(c.as_ref()?)
+            .ok()
+            .filter(|bytes| bytes.len() == 32)
+    };
+    let mut point = vec![0x04];
+    point.extend(coordinate(&jwk.x)?);
+    point.extend(coordinate(&jwk.y)?);
+    Some(EvidenceKey::EcdsaP256(point))
+}
+
+impl DecisionRecord {
+    /// Check `evidence` against the record's content and the signing key
+    /// named by `evidence.key_id`, among the keys `keys` holds now. Fails
+    /// with [`Error::UnknownKeyId`] for a key it does not hold; see
+    /// [`DecisionRecord::verify_with`] to refetch keys in that case.
+    pub fn verify(&self, keys: &Keyring) -> Result<(), Error> {
+        let key_id = &self.evidence.key_id;
+        let key = keys
+            .get(key_id)
+            .ok_or_else(|| Error::UnknownKeyId(key_id.clone()))?;
+        self.verify_with_evidence_key(&key)
+    }
+
+    /// Like [`DecisionRecord::verify`], but an unknown key id triggers one
+    /// [`Keyring::refresh`] before failing with [`Error::UnknownKeyId`], as
+    /// do keys older than [`Keyring::ttl`]. Both are limited to one attempt
+    /// per [`Keyring::min_refetch_interval`].
+    pub async fn verify_with(&self, keyring: &Keyring) -> Result<(), Error> {
+        let key_id = &self.evidence.key_id;
+        let mut refreshed = false;
+        if keyring.stale() && keyring.claim_refetch() {
+            refreshed = true;
+            if let Err(e) = keyring.refresh().await {
+                tracing::warn!(error = %e, "refetching evidence keys failed, using cached keys");
+            }
+        }
+        let key = match keyring.get(key_id) {
+            Some(key) => key,
+            None if refreshed || !keyring.claim_refetch() => {
+                return Err(Error::UnknownKeyId(key_id.clone()))
+            }
+            None => {
+                keyring.refresh().await?;
+                keyring
+                    .get(key_id)
+                    .ok_or_else(|| Error::UnknownKeyId(key_id.clone()))?
+            }
+        };
+        self.verify_with_evidence_key(&key)
+    }
+
+    /// Check `evidence` against the record's content and the Ed25519 `key`.
+    pub fn verify_with_key(&self, key: &VerifyingKey) -> Result<(), Error> {
+        self.verify_with_evidence_key(&EvidenceKey::Ed25519(*key))
+    }
+
+    /// Check `evidence` against the record's content and `key`.
+    pub fn verify_with_evidence_key(&self, key: &EvidenceKey) -> Result<(), Error> {
+        let hash = hex::encode(Sha256::digest(canonical_decision_bytes(self)));
+        if !hash.eq_ignore_ascii_case(&self.evidence.hash) {
+            return Err(Error::InvalidEvidence(
+                "hash does not match decision content".into(),
+            ));
+        }
+        let signature = hex::decode(&self.evidence.signature)
+            .map_err(|_| Error::InvalidEvidence("malformed signature".into()))?;
+        if key.verifies(self.evidence.hash.as_bytes(), &signature) {
+            Ok(())
+        } else {
+            Err(Error::InvalidEvidence("signature does not verify".into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn signed_record(key: &SigningKey, key_id: &str) -> DecisionRecord {
+        let mut record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        record.evidence.hash = hex::encode(Sha256::digest(canonical_decision_bytes(&record)));
+        record.evidence.signature =
+            hex::encode(key.sign(record.evidence.hash.as_bytes()).to_bytes());
+        record.evidence.key_id = key_id.into();
+        record
+    }
+
+    fn jwks(keys: &[(&str, &SigningKey)]) -> serde_json::Value {
+        let keys: Vec<_> = keys
+            .iter()
+            .map(|(kid, key)| {
+                serde_json::json!({
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "kid": kid,
+                    "x": URL_SAFE_NO_PAD.encode(key.verifying_key().as_bytes()),
+                })
+            })
+            .collect();
+        serde_json::json!({ "keys": keys })
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_keyring() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let keyring = Keyring::new();
+        keyring.insert("k1", key.verifying_key());
+
+        let mut record = signed_record(&key, "k1");
+        assert_eq!(record.verify_with(&keyring).await, Ok(()));
+
+        record.decision = "DENY".into();
+        assert!(matches!(
+            record.verify_with(&keyring).await,
+            Err(Error::InvalidEvidence(_))
+        ));
+
+        let other = signed_record(&SigningKey::from_bytes(&[8u8; 32]), "k1");
+        assert!(matches!(
+            other.verify_with(&keyring).await,
+            Err(Error::InvalidEvidence(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_ecdsa_p256_from_jwks() {
+        use ring::rand::SystemRandom;
+        use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let point = key.public_key().as_ref();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "keys": [{
+                    "kty": "EC",
+                    "crv": "P-256",
+                    "kid": "ec1",
+                    "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                    "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+                }],
+            })))
+            .mount(&server)
+            .await;
+        let keyring = Keyring::from_jwks(format!("{}/v1/keys", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(
+            keyring.get("ec1"),
+            Some(EvidenceKey::EcdsaP256(point.to_vec()))
+        );
+
+        let mut record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        record.evidence.hash = hex::encode(Sha256::digest(canonical_decision_bytes(&record)));
+        let signature = key.sign(&rng, record.evidence.hash.as_bytes()).unwrap();
+        record.evidence.signature = hex::encode(signature.as_ref());
+        record.evidence.key_id = "ec1".into();
+        assert_eq!(record.verify(&keyring), Ok(()));
+
+        let ed25519 = SigningKey::from_bytes(&[7u8; 32]);
+        record.evidence.signature =
+            hex::encode(ed25519.sign(record.evidence.hash.as_bytes()).to_bytes());
+        assert!(matches!(
+            record.verify(&keyring),
+            Err(Error::InvalidEvidence(_))
+        ));
+        record.evidence.key_id = "missing".into();
+        assert_eq!(
+            record.verify(&keyring),
+            Err(Error::UnknownKeyId("missing".into()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_keys_are_refetched() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(&[("k1", &key)])))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let keyring = Keyring::from_jwks(format!("{}/v1/keys", server.uri()))
+            .await
+            .unwrap()
+            .ttl(Duration::ZERO)
+            .min_refetch_interval(Duration::ZERO);
+        let record = signed_record(&key, "k1");
+        assert_eq!(record.verify_with(&keyring).await, Ok(()));
+        // A failed refetch falls back to the cached keys.
+        assert_eq!(record.verify_with(&keyring).await, Ok(()));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_key_id_refetches_jwks_once() {
+        let old = SigningKey::from_bytes(&[7u8; 32]);
+        let new = SigningKey::from_bytes(&[9u8; 32]);
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(&[("k1", &old)])))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(&[("k2", &new)])))
+            .mount(&server)
+            .await;
+
+        let keyring = Keyring::from_jwks(format!("{}/.well-known/jwks.json", server.uri()))
+            .await
+            .unwrap()
+            .min_refetch_interval(Duration::ZERO);
+        assert!(keyring.get("k2").is_none());
+
+        assert_eq!(
+            signed_record(&new, "k2").verify_with(&keyring).await,
+            Ok(())
+        );
+        // Rotated-out keys are kept.
+        assert_eq!(
+            signed_record(&old, "k1").verify_with(&keyring).await,
+            Ok(())
+        );
+
+        let err = signed_record(&new, "k3")
+            .verify_with(&keyring)
+            .await
+            .unwrap_err();
+        assert_eq!(err, Error::UnknownKeyId("k3".into()));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_refetches_are_throttled() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(&[("k1", &key)])))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let keyring = Keyring::from_jwks(format!("{}/v1/keys", server.uri()))
+            .await
+            .unwrap()
+            .ttl(Duration::ZERO)
+            .min_refetch_interval(Duration::from_millis(200));
+        // The keys are stale right away, but were just fetched.
+        let record = signed_record(&key, "k1");
+        let unknown = signed_record(&key, "k2");
+        for _ in 0..3 {
+            assert_eq!(record.verify_with(&keyring).await, Ok(()));
+            assert_eq!(
+                unknown.verify_with(&keyring).await,
+                Err(Error::UnknownKeyId("k2".into()))
+            );
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        // A failed refetch counts as an attempt too.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        for _ in 0..3 {
+            assert_eq!(record.verify_with(&keyring).await, Ok(()));
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+}
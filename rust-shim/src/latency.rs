@@ -0,0 +1,96 @@
+//! Rolling window of `decide` latencies for
+//! [`Client::latency_stats`](crate::Client::latency_stats).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Summary of the most recent `decide` calls; see
+/// [`Config::latency_window`](crate::Config::latency_window).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    /// Calls in the window, failed ones included.
+    pub count: usize,
+    /// Fraction of calls in the window answered with a fail-open ALLOW.
+    pub fail_open_rate: f64,
+}
+
+/// Fixed-size ring of `(latency, fail_open)` samples.
+#[derive(Debug)]
+pub(crate) struct LatencyWindow {
+    capacity: usize,
+    samples: Mutex<VecDeque<(Duration, bool)>>,
+}
+
+impl LatencyWindow {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, latency: Duration, fail_open: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back((latency, fail_open));
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        let (mut latencies, fail_open): (Vec<Duration>, usize) = {
+            let samples = self.samples.lock().unwrap();
+            (
+                samples.iter().map(|(latency, _)| *latency).collect(),
+                samples.iter().filter(|(_, fail_open)| *fail_open).count(),
+            )
+        };
+        if latencies.is_empty() {
+            return LatencyStats::default();
+        }
+        latencies.sort_unstable();
+        // Nearest-rank percentile.
+        let percentile = |p: f64| {
+            let rank = (p * latencies.len() as f64).ceil() as usize;
+            latencies[rank.clamp(1, latencies.len()) - 1]
+        };
+        LatencyStats {
+            p50: percentile(0.50),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            count: latencies.len(),
+            fail_open_rate: fail_open as f64 / latencies.len() as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_percentiles_and_eviction() {
+        let window = LatencyWindow::new(100);
+        assert_eq!(window.stats(), LatencyStats::default());
+        for ms in 1..=150 {
+            window.record(Duration::from_millis(ms), ms % 10 == 0);
+        }
+        let stats = window.stats();
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.p50, Duration::from_millis(100));
+        assert_eq!(stats.p95, Duration::from_millis(145));
+        assert_eq!(stats.p99, Duration::from_millis(149));
+        assert_eq!(stats.fail_open_rate, 0.1);
+
+        let disabled = LatencyWindow::new(0);
+        disabled.record(Duration::from_millis(1), false);
+        assert_eq!(disabled.stats().count, 0);
+    }
+}
@@ -3,62 +3,520 @@
 //! # Example
 //!
 //! ```rust,no_run
-//! use skillgate::{Client, Config, ToolInvocation, Actor, Agent, Tool, ToolRequest, ExecutionContext};
-//! use chrono::Utc;
+//! use skillgate::prelude::*;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), skillgate::Error> {
 //!     let client = Client::new(Config::from_env());
 //!
-//!     let decision = client.decide(ToolInvocation {
-//!         invocation_id: "inv-001".into(),
-//!         timestamp: Utc::now(),
-//!         actor: Actor { type_: "agent".into(), id: "agent-1".into(),
-//!                         workspace_id: "ws-1".into(), session_id: "sess-1".into() },
-//!         agent: Agent { name: "my-agent".into(), version: "1.0.0".into(),
-//!                         framework: "custom".into(), trust_tier: "standard".into() },
-//!         tool: Tool { name: "fs.read".into(), provider: "local".into(),
-//!                       capabilities: vec!["fs.read".into()], risk_class: "low".into() },
-//!         request: ToolRequest { params: Default::default(), resource_refs: vec![] },
-//!         context: ExecutionContext { repo: "my-repo".into(), environment: "dev".into(),
-//!                                     data_classification: "internal".into(), network_zone: "private".into() },
-//!     }).await?;
+//!     let invocation = ToolInvocation::builder()
+//!         .actor(Actor::builder().id("agent-1").workspace_id("ws-1").session_id("sess-1").build())
+//!         .agent(Agent::builder().name("my-agent").version("1.0.0").build())
+//!         .tool(Tool::builder("fs.read").risk_class("low").build())
+//!         .context(ExecutionContext::builder().repo("my-repo").environment("dev")
+//!                      .data_classification("internal").network_zone("private").build())
+//!         .build();
+//!     let decision = client.decide(invocation).await?;
 //!
 //!     println!("Decision: {}", decision.decision);
 //!     Ok(())
 //! }
 //! ```
+//!
+//! # TLS backends
+//!
+//! `https://` sidecar URLs need a TLS backend, selected with exactly one cargo
+//! feature:
+//!
+//! - `rustls-tls` (default): pure-Rust TLS, no system OpenSSL required.
+//! - `native-tls`: the platform TLS stack (OpenSSL, Secure Transport, SChannel).
+//!
+//! To use `native-tls`, disable default features:
+//!
+//! ```toml
+//! skillgate = { version = "0.1", default-features = false, features = ["native-tls"] }
+//! ```
+//!
+//! If both are enabled, rustls is used. With neither, only plain `http://`
+//! sidecars are reachable. All `Config` options behave the same under either
+//! backend.
+//!
+//! # Unix domain sockets
+//!
+//! A sidecar in the same pod or host can be reached without TCP by setting
+//! the sidecar URL to a socket path, e.g.
+//! `SKILLGATE_SIDECAR_URL=unix:///var/run/skillgate.sock`. Requests and
+//! responses are the same as over TCP.
+//!
+//! # JSON Schema
+//!
+//! The `schema` feature derives `schemars::JsonSchema` for the wire models
+//! and adds [`invocation_schema`] and [`decision_schema`], for validating
+//! other sidecar or mock implementations against this client.
+//!
+//! # Blocking client
+//!
+//! The `blocking` feature adds `blocking::Client`, a synchronous wrapper
+//! with the same methods and semantics for programs that do not run an
+//! async runtime of their own.
+//!
+//! # Forward compatibility
+//!
+//! [`enum@Error`], [`DecisionRecord`], [`BudgetStatus`] and [`Config`] are
+//! `#[non_exhaustive]`, so new variants and fields can ship in minor
+//! releases. Code written against earlier versions needs these changes:
+//!
+//! - `Config { .. }` literals: start from [`Config::default`],
+//!   [`Config::builder`] or [`Config::from_env`] and set fields through the
+//!   builder or by assignment.
+//! - `DecisionRecord { .. }` literals, typically in test stubs: use
+//!   [`DecisionRecord::new`] and assign the remaining public fields.
+//! - `BudgetStatus { .. }` literals: use [`BudgetStatus::new`].
+//! - Exhaustive `match` on [`enum@Error`]: add a wildcard arm.
 
-use std::collections::HashMap;
-use std::time::Duration;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use reqwest::{Client as HttpClient, StatusCode};
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, ETAG,
+    IF_NONE_MATCH,
+};
+use reqwest::{Client as HttpClient, NoProxy, Proxy, StatusCode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
+use tokio::sync::{oneshot, watch, Semaphore, SemaphorePermit};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+mod approval;
+mod audit;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod body_log;
+mod breaker;
+mod budget;
+mod builder;
+mod cache;
+mod canonical;
+mod degraded_audit;
+mod evidence;
+mod latency;
+mod noop;
+mod offline;
+pub mod prelude;
+mod prepared;
+mod queue;
+mod rate_limit;
+mod retry;
+mod sse;
+#[cfg(feature = "test-util")]
+pub mod testing;
+mod wire;
+
+pub use approval::{ApprovalHandle, ApprovalTicket};
+pub use audit::{read_audit_log, AuditEntry};
+pub use body_log::BodyLogMode;
+pub use breaker::{CircuitBreakerConfig, CircuitState};
+pub use budget::BudgetTracker;
+pub use builder::{
+    ActorBuilder, AgentBuilder, ExecutionContextBuilder, ToolBuilder, ToolInvocationBuilder,
+};
+pub use cache::{CacheConfig, CacheStats};
+pub use canonical::canonical_decision_bytes;
+pub use degraded_audit::{DegradedAuditConfig, DegradedAuditStats};
+pub use ed25519_dalek::VerifyingKey;
+pub use evidence::{EvidenceKey, Keyring};
+pub use latency::LatencyStats;
+pub use noop::{NoopClient, ENFORCEMENT_DISABLED_CODE};
+pub use offline::{OfflineBundle, OfflineRule};
+pub use prepared::PreparedInvocation;
+pub use queue::{Overflow, QueueConfig};
+pub use rate_limit::{RateLimit, RateLimitMode};
+pub use retry::RetryPolicy;
+
+use audit::AuditLog;
+use breaker::{Admission, CircuitBreaker};
+use cache::DecisionCache;
+use degraded_audit::DegradedAudit;
+use latency::LatencyWindow;
+use queue::DecideQueue;
+use sse::SseParser;
+use wire::DecideBody;
+
+/// Response header carrying the sidecar's internal request id.
+pub const REQUEST_ID_HEADER: &str = "X-SkillGate-Request-Id";
+
+/// Request header carrying the per-request nonce when signing is enabled.
+pub const NONCE_HEADER: &str = "X-SkillGate-Nonce";
+
+/// Request header carrying the signing timestamp (Unix seconds).
+pub const TIMESTAMP_HEADER: &str = "X-SkillGate-Timestamp";
+
+/// Request header carrying the hex HMAC-SHA256 request signature.
+pub const SIGNATURE_HEADER: &str = "X-SkillGate-Client-Signature";
+
+/// Request header carrying `actor.workspace_id` for header-based routing.
+pub const WORKSPACE_HEADER: &str = "X-SkillGate-Workspace";
+
+/// Response header carrying the sidecar's current time (RFC 3339).
+pub const SERVER_TIME_HEADER: &str = "X-SkillGate-Time";
+
+/// SHA-256 (hex) of an uploaded resource body; see [`Client::upload_resource`].
+pub const CONTENT_SHA256_HEADER: &str = "X-SkillGate-Content-Sha256";
+
+/// Request header asking the sidecar to evaluate a `decide` request without
+/// recording the decision or consuming budgets; see [`Client::replay`].
+pub const DRY_RUN_HEADER: &str = "X-SkillGate-Dry-Run";
+
+/// Response header carrying the sidecar's wire API version.
+pub const API_VERSION_HEADER: &str = "X-SkillGate-Api-Version";
+
+/// Major sidecar API version this client speaks; any `1.x` is accepted.
+pub const SUPPORTED_API_MAJOR: u64 = 1;
+
+/// Default `User-Agent` sent on every request.
+pub const USER_AGENT: &str = concat!("skillgate-rust/", env!("CARGO_PKG_VERSION"));
+
+/// Time allowed for [`Client::warmup`] to establish a connection.
+pub const WARMUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Decision code of the ALLOW returned when the sidecar is unreachable and
+/// [`Config::fail_open`] is set.
+const FAIL_OPEN_CODE: &str = "SG_ALLOW_DEGRADED_AUDIT_ASYNC";
+
+/// Replacement value for redacted params.
+const REDACTED: &str = "***";
+
+/// Stand-in base URL for requests over a Unix socket; the host only ends up
+/// in the `Host` header.
+const UNIX_SOCKET_BASE_URL: &str = "http://localhost";
 
 // ---- Errors -----------------------------------------------------------------
 
 /// Errors returned by the SkillGate client.
+///
+/// New variants may be added; match with a wildcard arm.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
-    #[error("sidecar unreachable (fail-closed): {0}")]
-    EnforcerUnavailable(String),
+    /// The sidecar could not be reached. `source` holds the transport
+    /// error when there is one; `reason` says what failed.
+    #[error("sidecar unreachable (fail-closed): {reason}")]
+    EnforcerUnavailable {
+        reason: String,
+        #[source]
+        source: Option<reqwest::Error>,
+    },
 
-    #[error("sidecar returned error status {0}: {1}")]
-    SidecarError(u16, String),
+    /// Non-success response. `Display` shows the status, the body truncated
+    /// to 200 characters and, when present, the `X-SkillGate-Request-Id`;
+    /// all response headers are kept in `headers`.
+    #[error(
+        "sidecar returned error status {status}: {}{}",
+        truncate_for_display(body),
+        request_id_suffix(headers)
+    )]
+    SidecarError {
+        status: u16,
+        body: String,
+        headers: HeaderMap,
+    },
 
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
 
+    /// A MessagePack or CBOR body could not be encoded or decoded; see
+    /// [`WireFormat`].
+    #[error("wire format error: {0}")]
+    Codec(String),
+
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
+
+    #[error("offline bundle error: {0}")]
+    OfflineBundle(String),
+
+    #[error("audit log error: {0}")]
+    AuditLog(String),
+
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+
+    /// [`Config::slt`] is a JWT whose `exp` has passed; returned before
+    /// sending when [`Config::slt_expiry_warning`] is set.
+    #[error("session license token expired at {expired_at}")]
+    SltExpired { expired_at: DateTime<Utc> },
+
+    #[error("{count} resource refs exceed the configured limit of {limit}")]
+    TooManyResourceRefs { count: usize, limit: usize },
+
+    /// A param value exceeds [`ParamLimits::max_param_bytes`].
+    #[error("param {key:?} is {size} bytes, over the configured limit of {limit}")]
+    ParamTooLarge {
+        key: String,
+        size: usize,
+        limit: usize,
+    },
+
+    #[error("{count} params exceed the configured limit of {limit}")]
+    TooManyParams { count: usize, limit: usize },
+
+    /// The params as a whole exceed [`ParamLimits::max_total_bytes`].
+    #[error("params are {size} bytes, over the configured limit of {limit}")]
+    ParamsTooLarge { size: usize, limit: usize },
+
+    #[error("sidecar policy version {got} is older than required {required}")]
+    PolicyVersionTooOld { got: String, required: String },
+
+    #[error("sidecar response exceeds the configured limit of {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+
+    #[error("sidecar API version {got} is not supported (client speaks {supported}.x)")]
+    ApiVersionUnsupported { got: String, supported: u64 },
+
+    /// The sidecar answered `decide` with success but an empty, `null` or
+    /// `{}` body. Resolved like an unreachable sidecar when `fail_open` is
+    /// set.
+    #[error("sidecar returned an empty decision")]
+    EmptyDecision,
+
+    /// [`Config::rate_limit`] has no token available; one is expected after
+    /// `retry_after`.
+    #[error("client rate limit exceeded, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+
+    /// The call overflowed [`Config::queue`], or was evicted from it by
+    /// [`Overflow::DropOldest`].
+    #[error("decide queue is full")]
+    QueueFull,
+
+    /// The decision's `evidence.key_id` is not in the [`Keyring`], even
+    /// after refreshing it.
+    #[error("unknown evidence signing key {0:?}")]
+    UnknownKeyId(String),
+
+    /// The decision's evidence hash or signature does not check out.
+    #[error("invalid decision evidence: {0}")]
+    InvalidEvidence(String),
+
+    #[error("unknown trust tier {0:?}")]
+    UnknownTrustTier(String),
+
+    /// The sidecar has no record of the requested resource (HTTP 404),
+    /// e.g. `"session sess-1"`.
+    #[error("{0} not found")]
+    NotFound(String),
+
+    /// The approval with this id was still pending when
+    /// [`Client::wait_for_approval`] or [`ApprovalHandle::await_outcome`]
+    /// gave up.
+    #[error("approval {0} still pending after timeout")]
+    ApprovalTimeout(String),
+
+    /// The sidecar rejected one invocation of a
+    /// [`Client::decide_batch_partial`] call; `index` is its position in the
+    /// batch. The other invocations are unaffected.
+    #[error("batch item {index} failed: {message}")]
+    BatchItem { index: usize, message: String },
+
+    /// A [`Client::decide`] failure, tagged with the invocation it belongs
+    /// to. The classification helpers ([`status`](Error::status),
+    /// [`is_transient`](Error::is_transient), ...) look through to the
+    /// underlying error; use [`root`](Error::root) to match on it.
+    #[error("invocation {invocation_id}{}: {source}", session_suffix(session_id))]
+    Request {
+        invocation_id: String,
+        session_id: Option<String>,
+        source: Box<Error>,
+    },
+}
+
+/// Errors compare equal when they are the same variant with the same data.
+/// Wrapped `serde_json` and `reqwest` errors, which are not comparable
+/// themselves, are compared by their `Display` text.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Error::SidecarError {
+                    status: a,
+                    body: a_body,
+                    headers: a_headers,
+                },
+                Error::SidecarError {
+                    status: b,
+                    body: b_body,
+                    headers: b_headers,
+                },
+            ) => a == b && a_body == b_body && a_headers == b_headers,
+            (
+                Error::Request {
+                    invocation_id: a,
+                    session_id: a_session,
+                    source: a_source,
+                },
+                Error::Request {
+                    invocation_id: b,
+                    session_id: b_session,
+                    source: b_source,
+                },
+            ) => a == b && a_session == b_session && a_source == b_source,
+            _ => {
+                std::mem::discriminant(self) == std::mem::discriminant(other)
+                    && self.to_string() == other.to_string()
+            }
+        }
+    }
+}
+
+impl Error {
+    /// [`Error::EnforcerUnavailable`] for a request that could not be sent.
+    fn unreachable(source: reqwest::Error) -> Self {
+        let reason = if source.is_timeout() {
+            "request timed out"
+        } else if source.is_connect() {
+            "connection failed"
+        } else {
+            "request failed"
+        };
+        Error::EnforcerUnavailable {
+            reason: reason.into(),
+            source: Some(source),
+        }
+    }
+
+    /// The underlying error, looking through [`Error::Request`] context.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Request { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// Owned version of [`root`](Error::root).
+    pub fn into_root(self) -> Error {
+        match self {
+            Error::Request { source, .. } => source.into_root(),
+            err => err,
+        }
+    }
+
+    /// Invocation id attached by [`Client::decide`], if any.
+    pub fn invocation_id(&self) -> Option<&str> {
+        match self {
+            Error::Request { invocation_id, .. } => Some(invocation_id),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of an [`Error::SidecarError`].
+    pub fn status(&self) -> Option<u16> {
+        match self.root() {
+            Error::SidecarError { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Response header of an [`Error::SidecarError`], e.g. `Retry-After` or
+    /// `X-Policy-Version`. Returns `None` for other variants and non-UTF-8
+    /// values.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        match self.root() {
+            Error::SidecarError { headers, .. } => headers.get(name)?.to_str().ok(),
+            _ => None,
+        }
+    }
+
+    /// True for [`Error::Http`] and [`Error::EnforcerUnavailable`] errors
+    /// caused by the request timing out.
+    pub fn is_timeout(&self) -> bool {
+        match self.root() {
+            Error::Http(e) => e.is_timeout(),
+            Error::EnforcerUnavailable {
+                source: Some(e), ..
+            } => e.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// True when no connection to the sidecar could be used:
+    /// [`Error::EnforcerUnavailable`] (the request could not be sent at all,
+    /// including connect timeouts) and [`Error::Http`] connect failures.
+    pub fn is_connect(&self) -> bool {
+        match self.root() {
+            Error::EnforcerUnavailable { .. } => true,
+            Error::Http(e) => e.is_connect(),
+            _ => false,
+        }
+    }
+
+    /// True for failures worth retrying: anything matched by
+    /// [`is_timeout`](Self::is_timeout) or [`is_connect`](Self::is_connect),
+    /// plus [`Error::SidecarError`] with status 429 or 503,
+    /// [`Error::RateLimited`] and [`Error::QueueFull`].
+    pub fn is_transient(&self) -> bool {
+        self.is_timeout()
+            || self.is_connect()
+            || matches!(
+                self.root(),
+                Error::SidecarError {
+                    status: 429 | 503,
+                    ..
+                } | Error::RateLimited { .. }
+                    | Error::QueueFull
+            )
+    }
+}
+
+/// True for a success body that carries no decision: empty, `null` or `{}`.
+fn is_empty_decision(body: &[u8]) -> bool {
+    match body.trim_ascii() {
+        b"" | b"null" => true,
+        trimmed => serde_json::from_slice::<serde_json::Value>(trimmed)
+            .is_ok_and(|v| v.as_object().is_some_and(|o| o.is_empty())),
+    }
+}
+
+fn session_suffix(session_id: &Option<String>) -> String {
+    match session_id {
+        Some(id) => format!(" (session {id})"),
+        None => String::new(),
+    }
+}
+
+fn truncate_for_display(body: &str) -> String {
+    const MAX: usize = 200;
+    match body.char_indices().nth(MAX) {
+        Some((idx, _)) => format!("{}...", &body[..idx]),
+        None => body.to_string(),
+    }
+}
+
+fn request_id_suffix(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|id| format!(" (request id {id})"))
+        .unwrap_or_default()
 }
 
 // ---- Models -----------------------------------------------------------------
 
 /// Actor invoking the tool.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Actor {
     #[serde(rename = "type")]
     pub type_: String,
@@ -68,7 +526,8 @@ pub struct Actor {
 }
 
 /// Agent metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Agent {
     pub name: String,
     pub version: String,
@@ -76,8 +535,68 @@ pub struct Agent {
     pub trust_tier: String,
 }
 
+impl Agent {
+    /// Set [`trust_tier`](Self::trust_tier) from a [`TrustTier`].
+    pub fn with_trust_tier(mut self, tier: TrustTier) -> Self {
+        self.trust_tier = tier.as_str().to_string();
+        self
+    }
+
+    /// [`trust_tier`](Self::trust_tier) as a [`TrustTier`], failing with
+    /// [`Error::UnknownTrustTier`] for values this client does not recognise.
+    pub fn trust_tier_typed(&self) -> Result<TrustTier, Error> {
+        self.trust_tier.parse()
+    }
+}
+
+/// Agent trust tier, ordered by privilege:
+/// `Untrusted < Standard < Elevated < Trusted`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TrustTier {
+    Untrusted,
+    Standard,
+    Elevated,
+    Trusted,
+}
+
+impl TrustTier {
+    /// The wire value, e.g. `"elevated"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Untrusted => "untrusted",
+            Self::Standard => "standard",
+            Self::Elevated => "elevated",
+            Self::Trusted => "trusted",
+        }
+    }
+}
+
+impl std::fmt::Display for TrustTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses a wire value, case-insensitively.
+impl std::str::FromStr for TrustTier {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "untrusted" => Ok(Self::Untrusted),
+            "standard" => Ok(Self::Standard),
+            "elevated" => Ok(Self::Elevated),
+            "trusted" => Ok(Self::Trusted),
+            _ => Err(Error::UnknownTrustTier(value.to_string())),
+        }
+    }
+}
+
 /// Tool metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Tool {
     pub name: String,
     pub provider: String,
@@ -86,14 +605,27 @@ pub struct Tool {
 }
 
 /// Tool call parameters and resource references.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ToolRequest {
     pub params: HashMap<String, serde_json::Value>,
     pub resource_refs: Vec<String>,
 }
 
+/// Hashes params in key order, so equal requests hash equally regardless of
+/// map iteration order.
+impl Hash for ToolRequest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut params: Vec<_> = self.params.iter().collect();
+        params.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        params.hash(state);
+        self.resource_refs.hash(state);
+    }
+}
+
 /// Execution environment metadata.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExecutionContext {
     pub repo: String,
     pub environment: String,
@@ -102,7 +634,8 @@ pub struct ExecutionContext {
 }
 
 /// Canonical enforcement request payload.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ToolInvocation {
     pub invocation_id: String,
     pub timestamp: DateTime<Utc>,
@@ -113,26 +646,158 @@ pub struct ToolInvocation {
     pub context: ExecutionContext,
 }
 
+impl ToolInvocation {
+    /// Hex SHA-256 of the invocation's semantic content, for deduplication
+    /// and cache keys. `invocation_id` and `timestamp` are left out and
+    /// `resource_refs` are sorted, so retries of the same tool call share a
+    /// fingerprint. The hash input is the same canonical JSON as
+    /// [`canonical_decision_bytes`], so map ordering does not matter either.
+    pub fn fingerprint(&self) -> String {
+        let mut value = serde_json::to_value(self).expect("ToolInvocation serializes to JSON");
+        if let serde_json::Value::Object(map) = &mut value {
+            map.remove("invocation_id");
+            map.remove("timestamp");
+        }
+        if let Some(serde_json::Value::Array(refs)) = value.pointer_mut("/request/resource_refs") {
+            refs.sort_unstable_by(|a, b| a.as_str().cmp(&b.as_str()));
+        }
+        hex::encode(Sha256::digest(canonical::to_canonical_string(&value)))
+    }
+}
+
+/// Fields used to fill in invocations the client builds itself, e.g. for
+/// [`Client::check_capability`] and
+/// [`Client::decide_with_context_override`]. Set via
+/// [`Config::invocation_defaults`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvocationDefaults {
+    pub actor: Actor,
+    pub agent: Agent,
+    /// `repo` is overridden per call where the call takes one.
+    pub context: ExecutionContext,
+    /// `Tool.provider` of synthesized invocations.
+    pub tool_provider: String,
+    /// `Tool.risk_class` of synthesized invocations.
+    pub risk_class: String,
+}
+
 /// Budget snapshot for a single capability.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
 pub struct BudgetStatus {
     pub remaining: u64,
     pub limit: u64,
+    /// Length of the window after which a recurring budget is replenished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_seconds: Option<u64>,
+    /// When the budget is next replenished (RFC 3339). Kept as sent, since
+    /// it is covered by the evidence hash; see
+    /// [`next_reset`](Self::next_reset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resets_at: Option<String>,
+}
+
+impl BudgetStatus {
+    /// A budget with no reset window.
+    pub fn new(remaining: u64, limit: u64) -> Self {
+        Self {
+            remaining,
+            limit,
+            window_seconds: None,
+            resets_at: None,
+        }
+    }
+
+    /// `resets_at` parsed; `None` for one-off budgets and unparseable
+    /// timestamps.
+    pub fn next_reset(&self) -> Option<DateTime<Utc>> {
+        let resets_at = DateTime::parse_from_rfc3339(self.resets_at.as_deref()?).ok()?;
+        Some(resets_at.with_timezone(&Utc))
+    }
+
+    /// Units consumed so far.
+    pub fn used(&self) -> u64 {
+        self.limit.saturating_sub(self.remaining)
+    }
+
+    /// Remaining share of the limit in `0.0..=1.0`. A `limit` of 0 is treated
+    /// as unlimited and reports `1.0`.
+    pub fn fraction_remaining(&self) -> f64 {
+        if self.limit == 0 {
+            return 1.0;
+        }
+        (self.remaining as f64 / self.limit as f64).min(1.0)
+    }
+
+    /// True when a limited budget has nothing left.
+    pub fn is_exhausted(&self) -> bool {
+        self.limit > 0 && self.remaining == 0
+    }
 }
 
 /// Signed attestation evidence.
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DecisionEvidence {
     pub hash: String,
     pub signature: String,
     pub key_id: String,
 }
 
+/// An action the caller must perform as a condition of a decision, e.g.
+/// "log to SIEM" or "redact field X in the result".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Obligation {
+    pub id: String,
+    pub action: String,
+    #[serde(default)]
+    pub params: HashMap<String, serde_json::Value>,
+}
+
+/// A policy rule that contributed to a decision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MatchedPolicy {
+    pub id: String,
+    pub name: String,
+    /// "ALLOW" | "DENY" | ...
+    pub effect: String,
+}
+
+/// A [`DecisionRecord`] split by outcome, carrying only the fields that are
+/// meaningful for it. See [`DecisionRecord::outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome<'a> {
+    Allowed {
+        budgets: &'a HashMap<String, BudgetStatus>,
+        obligations: &'a [Obligation],
+    },
+    Denied {
+        reason_codes: &'a [String],
+        matched_policies: &'a [MatchedPolicy],
+    },
+    /// `approval_id` is taken from the sidecar's `approval_id` field, when
+    /// present.
+    ApprovalRequired { approval_id: Option<&'a str> },
+    /// `FAIL`, and any decision this client does not recognize (including
+    /// interim `PENDING` records), so they are never mistaken for an allow.
+    Failed { reason_codes: &'a [String] },
+}
+
 /// Enforcement decision returned by the sidecar.
-#[derive(Debug, Clone, Deserialize)]
+///
+/// Implements `Eq` but not `Hash`, since `budgets` and `extra` are hash maps;
+/// key caches on the [`ToolInvocation`] instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[non_exhaustive]
 pub struct DecisionRecord {
     pub invocation_id: String,
-    /// "ALLOW" | "DENY" | "FAIL" | "REQUIRE_APPROVAL"
+    /// "ALLOW" | "DENY" | "FAIL" | "REQUIRE_APPROVAL", or "PENDING" for interim
+    /// records from [`Client::decide_stream`]; see
+    /// [`decision_typed`](Self::decision_typed).
     pub decision: String,
     pub decision_code: String,
     pub reason_codes: Vec<String>,
@@ -142,207 +807,7335 @@ pub struct DecisionRecord {
     pub degraded: bool,
     pub entitlement_version: String,
     pub license_mode: String,
+    /// Conditions attached to the decision. The client does not act on them:
+    /// callers are responsible for fulfilling every obligation, and ignoring
+    /// one is a policy violation.
+    #[serde(default)]
+    pub obligations: Vec<Obligation>,
+    /// Policy rules the sidecar matched, in evaluation order. Empty when the
+    /// sidecar does not report them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_policies: Vec<MatchedPolicy>,
+    /// Value of the sidecar's `X-SkillGate-Request-Id` response header.
+    /// `None` for locally synthesized records.
+    #[serde(default)]
+    pub server_request_id: Option<String>,
+    /// Human-readable reason, localized per [`Config::accept_language`] when
+    /// the sidecar supports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// How long the decision may be acted on, counted from `evaluated_at`.
+    /// `None` means it does not expire.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_seconds: Option<u64>,
+    /// When the sidecar evaluated the decision (RFC 3339, sidecar clock).
+    /// Kept as sent, since it is covered by the evidence hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluated_at: Option<String>,
+    /// Top-level response fields not modelled above, preserved so newer
+    /// sidecar features are usable before this struct is updated.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-// ---- Config -----------------------------------------------------------------
+impl DecisionRecord {
+    /// Create a record with the given outcome; everything else is empty,
+    /// `degraded` is false and version/licence fields are `"unknown"`. Set the
+    /// public fields afterwards as needed, e.g. for test stubs.
+    pub fn new(
+        invocation_id: impl Into<String>,
+        decision: impl Into<String>,
+        decision_code: impl Into<String>,
+    ) -> Self {
+        Self {
+            invocation_id: invocation_id.into(),
+            decision: decision.into(),
+            decision_code: decision_code.into(),
+            reason_codes: Vec::new(),
+            policy_version: "unknown".into(),
+            budgets: HashMap::new(),
+            evidence: DecisionEvidence::default(),
+            degraded: false,
+            entitlement_version: "unknown".into(),
+            license_mode: "unknown".into(),
+            obligations: Vec::new(),
+            matched_policies: Vec::new(),
+            server_request_id: None,
+            message: None,
+            ttl_seconds: None,
+            evaluated_at: None,
+            extra: HashMap::new(),
+        }
+    }
 
-/// Client configuration.
-#[derive(Debug, Clone)]
-pub struct Config {
-    /// Sidecar base URL. Default: `http://localhost:8910`.
-    pub sidecar_url: String,
-    /// Per-request timeout. Default: 50 ms.
-    pub timeout: Duration,
-    /// When true, return a degraded ALLOW on sidecar failure instead of an error.
-    pub fail_open: bool,
-    /// Session License Token for Authorization header.
-    pub slt: Option<String>,
-}
+    /// Obligations the caller must fulfil before acting on this decision.
+    pub fn obligations(&self) -> &[Obligation] {
+        &self.obligations
+    }
 
-impl Config {
-    /// Build configuration from environment variables with production-safe defaults.
-    pub fn from_env() -> Self {
-        let sidecar_url = std::env::var("SKILLGATE_SIDECAR_URL")
-            .unwrap_or_else(|_| "http://localhost:8910".into());
-        let slt = std::env::var("SKILLGATE_SLT").ok();
-        Self {
-            sidecar_url,
-            timeout: Duration::from_millis(50),
-            fail_open: false,
-            slt,
+    /// The decision as an [`Outcome`], for exhaustive handling.
+    pub fn outcome(&self) -> Outcome<'_> {
+        match self.decision.as_str() {
+            "ALLOW" => Outcome::Allowed {
+                budgets: &self.budgets,
+                obligations: &self.obligations,
+            },
+            "DENY" => Outcome::Denied {
+                reason_codes: &self.reason_codes,
+                matched_policies: &self.matched_policies,
+            },
+            "REQUIRE_APPROVAL" => Outcome::ApprovalRequired {
+                approval_id: self.extra("approval_id").and_then(|v| v.as_str()),
+            },
+            _ => Outcome::Failed {
+                reason_codes: &self.reason_codes,
+            },
         }
     }
-}
 
-// ---- Client -----------------------------------------------------------------
+    /// End of the validity window, `evaluated_at + ttl_seconds`. `None`
+    /// when either is missing or `evaluated_at` is not valid RFC 3339.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        let ttl = chrono::Duration::seconds(i64::try_from(self.ttl_seconds?).ok()?);
+        let evaluated_at = DateTime::parse_from_rfc3339(self.evaluated_at.as_deref()?).ok()?;
+        evaluated_at.with_timezone(&Utc).checked_add_signed(ttl)
+    }
 
-/// Async HTTP client for the SkillGate runtime sidecar.
-pub struct Client {
-    cfg: Config,
-    http: HttpClient,
-}
+    /// Whether the decision must no longer be acted on at `now`. Pass
+    /// [`Client::now`] so the comparison uses the sidecar's clock. A TTL
+    /// without a usable `evaluated_at` cannot be checked and counts as
+    /// expired.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        match self.expires_at() {
+            Some(expires_at) => now >= expires_at,
+            None => self.ttl_seconds.is_some(),
+        }
+    }
 
-impl Client {
-    /// Create a new client with the given config.
-    pub fn new(cfg: Config) -> Self {
-        let http = HttpClient::builder()
-            .timeout(cfg.timeout)
-            .build()
-            .expect("failed to build HTTP client");
-        Self { cfg, http }
+    /// Matched rules with a DENY effect, for showing why a call was blocked.
+    pub fn denied_by(&self) -> Vec<&MatchedPolicy> {
+        self.matched_policies
+            .iter()
+            .filter(|p| p.effect.eq_ignore_ascii_case("DENY"))
+            .collect()
     }
 
-    fn auth_header(&self) -> Option<String> {
-        self.cfg.slt.as_ref().map(|t| format!("Bearer {t}"))
+    /// Returns true if an obligation with the given id is attached.
+    pub fn has_obligation(&self, id: &str) -> bool {
+        self.obligations.iter().any(|o| o.id == id)
     }
 
-    fn degraded_allow(invocation_id: &str) -> DecisionRecord {
-        DecisionRecord {
-            invocation_id: invocation_id.to_string(),
-            decision: "ALLOW".into(),
-            decision_code: "SG_ALLOW_DEGRADED_AUDIT_ASYNC".into(),
-            reason_codes: vec!["enforcer_unavailable_fail_open".into()],
-            policy_version: "unknown".into(),
-            budgets: HashMap::new(),
-            evidence: DecisionEvidence::default(),
-            degraded: true,
-            entitlement_version: "unknown".into(),
-            license_mode: "offline".into(),
-        }
+    /// [`license_mode`](Self::license_mode) as a [`LicenseMode`].
+    pub fn license_mode_typed(&self) -> LicenseMode {
+        LicenseMode::parse(&self.license_mode)
     }
 
-    /// Send a `ToolInvocation` to the sidecar for an enforcement decision.
-    ///
-    /// Returns [`Error::EnforcerUnavailable`] if the sidecar is unreachable and
-    /// `fail_open` is `false`.
-    pub async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
-        let body = serde_json::json!({
-            "invocation_id": invocation.invocation_id,
-            "tool_invocation": invocation,
-        });
+    /// True when the sidecar is running on an offline or grace-period license.
+    pub fn is_degraded_license(&self) -> bool {
+        self.license_mode_typed().is_degraded()
+    }
 
-        let mut req = self
-            .http
-            .post(format!("{}/v1/decide", self.cfg.sidecar_url))
-            .json(&body);
+    /// Look up an unmodelled response field by name.
+    pub fn extra(&self, key: &str) -> Option<&serde_json::Value> {
+        self.extra.get(key)
+    }
 
-        if let Some(auth) = self.auth_header() {
-            req = req.header("Authorization", auth);
-        }
+    /// Returns true if `code` is among the record's `reason_codes`.
+    pub fn has_reason(&self, code: &str) -> bool {
+        self.reason_codes.iter().any(|c| c == code)
+    }
 
-        match req.send().await {
-            Err(e) => {
-                if self.cfg.fail_open {
-                    return Ok(Self::degraded_allow(&body["invocation_id"].as_str().unwrap_or("")));
-                }
-                Err(Error::EnforcerUnavailable(e.to_string()))
-            }
-            Ok(resp) => {
-                let status = resp.status();
-                if !status.is_success() {
-                    let text = resp.text().await.unwrap_or_default();
-                    return Err(Error::SidecarError(status.as_u16(), text));
-                }
-                let record: DecisionRecord = resp.json().await?;
-                Ok(record)
-            }
-        }
+    /// Capabilities whose remaining budget fraction is below `threshold`,
+    /// sorted by name.
+    pub fn budgets_below(&self, threshold: f64) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .budgets
+            .iter()
+            .filter(|(_, b)| b.fraction_remaining() < threshold)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
     }
 
-    /// Register or update a tool AI-BOM in the sidecar registry.
-    /// Best-effort — returns `false` on any connectivity failure.
-    pub async fn register_tool(
-        &self,
-        tool_name: &str,
-        metadata: &HashMap<String, serde_json::Value>,
-    ) -> bool {
-        let mut req = self
-            .http
-            .put(format!("{}/v1/registry/{tool_name}", self.cfg.sidecar_url))
-            .json(metadata);
+    /// [`decision_code`](Self::decision_code) as a [`DecisionCode`].
+    pub fn code(&self) -> DecisionCode {
+        DecisionCode(self.decision_code.clone())
+    }
 
-        if let Some(auth) = self.auth_header() {
-            req = req.header("Authorization", auth);
-        }
+    /// [`decision`](Self::decision) as a [`Decision`].
+    pub fn decision_typed(&self) -> Decision {
+        Decision::parse(&self.decision)
+    }
 
-        req.send()
-            .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+    /// True only for an `ALLOW` decision.
+    pub fn is_allowed(&self) -> bool {
+        self.decision == "ALLOW"
     }
 
-    /// Returns `Ok(())` if the sidecar is reachable and healthy.
-    pub async fn health(&self) -> Result<(), Error> {
-        let resp = self
-            .http
-            .get(format!("{}/v1/health", self.cfg.sidecar_url))
-            .send()
-            .await?;
+    /// True when the tool call must not go ahead now; see
+    /// [`Decision::is_blocking`].
+    pub fn is_blocking(&self) -> bool {
+        !self.is_allowed()
+    }
 
-        if resp.status() != StatusCode::OK {
-            return Err(Error::SidecarError(resp.status().as_u16(), String::new()));
-        }
-        Ok(())
+    pub fn requires_approval(&self) -> bool {
+        self.decision_typed().requires_approval()
     }
 }
 
-// ---- Tests ------------------------------------------------------------------
+/// A [`DecisionRecord::decision`] value. Serializes as the wire string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+#[non_exhaustive]
+pub enum Decision {
+    Allow,
+    Deny,
+    Fail,
+    RequireApproval,
+    /// Interim record from [`Client::decide_stream`].
+    Pending,
+    /// Any value this client does not recognise, verbatim.
+    Unknown(String),
+}
+
+impl Decision {
+    /// Parse a wire value. Matching is exact, as the sidecar only sends
+    /// upper-case decisions.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "ALLOW" => Self::Allow,
+            "DENY" => Self::Deny,
+            "FAIL" => Self::Fail,
+            "REQUIRE_APPROVAL" => Self::RequireApproval,
+            "PENDING" => Self::Pending,
+            _ => Self::Unknown(value.to_string()),
+        }
+    }
+
+    /// The wire value, e.g. `"REQUIRE_APPROVAL"`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Allow => "ALLOW",
+            Self::Deny => "DENY",
+            Self::Fail => "FAIL",
+            Self::RequireApproval => "REQUIRE_APPROVAL",
+            Self::Pending => "PENDING",
+            Self::Unknown(value) => value,
+        }
+    }
+
+    /// True only for [`Decision::Allow`].
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allow)
+    }
+
+    /// True when the tool call must not go ahead now: every decision but
+    /// [`Decision::Allow`], unknown ones included.
+    pub fn is_blocking(&self) -> bool {
+        !self.is_allowed()
+    }
+
+    pub fn requires_approval(&self) -> bool {
+        matches!(self, Self::RequireApproval)
+    }
+}
+
+impl From<String> for Decision {
+    fn from(value: String) -> Self {
+        match Self::parse(&value) {
+            Self::Unknown(_) => Self::Unknown(value),
+            known => known,
+        }
+    }
+}
+
+impl From<Decision> for String {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Unknown(value) => value,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Decision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A [`DecisionRecord::decision_code`], classified by its prefix
+/// (`SG_ALLOW_*`, `SG_DENY_*`, ...) so that codes added server-side later
+/// can still be handled by category.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DecisionCode(String);
+
+/// Broad class of a [`DecisionCode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecisionCategory {
+    Allow,
+    Deny,
+    Fail,
+    RequireApproval,
+    /// An interim code such as `SG_PENDING_EXTERNAL_CHECK`.
+    Pending,
+    /// A code without a recognised prefix.
+    Other,
+}
+
+impl DecisionCode {
+    pub fn new(code: impl Into<String>) -> Self {
+        Self(code.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn category(&self) -> DecisionCategory {
+        const PREFIXES: [(&str, DecisionCategory); 5] = [
+            ("SG_ALLOW", DecisionCategory::Allow),
+            ("SG_DENY", DecisionCategory::Deny),
+            ("SG_FAIL", DecisionCategory::Fail),
+            ("SG_REQUIRE_APPROVAL", DecisionCategory::RequireApproval),
+            ("SG_PENDING", DecisionCategory::Pending),
+        ];
+        PREFIXES
+            .into_iter()
+            .find(|(prefix, _)| {
+                self.0
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('_'))
+            })
+            .map_or(DecisionCategory::Other, |(_, category)| category)
+    }
+
+    /// True for codes marking a locally degraded decision, e.g.
+    /// `SG_ALLOW_DEGRADED_AUDIT_ASYNC`.
+    pub fn is_degraded(&self) -> bool {
+        self.0.split('_').any(|part| part == "DEGRADED")
+    }
+}
+
+impl std::fmt::Display for DecisionCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Never fails: unknown codes are kept verbatim and classified as
+/// [`DecisionCategory::Other`].
+impl std::str::FromStr for DecisionCode {
+    type Err = std::convert::Infallible;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(code))
+    }
+}
+
+/// Licensing mode reported in [`DecisionRecord::license_mode`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LicenseMode {
+    Online,
+    Offline,
+    Grace,
+    /// Any value this client does not recognise, verbatim.
+    Unknown(String),
+}
+
+impl LicenseMode {
+    /// Parse a wire value, case-insensitively.
+    pub fn parse(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "online" => Self::Online,
+            "offline" => Self::Offline,
+            "grace" => Self::Grace,
+            _ => Self::Unknown(value.to_string()),
+        }
+    }
+
+    /// True for modes with reduced functionality (`offline`, `grace`).
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, Self::Offline | Self::Grace)
+    }
+}
+
+/// Result of [`Client::check_budget`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BudgetCheck {
+    /// Whether the budget covers the cost right now.
+    pub allowed: bool,
+    /// The capability's budget before the cost; `None` for capabilities
+    /// without one.
+    #[serde(default)]
+    pub budget: Option<BudgetStatus>,
+}
+
+/// Human-readable explanation of a past decision.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DecisionExplanation {
+    pub summary: String,
+    #[serde(default)]
+    pub matched_rules: Vec<String>,
+    #[serde(default)]
+    pub suggested_remediation: Option<String>,
+}
+
+/// A tool in the sidecar registry, from [`Client::list_tools`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToolSummary {
+    pub name: String,
+    /// The AI-BOM fields registered for the tool.
+    #[serde(flatten)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Result of [`Client::list_tools`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryListing {
+    /// The registry has not changed since the previous listing.
+    Unchanged,
+    Changed(Vec<ToolSummary>),
+}
+
+/// Activity over an agent session's lifetime, from
+/// [`Client::session_summary`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SessionSummary {
+    pub session_id: String,
+    #[serde(default)]
+    pub allowed: u64,
+    #[serde(default)]
+    pub denied: u64,
+    #[serde(default)]
+    pub approval_required: u64,
+    /// Budget units consumed by the session, by capability.
+    #[serde(default)]
+    pub budget_usage: HashMap<String, u64>,
+}
+
+impl SessionSummary {
+    /// Decisions of every kind in the session.
+    pub fn total(&self) -> u64 {
+        self.allowed + self.denied + self.approval_required
+    }
+}
+
+/// Outcome of [`Client::replay`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayResult {
+    /// The decision the sidecar makes now.
+    pub replayed: DecisionRecord,
+    /// Fields where `replayed` differs from the recorded decision; empty
+    /// when the decision reproduces.
+    pub differences: Vec<ReplayDifference>,
+}
+
+impl ReplayResult {
+    /// True when the replayed decision matches the recorded one.
+    pub fn is_reproduced(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+/// A field that differs between a recorded decision and its replay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayDifference {
+    Decision {
+        expected: String,
+        actual: String,
+    },
+    DecisionCode {
+        expected: String,
+        actual: String,
+    },
+    /// Reason codes, compared without regard to order.
+    ReasonCodes {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    PolicyVersion {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl ReplayDifference {
+    fn between(expected: &DecisionRecord, actual: &DecisionRecord) -> Vec<Self> {
+        let mut differences = Vec::new();
+        if expected.decision != actual.decision {
+            differences.push(Self::Decision {
+                expected: expected.decision.clone(),
+                actual: actual.decision.clone(),
+            });
+        }
+        if expected.decision_code != actual.decision_code {
+            differences.push(Self::DecisionCode {
+                expected: expected.decision_code.clone(),
+                actual: actual.decision_code.clone(),
+            });
+        }
+        let sorted = |codes: &[String]| {
+            let mut codes = codes.to_vec();
+            codes.sort_unstable();
+            codes
+        };
+        if sorted(&expected.reason_codes) != sorted(&actual.reason_codes) {
+            differences.push(Self::ReasonCodes {
+                expected: expected.reason_codes.clone(),
+                actual: actual.reason_codes.clone(),
+            });
+        }
+        if expected.policy_version != actual.policy_version {
+            differences.push(Self::PolicyVersion {
+                expected: expected.policy_version.clone(),
+                actual: actual.policy_version.clone(),
+            });
+        }
+        differences
+    }
+}
+
+/// A [`Config::shadow_url`] decision that differs from the authoritative
+/// one, passed to [`Config::on_shadow_divergence`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowDivergence {
+    pub authoritative: DecisionRecord,
+    pub shadow: DecisionRecord,
+    /// How `shadow` (`actual`) differs from `authoritative` (`expected`);
+    /// never empty and never [`ReplayDifference::PolicyVersion`].
+    pub differences: Vec<ReplayDifference>,
+}
+
+/// Sidecar health as published by [`Client::health_watch`].
+#[derive(Debug, PartialEq)]
+pub enum HealthState {
+    /// No probe has completed yet.
+    Unknown,
+    Healthy,
+    /// The last probe failed, for the first reason seen since the sidecar
+    /// became unhealthy.
+    Unhealthy(Error),
+}
+
+impl HealthState {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
+
+/// One event from [`Client::subscribe_events`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    /// Sidecar-assigned event id; the resume cursor after a reconnect.
+    pub id: Option<String>,
+    /// Event type, e.g. `"decision"`; `"message"` when the sidecar sends
+    /// none.
+    pub kind: String,
+    /// The event payload. Data that is not JSON is kept as a string.
+    pub data: serde_json::Value,
+}
+
+impl AuditEvent {
+    /// The payload of a `decision` event.
+    pub fn decision(&self) -> Option<DecisionRecord> {
+        if self.kind != "decision" {
+            return None;
+        }
+        serde_json::from_value(self.data.clone()).ok()
+    }
+}
+
+// ---- Config -----------------------------------------------------------------
+
+/// Client configuration.
+///
+/// Construct it with [`Config::from_env`] or [`Config::builder`] and adjust
+/// fields by assignment; struct literals are not available outside this
+/// crate so that new options can be added without breaking callers.
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct Config {
+    /// Sidecar base URL, or `unix:///path/to/sidecar.sock` to reach a
+    /// sidecar listening on a Unix domain socket (Unix only). A socket
+    /// cannot be combined with `sidecar_urls`, `shadow_url` or the proxy
+    /// options. Default: `http://localhost:8910`.
+    pub sidecar_url: String,
+    /// Per-request timeout. Default: 50 ms.
+    pub timeout: Duration,
+    /// When true, return a degraded ALLOW on sidecar failure instead of an error.
+    pub fail_open: bool,
+    /// Picks the [`FailMode`] per invocation, e.g. failing closed for
+    /// high-risk tools while `fail_open` covers the rest. Overrides
+    /// `fail_open` for every invocation it is called on. Default: `None`.
+    pub fail_mode_resolver: Option<FailModeResolver>,
+    /// Session License Token for Authorization header.
+    pub slt: Option<String>,
+    /// When `slt` is a JWT with an `exp` claim, warn this long before it
+    /// expires: `decide` calls log a `warn` event and call
+    /// `on_slt_expiring` once per client, and fail with
+    /// [`Error::SltExpired`] without contacting the sidecar once it has
+    /// expired. Opaque tokens are not checked. Default: `None` (no check).
+    pub slt_expiry_warning: Option<Duration>,
+    /// Called with the token's expiry the first time `slt` is found within
+    /// `slt_expiry_warning` of it, e.g. to start a refresh. Default: `None`.
+    pub on_slt_expiring: Option<SltExpiringHook>,
+    /// `ToolRequest.params` keys whose values are replaced with `"***"` before
+    /// the invocation is sent. Matching is case-insensitive. Default: empty.
+    pub redact_param_keys: Vec<String>,
+    /// Log `decide` request and response bodies at `debug` level; see
+    /// [`BodyLogMode`]. Default: [`BodyLogMode::Off`].
+    pub log_bodies: BodyLogMode,
+    /// Policy bundle evaluated locally when the sidecar is unreachable. Takes
+    /// precedence over `fail_open` for invocations it covers. Requires
+    /// `offline_bundle_keys`; see [`OfflineBundle`] for the signature format.
+    pub offline_bundle: Option<PathBuf>,
+    /// Keys the `offline_bundle` signature is checked against. Checked once,
+    /// when the client is built, so the signing key must already be in the
+    /// keyring. Default: `None`.
+    pub offline_bundle_keys: Option<Arc<Keyring>>,
+    /// Upper bound for a whole [`Client::decide_stream`] exchange. Default: 30 s.
+    pub stream_timeout: Duration,
+    /// Additional sidecar base URLs (e.g. replicas) probed by
+    /// [`Client::health_all`] alongside `sidecar_url`. Requests are still sent
+    /// to `sidecar_url` only. Default: empty.
+    pub sidecar_urls: Vec<String>,
+    /// Path prefix inserted between the base URL and the `/v1/...` endpoint
+    /// paths, for sidecars mounted under a reverse-proxy path such as
+    /// `/skillgate`. Default: empty.
+    pub path_prefix: String,
+    /// Reject sidecar decisions whose `policy_version` is older than this
+    /// (compared component-wise as dotted numbers, e.g. `1.4.0`). Decisions
+    /// with an unparseable version are rejected too. Default: `None`.
+    pub min_policy_version: Option<String>,
+    /// Verify the evidence of every sidecar decision against these keys
+    /// (e.g. [`Keyring::from_sidecar`]) with
+    /// [`DecisionRecord::verify_with`], failing the call with
+    /// [`Error::InvalidEvidence`] or [`Error::UnknownKeyId`] rather than
+    /// returning an unverified decision. Synthetic decisions (fail-open,
+    /// offline bundle, queue overflow) carry no evidence and are not
+    /// checked. Default: `None`.
+    pub verify_evidence: Option<Arc<Keyring>>,
+    /// Static headers added to every request, e.g. a tenant id or API gateway
+    /// key. May override the default `skillgate-rust/{version}` `User-Agent`;
+    /// an `Authorization` entry is rejected so the SLT stays authoritative.
+    pub default_headers: HashMap<String, String>,
+    /// Maximum number of `decide` calls in flight at once; further calls wait
+    /// for a free slot. Default: `None` (unbounded).
+    pub max_concurrent: Option<usize>,
+    /// Sign `decide` requests for replay protection. Requires
+    /// `client_signing_key`. Default: `false`.
+    ///
+    /// Each signed request carries a random [`NONCE_HEADER`], a
+    /// [`TIMESTAMP_HEADER`] in Unix seconds, and a [`SIGNATURE_HEADER`] holding
+    /// the lowercase hex HMAC-SHA256, keyed with `client_signing_key`, of
+    /// `"{nonce}.{timestamp}.{body}"`. The body is sent in canonical JSON form
+    /// (sorted keys, no whitespace, non-ASCII escaped as `\uXXXX`, matching
+    /// Python's `json.dumps(sort_keys=True, separators=(",", ":"))`), so the
+    /// sidecar can verify the received bytes directly.
+    pub sign_requests: bool,
+    /// Shared secret used when `sign_requests` is enabled.
+    pub client_signing_key: Option<String>,
+    /// Proxy URL for all sidecar traffic, replacing any system proxy.
+    ///
+    /// When neither `proxy` nor `no_proxy` is set, the system proxy
+    /// environment (`HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`, `NO_PROXY`, and
+    /// lowercase forms) is honored. Default: `None`.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts, domains and CIDRs that bypass the proxy, in
+    /// `NO_PROXY` syntax (`*` bypasses all). Applies to `proxy` when set,
+    /// otherwise to the `HTTP_PROXY`/`HTTPS_PROXY` environment proxies, so
+    /// e.g. `localhost,127.0.0.1` keeps a local sidecar direct even when a
+    /// global `HTTPS_PROXY` is set. Default: `None`.
+    pub no_proxy: Option<String>,
+    /// Maximum number of `resource_refs` sent per invocation, after
+    /// de-duplication. Default: `None` (unbounded).
+    pub max_resource_refs: Option<usize>,
+    /// What to do when `max_resource_refs` is exceeded. Default: truncate.
+    pub resource_refs_overflow: RefsOverflow,
+    /// Size and count limits on `ToolRequest.params`, checked after
+    /// redaction; a violation fails the call without contacting the
+    /// sidecar. Default: `None`.
+    pub param_limits: Option<ParamLimits>,
+    /// Send `actor.workspace_id` as [`WORKSPACE_HEADER`] on decide requests
+    /// so sharding proxies can route without parsing the body. The header is
+    /// omitted when the workspace id is empty. Default: `true`.
+    pub workspace_header: bool,
+    /// Shape of the `decide` request body. Default: [`Envelope::Legacy`].
+    pub request_envelope: Envelope,
+    /// Encoding of the invocation timestamp in `decide` bodies. Default:
+    /// [`TimestampFormat::Rfc3339`].
+    pub timestamp_format: TimestampFormat,
+    /// Encoding of `decide` request and response bodies; see [`WireFormat`].
+    /// Default: [`WireFormat::Json`].
+    pub wire_format: WireFormat,
+    /// Append every decision returned by the client (including degraded and
+    /// synthesized ones) to this JSONL file. Writes happen on a background
+    /// thread; see [`Client::flush_audit_log`]. Default: `None`.
+    pub audit_log_path: Option<PathBuf>,
+    /// Measure the sidecar's clock on every [`Client::health`] /
+    /// [`Client::warmup`] and shift `ToolInvocation.timestamp` by the offset in
+    /// `decide`, so hosts with drifting clocks are not rejected for skew.
+    /// Default: `false`.
+    pub sync_clock: bool,
+    /// Queue fail-open degraded ALLOWs and deliver them to the sidecar's
+    /// `/v1/audit` endpoint once it is reachable again. Default: `None`.
+    pub degraded_audit: Option<DegradedAuditConfig>,
+    /// Maximum age of the last-known-good budgets (per workspace) copied into
+    /// fail-open degraded records, which are then marked with
+    /// `extra["budgets_stale"] = true`. `None` leaves degraded budgets empty.
+    /// Default: 5 minutes.
+    pub budget_snapshot_ttl: Option<Duration>,
+    /// Mirror the budgets reported by the sidecar in a [`BudgetTracker`]
+    /// ([`Client::budget_tracker`]), warning and calling `on_budget_low`
+    /// when a capability's remaining share of its limit falls below this
+    /// (e.g. `0.1`). `0.0` tracks without alerting. Default: `None` (no
+    /// tracking).
+    pub budget_low_water: Option<f64>,
+    /// Called with the capability and its budget when the budget falls
+    /// below `budget_low_water`. Default: `None`.
+    pub on_budget_low: Option<BudgetLowHook>,
+    /// Serialized size of `ToolRequest.params`, in bytes, above which the
+    /// `decide` body is streamed to the sidecar (chunked transfer encoding)
+    /// instead of being built in memory first. Ignored when
+    /// [`sign_requests`](Self::sign_requests) is set, since the signature
+    /// covers the whole body. `None` always buffers. Default: 1 MiB.
+    pub stream_body_threshold: Option<usize>,
+    /// Largest response body, in bytes, read from the sidecar before giving
+    /// up with [`Error::ResponseTooLarge`]. For
+    /// [`Client::decide_stream`] the limit applies per event. Error response
+    /// bodies are cut off at the limit instead, so the status still comes
+    /// through in [`Error::SidecarError`]. `None` is unbounded. Default: 8 MiB.
+    pub max_response_bytes: Option<usize>,
+    /// `Accept-Language` sent with `decide` and `explain`, asking the
+    /// sidecar for localized [`DecisionRecord::message`] text. Default:
+    /// `None`.
+    pub accept_language: Option<String>,
+    /// Fail `health` and `decide` with [`Error::ApiVersionUnsupported`] when
+    /// the sidecar reports an API version this client does not support,
+    /// instead of logging a one-time warning. Default: `false`.
+    pub strict_version: bool,
+    /// Called whenever a sidecar failure is resolved locally: offline
+    /// evaluation, fail-open ALLOW, fail-closed error, or the static DENY of
+    /// [`Client::decide_or_deny`]. Runs synchronously on the calling task
+    /// before the result is returned, so it must be cheap; hand anything
+    /// slow (network calls, locks) off to another task. Default: `None`.
+    pub on_degrade: Option<DegradeHook>,
+    /// Actor, agent and context for invocations the client builds itself.
+    /// Default: all fields empty.
+    pub invocation_defaults: InvocationDefaults,
+    /// After a request to the sidecar fails at the transport level
+    /// (connect error, timeout), resolve `decide` calls in fail mode without
+    /// contacting it for this long. Any successful exchange, including
+    /// [`Client::health`], clears the state; the first call after the window
+    /// probes the sidecar again. Error statuses do not count as failures.
+    /// Default: `None` (always send).
+    pub health_gate: Option<Duration>,
+    /// Open a circuit after repeated transport failures, so `decide` calls
+    /// fail fast while the sidecar is down; see [`CircuitBreakerConfig`].
+    /// Default: `None`.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Serve repeated `decide` calls from an in-memory cache; see
+    /// [`CacheConfig`]. Default: `None`.
+    pub cache: Option<CacheConfig>,
+    /// Token bucket capping this client's `decide` rate. Clients built from
+    /// clones of the same config share the bucket. Default: `None`.
+    pub rate_limit: Option<RateLimit>,
+    /// Retries of transient failures in `decide` calls, tool registration
+    /// and health checks. Each attempt gets the full `timeout`, so a call
+    /// can take up to `max_attempts` timeouts plus the delays before it
+    /// fails over to `fail_open`. Streamed bodies are sent once. Default:
+    /// `None` (no retries).
+    pub retry: Option<RetryPolicy>,
+    /// Called with the invocation just before it is serialized in `decide`
+    /// (and `decide_ref` / `decide_prepared`), e.g. to stamp a deployment id
+    /// into `context` or params. It runs on the client's own copy, after
+    /// clock sync and after redaction and resource-ref limits have been
+    /// applied, so it sees the final invocation; values it adds are sent as
+    /// set and are not redacted. Default: `None`.
+    pub request_interceptor: Option<RequestInterceptor>,
+    /// Number of recent `decide` calls summarized by
+    /// [`Client::latency_stats`]; `0` disables tracking. Default: 1024.
+    pub latency_window: usize,
+    /// Bounded queue for calls waiting on [`Config::max_concurrent`], with
+    /// its overflow policy; requires `max_concurrent`, and a capacity of at
+    /// least 1 with [`Overflow::Block`]. `None` lets calls wait without
+    /// bound. Default: `None`.
+    pub queue: Option<QueueConfig>,
+    /// A second sidecar, e.g. one running a candidate policy, sent a copy
+    /// of every [`Client::decide`] request. Its answers never affect the
+    /// returned decision: the copy is sent concurrently on a background
+    /// task, bounded by `timeout`, and compared with the authoritative
+    /// decision once both are in. Streamed bodies are not shadowed.
+    /// Default: `None`.
+    pub shadow_url: Option<String>,
+    /// Called on a background task when the [`Config::shadow_url`] sidecar
+    /// decides differently from the authoritative one. Differing policy
+    /// versions alone do not count. Without a hook, divergences are logged
+    /// at `info` level. Default: `None`.
+    pub on_shadow_divergence: Option<ShadowDivergenceHook>,
+}
+
+/// Callback type of [`Config::request_interceptor`].
+pub type RequestInterceptor = Arc<dyn Fn(&mut ToolInvocation) + Send + Sync>;
+
+/// Callback type of [`Config::on_degrade`].
+pub type DegradeHook = Arc<dyn Fn(&ToolInvocation, &Error) + Send + Sync>;
+
+/// Callback type of [`Config::on_shadow_divergence`].
+pub type ShadowDivergenceHook = Arc<dyn Fn(&ShadowDivergence) + Send + Sync>;
+
+/// Callback type of [`Config::on_slt_expiring`]; receives the token's
+/// expiry.
+pub type SltExpiringHook = Arc<dyn Fn(DateTime<Utc>) + Send + Sync>;
+
+/// Callback type of [`Config::on_budget_low`]; receives the capability and
+/// its budget.
+pub type BudgetLowHook = Arc<dyn Fn(&str, &BudgetStatus) + Send + Sync>;
+
+/// Callback type of [`Config::fail_mode_resolver`].
+pub type FailModeResolver = Arc<dyn Fn(&ToolInvocation) -> FailMode + Send + Sync>;
+
+/// How a `decide` call resolves when the sidecar cannot be reached and no
+/// offline bundle rule covers it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailMode {
+    /// Return a degraded ALLOW.
+    Open,
+    /// Return [`Error::EnforcerUnavailable`].
+    Closed,
+}
+
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("sidecar_url", &self.sidecar_url)
+            .field("timeout", &self.timeout)
+            .field("fail_open", &self.fail_open)
+            .field(
+                "fail_mode_resolver",
+                &self.fail_mode_resolver.as_ref().map(|_| "<fn>"),
+            )
+            .field("slt", &self.slt)
+            .field("slt_expiry_warning", &self.slt_expiry_warning)
+            .field(
+                "on_slt_expiring",
+                &self.on_slt_expiring.as_ref().map(|_| "<fn>"),
+            )
+            .field("redact_param_keys", &self.redact_param_keys)
+            .field("log_bodies", &self.log_bodies)
+            .field("offline_bundle", &self.offline_bundle)
+            .field("offline_bundle_keys", &self.offline_bundle_keys)
+            .field("stream_timeout", &self.stream_timeout)
+            .field("sidecar_urls", &self.sidecar_urls)
+            .field("path_prefix", &self.path_prefix)
+            .field("min_policy_version", &self.min_policy_version)
+            .field("verify_evidence", &self.verify_evidence)
+            .field("default_headers", &self.default_headers)
+            .field("max_concurrent", &self.max_concurrent)
+            .field("sign_requests", &self.sign_requests)
+            .field("client_signing_key", &self.client_signing_key)
+            .field("proxy", &self.proxy)
+            .field("no_proxy", &self.no_proxy)
+            .field("max_resource_refs", &self.max_resource_refs)
+            .field("resource_refs_overflow", &self.resource_refs_overflow)
+            .field("param_limits", &self.param_limits)
+            .field("workspace_header", &self.workspace_header)
+            .field("request_envelope", &self.request_envelope)
+            .field("timestamp_format", &self.timestamp_format)
+            .field("wire_format", &self.wire_format)
+            .field("audit_log_path", &self.audit_log_path)
+            .field("sync_clock", &self.sync_clock)
+            .field("degraded_audit", &self.degraded_audit)
+            .field("budget_snapshot_ttl", &self.budget_snapshot_ttl)
+            .field("budget_low_water", &self.budget_low_water)
+            .field(
+                "on_budget_low",
+                &self.on_budget_low.as_ref().map(|_| "<fn>"),
+            )
+            .field("stream_body_threshold", &self.stream_body_threshold)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("accept_language", &self.accept_language)
+            .field("strict_version", &self.strict_version)
+            .field("on_degrade", &self.on_degrade.as_ref().map(|_| "<fn>"))
+            .field("invocation_defaults", &self.invocation_defaults)
+            .field("health_gate", &self.health_gate)
+            .field("circuit_breaker", &self.circuit_breaker)
+            .field("cache", &self.cache)
+            .field("rate_limit", &self.rate_limit)
+            .field("retry", &self.retry)
+            .field(
+                "request_interceptor",
+                &self.request_interceptor.as_ref().map(|_| "<fn>"),
+            )
+            .field("latency_window", &self.latency_window)
+            .field("queue", &self.queue)
+            .field("shadow_url", &self.shadow_url)
+            .field(
+                "on_shadow_divergence",
+                &self.on_shadow_divergence.as_ref().map(|_| "<fn>"),
+            )
+            .finish()
+    }
+}
+
+/// Wire shape of the `decide` request body.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Envelope {
+    /// `{"invocation_id": ..., "tool_invocation": {...}}`, as expected by
+    /// current sidecars.
+    #[default]
+    Legacy,
+    /// The `ToolInvocation` itself at the top level, for newer sidecars.
+    Flat,
+}
+
+/// Wire representation of [`ToolInvocation::timestamp`] in `decide` bodies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 string, e.g. `"2026-01-01T00:00:00.123Z"`.
+    #[default]
+    Rfc3339,
+    /// Integer milliseconds since the Unix epoch, for older sidecars.
+    EpochMillis,
+    /// Integer seconds since the Unix epoch.
+    EpochSeconds,
+}
+
+/// Encoding of `decide` bodies, sent as `Content-Type` and `Accept`.
+///
+/// Applies to [`Client::decide`], [`Client::decide_ref`] and
+/// [`Client::decide_prepared`]; other endpoints always use JSON, as do
+/// signed requests ([`Config::sign_requests`]). Responses are decoded by
+/// their `Content-Type`. A sidecar that answers a binary body with 415 gets
+/// the request again as JSON, and JSON from then on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    #[default]
+    Json,
+    /// MessagePack (`application/msgpack`). Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    /// CBOR (`application/cbor`). Requires the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+/// Behaviour when an invocation carries more than
+/// [`Config::max_resource_refs`] refs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefsOverflow {
+    /// Keep the first `max_resource_refs` refs and report the number dropped
+    /// to the sidecar as `resource_refs_truncated` in the request body.
+    #[default]
+    Truncate,
+    /// Fail with [`Error::TooManyResourceRefs`] without contacting the sidecar.
+    Error,
+}
+
+/// Settings for [`Config::param_limits`]. Sizes are serialized JSON bytes;
+/// `None` leaves a dimension unchecked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParamLimits {
+    /// Largest single param value; see [`Error::ParamTooLarge`].
+    pub max_param_bytes: Option<usize>,
+    /// Most params per request; see [`Error::TooManyParams`].
+    pub max_param_count: Option<usize>,
+    /// Largest `params` object as a whole; see [`Error::ParamsTooLarge`].
+    pub max_total_bytes: Option<usize>,
+}
+
+/// The documented defaults: `http://localhost:8910`, 50 ms timeout,
+/// fail-closed, no SLT. Unlike [`Config::from_env`], never reads the
+/// environment.
+///
+/// `Config` is `#[non_exhaustive]`, so outside this crate start from
+/// `Config::default()` and assign fields rather than using
+/// `..Default::default()` struct update syntax.
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sidecar_url: "http://localhost:8910".into(),
+            timeout: Duration::from_millis(50),
+            fail_open: false,
+            fail_mode_resolver: None,
+            slt: None,
+            slt_expiry_warning: None,
+            on_slt_expiring: None,
+            redact_param_keys: Vec::new(),
+            log_bodies: BodyLogMode::Off,
+            offline_bundle: None,
+            offline_bundle_keys: None,
+            stream_timeout: Duration::from_secs(30),
+            sidecar_urls: Vec::new(),
+            path_prefix: String::new(),
+            min_policy_version: None,
+            verify_evidence: None,
+            default_headers: HashMap::new(),
+            max_concurrent: None,
+            sign_requests: false,
+            client_signing_key: None,
+            proxy: None,
+            no_proxy: None,
+            max_resource_refs: None,
+            resource_refs_overflow: RefsOverflow::Truncate,
+            param_limits: None,
+            workspace_header: true,
+            request_envelope: Envelope::Legacy,
+            timestamp_format: TimestampFormat::Rfc3339,
+            wire_format: WireFormat::Json,
+            audit_log_path: None,
+            sync_clock: false,
+            degraded_audit: None,
+            budget_snapshot_ttl: Some(Duration::from_secs(300)),
+            budget_low_water: None,
+            on_budget_low: None,
+            stream_body_threshold: Some(1024 * 1024),
+            max_response_bytes: Some(8 * 1024 * 1024),
+            accept_language: None,
+            strict_version: false,
+            on_degrade: None,
+            invocation_defaults: InvocationDefaults::default(),
+            health_gate: None,
+            circuit_breaker: None,
+            cache: None,
+            rate_limit: None,
+            retry: None,
+            request_interceptor: None,
+            latency_window: 1024,
+            queue: None,
+            shadow_url: None,
+            on_shadow_divergence: None,
+        }
+    }
+}
+
+impl Config {
+    /// [`Config::default`] with overrides applied from the environment.
+    ///
+    /// | Variable | Field | Format |
+    /// |---|---|---|
+    /// | `SKILLGATE_SIDECAR_URL` | [`sidecar_url`](Self::sidecar_url) | URL |
+    /// | `SKILLGATE_SIDECAR_URLS` | [`sidecar_urls`](Self::sidecar_urls) | comma-separated URLs |
+    /// | `SKILLGATE_SLT` | [`slt`](Self::slt) | token |
+    /// | `SKILLGATE_TIMEOUT_MS` | [`timeout`](Self::timeout) | milliseconds |
+    /// | `SKILLGATE_STREAM_TIMEOUT_MS` | [`stream_timeout`](Self::stream_timeout) | milliseconds |
+    /// | `SKILLGATE_FAIL_MODE` | [`fail_open`](Self::fail_open) | `open`, or `closed`/`deny` |
+    /// | `SKILLGATE_MAX_RETRIES` | [`retry`](Self::retry) | retries after the first attempt, `0` for none |
+    /// | `SKILLGATE_PATH_PREFIX` | [`path_prefix`](Self::path_prefix) | path |
+    /// | `SKILLGATE_MIN_POLICY_VERSION` | [`min_policy_version`](Self::min_policy_version) | version |
+    /// | `SKILLGATE_MAX_CONCURRENT` | [`max_concurrent`](Self::max_concurrent) | integer |
+    /// | `SKILLGATE_REDACT_PARAM_KEYS` | [`redact_param_keys`](Self::redact_param_keys) | comma-separated keys |
+    /// | `SKILLGATE_OFFLINE_BUNDLE` | [`offline_bundle`](Self::offline_bundle) | path |
+    /// | `SKILLGATE_AUDIT_LOG` | [`audit_log_path`](Self::audit_log_path) | path |
+    /// | `SKILLGATE_PROXY` | [`proxy`](Self::proxy) | URL |
+    /// | `SKILLGATE_NO_PROXY` | [`no_proxy`](Self::no_proxy) | `NO_PROXY` syntax |
+    /// | `SKILLGATE_MAX_RESOURCE_REFS` | [`max_resource_refs`](Self::max_resource_refs) | integer |
+    /// | `SKILLGATE_MAX_RESPONSE_BYTES` | [`max_response_bytes`](Self::max_response_bytes) | integer, `0` for no limit |
+    /// | `SKILLGATE_ACCEPT_LANGUAGE` | [`accept_language`](Self::accept_language) | language tag |
+    /// | `SKILLGATE_WORKSPACE_HEADER` | [`workspace_header`](Self::workspace_header) | `true`/`false` |
+    /// | `SKILLGATE_SYNC_CLOCK` | [`sync_clock`](Self::sync_clock) | `true`/`false` |
+    /// | `SKILLGATE_STRICT_VERSION` | [`strict_version`](Self::strict_version) | `true`/`false` |
+    ///
+    /// Unset variables keep the [`Config::default`] value. Malformed values
+    /// are logged and ignored; use [`Config::try_from_env`] to reject them.
+    pub fn from_env() -> Self {
+        let mut cfg = Self::default();
+        // The handler never aborts, so this cannot fail.
+        let _ = cfg.apply_env(|name| std::env::var(name), &mut |err| {
+            tracing::warn!(error = %err, "ignoring invalid environment override");
+            Ok(())
+        });
+        cfg
+    }
+
+    /// Like [`Config::from_env`], but fails with [`Error::InvalidConfig`]
+    /// naming the variable when a value cannot be parsed.
+    pub fn try_from_env() -> Result<Self, Error> {
+        let mut cfg = Self::default();
+        cfg.apply_env(|name| std::env::var(name), &mut Err)?;
+        Ok(cfg)
+    }
+
+    /// Apply the variables documented on [`Config::from_env`], read through
+    /// `var`. Parse failures go to `invalid`, which decides whether to abort.
+    fn apply_env(
+        &mut self,
+        var: impl Fn(&str) -> Result<String, std::env::VarError>,
+        invalid: &mut dyn FnMut(Error) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let mut env = EnvReader { var: &var, invalid };
+        if let Some(v) = env.get("SKILLGATE_SIDECAR_URL", env_text)? {
+            self.sidecar_url = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_SIDECAR_URLS", env_list)? {
+            self.sidecar_urls = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_SLT", env_text)? {
+            self.slt = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_TIMEOUT_MS", env_millis)? {
+            self.timeout = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_STREAM_TIMEOUT_MS", env_millis)? {
+            self.stream_timeout = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_FAIL_MODE", env_fail_open)? {
+            self.fail_open = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_MAX_RETRIES", env_retries)? {
+            self.retry = (v > 0).then(|| RetryPolicy::new(v.saturating_add(1)));
+        }
+        if let Some(v) = env.get("SKILLGATE_PATH_PREFIX", env_text)? {
+            self.path_prefix = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_MIN_POLICY_VERSION", env_text)? {
+            self.min_policy_version = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_MAX_CONCURRENT", env_count)? {
+            self.max_concurrent = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_REDACT_PARAM_KEYS", env_list)? {
+            self.redact_param_keys = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_OFFLINE_BUNDLE", env_text)? {
+            self.offline_bundle = Some(v.into());
+        }
+        if let Some(v) = env.get("SKILLGATE_AUDIT_LOG", env_text)? {
+            self.audit_log_path = Some(v.into());
+        }
+        if let Some(v) = env.get("SKILLGATE_PROXY", env_text)? {
+            self.proxy = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_NO_PROXY", env_text)? {
+            self.no_proxy = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_MAX_RESOURCE_REFS", env_count)? {
+            self.max_resource_refs = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_MAX_RESPONSE_BYTES", env_count)? {
+            self.max_response_bytes = (v > 0).then_some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_ACCEPT_LANGUAGE", env_text)? {
+            self.accept_language = Some(v);
+        }
+        if let Some(v) = env.get("SKILLGATE_WORKSPACE_HEADER", env_flag)? {
+            self.workspace_header = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_SYNC_CLOCK", env_flag)? {
+            self.sync_clock = v;
+        }
+        if let Some(v) = env.get("SKILLGATE_STRICT_VERSION", env_flag)? {
+            self.strict_version = v;
+        }
+        Ok(())
+    }
+
+    /// Start a [`ConfigBuilder`] seeded from [`Config::from_env`].
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            cfg: Self::from_env(),
+        }
+    }
+}
+
+/// Environment lookups for [`Config::apply_env`].
+struct EnvReader<'a> {
+    var: &'a dyn Fn(&str) -> Result<String, std::env::VarError>,
+    invalid: &'a mut dyn FnMut(Error) -> Result<(), Error>,
+}
+
+impl EnvReader<'_> {
+    /// The parsed value of `name`, or `None` when it is unset or invalid and
+    /// `invalid` chose to carry on.
+    fn get<T>(
+        &mut self,
+        name: &str,
+        parse: fn(&str) -> Result<T, String>,
+    ) -> Result<Option<T>, Error> {
+        let value = match (self.var)(name) {
+            Ok(value) => value,
+            Err(std::env::VarError::NotPresent) => return Ok(None),
+            Err(std::env::VarError::NotUnicode(_)) => {
+                (self.invalid)(Error::InvalidConfig(format!("{name} is not valid UTF-8")))?;
+                return Ok(None);
+            }
+        };
+        match parse(&value) {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(e) => {
+                (self.invalid)(Error::InvalidConfig(format!("{name}={value:?}: {e}")))?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn env_text(value: &str) -> Result<String, String> {
+    Ok(value.to_string())
+}
+
+fn env_list(value: &str) -> Result<Vec<String>, String> {
+    Ok(value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn env_count(value: &str) -> Result<usize, String> {
+    value.trim().parse().map_err(|e| format!("{e}"))
+}
+
+fn env_retries(value: &str) -> Result<u32, String> {
+    value.trim().parse().map_err(|e| format!("{e}"))
+}
+
+fn env_millis(value: &str) -> Result<Duration, String> {
+    value
+        .trim()
+        .parse()
+        .map(Duration::from_millis)
+        .map_err(|e| format!("{e}"))
+}
+
+fn env_flag(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err("expected true or false".into()),
+    }
+}
+
+/// `SKILLGATE_FAIL_MODE`, as the value of [`Config::fail_open`].
+fn env_fail_open(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "open" => Ok(true),
+        "closed" | "deny" => Ok(false),
+        _ => Err("expected open, closed or deny".into()),
+    }
+}
+
+/// Chainable constructor for [`Config`]. Anything not set keeps the value
+/// from [`Config::from_env`].
+///
+/// ```rust,no_run
+/// use std::time::{Duration, Instant};
+///
+/// let cfg = skillgate::Config::builder()
+///     .sidecar_url("http://sidecar:8910")
+///     .timeout(Duration::from_millis(100))
+///     .fail_open(true)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    cfg: Config,
+}
+
+impl ConfigBuilder {
+    pub fn sidecar_url(mut self, url: impl Into<String>) -> Self {
+        self.cfg.sidecar_url = url.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.cfg.timeout = timeout;
+        self
+    }
+
+    pub fn fail_open(mut self, fail_open: bool) -> Self {
+        self.cfg.fail_open = fail_open;
+        self
+    }
+
+    pub fn fail_mode_resolver(
+        mut self,
+        resolver: impl Fn(&ToolInvocation) -> FailMode + Send + Sync + 'static,
+    ) -> Self {
+        self.cfg.fail_mode_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn slt(mut self, slt: impl Into<String>) -> Self {
+        self.cfg.slt = Some(slt.into());
+        self
+    }
+
+    pub fn slt_expiry_warning(mut self, warning: Duration) -> Self {
+        self.cfg.slt_expiry_warning = Some(warning);
+        self
+    }
+
+    pub fn on_slt_expiring(mut self, hook: impl Fn(DateTime<Utc>) + Send + Sync + 'static) -> Self {
+        self.cfg.on_slt_expiring = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn redact_param_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cfg.redact_param_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn log_bodies(mut self, mode: BodyLogMode) -> Self {
+        self.cfg.log_bodies = mode;
+        self
+    }
+
+    pub fn offline_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cfg.offline_bundle = Some(path.into());
+        self
+    }
+
+    pub fn offline_bundle_keys(mut self, keyring: Arc<Keyring>) -> Self {
+        self.cfg.offline_bundle_keys = Some(keyring);
+        self
+    }
+
+    pub fn stream_timeout(mut self, timeout: Duration) -> Self {
+        self.cfg.stream_timeout = timeout;
+        self
+    }
+
+    pub fn sidecar_urls<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cfg.sidecar_urls = urls.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.cfg.path_prefix = prefix.into();
+        self
+    }
+
+    pub fn min_policy_version(mut self, version: impl Into<String>) -> Self {
+        self.cfg.min_policy_version = Some(version.into());
+        self
+    }
+
+    pub fn verify_evidence(mut self, keyring: Arc<Keyring>) -> Self {
+        self.cfg.verify_evidence = Some(keyring);
+        self
+    }
+
+    /// Add one entry to [`Config::default_headers`].
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.cfg.default_headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn max_concurrent(mut self, max: usize) -> Self {
+        self.cfg.max_concurrent = Some(max);
+        self
+    }
+
+    /// Enable request signing with the given shared secret.
+    pub fn sign_requests(mut self, key: impl Into<String>) -> Self {
+        self.cfg.sign_requests = true;
+        self.cfg.client_signing_key = Some(key.into());
+        self
+    }
+
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.cfg.proxy = Some(url.into());
+        self
+    }
+
+    pub fn no_proxy(mut self, hosts: impl Into<String>) -> Self {
+        self.cfg.no_proxy = Some(hosts.into());
+        self
+    }
+
+    pub fn param_limits(mut self, limits: ParamLimits) -> Self {
+        self.cfg.param_limits = Some(limits);
+        self
+    }
+
+    pub fn max_resource_refs(mut self, max: usize, overflow: RefsOverflow) -> Self {
+        self.cfg.max_resource_refs = Some(max);
+        self.cfg.resource_refs_overflow = overflow;
+        self
+    }
+
+    pub fn workspace_header(mut self, enabled: bool) -> Self {
+        self.cfg.workspace_header = enabled;
+        self
+    }
+
+    pub fn request_envelope(mut self, envelope: Envelope) -> Self {
+        self.cfg.request_envelope = envelope;
+        self
+    }
+
+    pub fn timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.cfg.timestamp_format = format;
+        self
+    }
+
+    pub fn wire_format(mut self, format: WireFormat) -> Self {
+        self.cfg.wire_format = format;
+        self
+    }
+
+    pub fn audit_log_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cfg.audit_log_path = Some(path.into());
+        self
+    }
+
+    pub fn sync_clock(mut self, enabled: bool) -> Self {
+        self.cfg.sync_clock = enabled;
+        self
+    }
+
+    pub fn degraded_audit(mut self, audit: DegradedAuditConfig) -> Self {
+        self.cfg.degraded_audit = Some(audit);
+        self
+    }
+
+    pub fn budget_snapshot_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.cfg.budget_snapshot_ttl = ttl;
+        self
+    }
+
+    pub fn budget_low_water(mut self, low_water: f64) -> Self {
+        self.cfg.budget_low_water = Some(low_water);
+        self
+    }
+
+    pub fn on_budget_low(
+        mut self,
+        hook: impl Fn(&str, &BudgetStatus) + Send + Sync + 'static,
+    ) -> Self {
+        self.cfg.on_budget_low = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn stream_body_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.cfg.stream_body_threshold = threshold;
+        self
+    }
+
+    pub fn max_response_bytes(mut self, limit: Option<usize>) -> Self {
+        self.cfg.max_response_bytes = limit;
+        self
+    }
+
+    pub fn accept_language(mut self, language: impl Into<String>) -> Self {
+        self.cfg.accept_language = Some(language.into());
+        self
+    }
+
+    pub fn strict_version(mut self, strict: bool) -> Self {
+        self.cfg.strict_version = strict;
+        self
+    }
+
+    pub fn on_degrade(
+        mut self,
+        hook: impl Fn(&ToolInvocation, &Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.cfg.on_degrade = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn invocation_defaults(mut self, defaults: InvocationDefaults) -> Self {
+        self.cfg.invocation_defaults = defaults;
+        self
+    }
+
+    pub fn health_gate(mut self, window: Duration) -> Self {
+        self.cfg.health_gate = Some(window);
+        self
+    }
+
+    pub fn circuit_breaker(mut self, breaker: CircuitBreakerConfig) -> Self {
+        self.cfg.circuit_breaker = Some(breaker);
+        self
+    }
+
+    pub fn cache(mut self, cache: CacheConfig) -> Self {
+        self.cfg.cache = Some(cache);
+        self
+    }
+
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.cfg.rate_limit = Some(limit);
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.cfg.retry = Some(policy);
+        self
+    }
+
+    pub fn request_interceptor(
+        mut self,
+        interceptor: impl Fn(&mut ToolInvocation) + Send + Sync + 'static,
+    ) -> Self {
+        self.cfg.request_interceptor = Some(Arc::new(interceptor));
+        self
+    }
+
+    pub fn latency_window(mut self, calls: usize) -> Self {
+        self.cfg.latency_window = calls;
+        self
+    }
+
+    pub fn queue(mut self, capacity: usize, overflow: Overflow) -> Self {
+        self.cfg.queue = Some(QueueConfig::new(capacity, overflow));
+        self
+    }
+
+    pub fn shadow_url(mut self, url: impl Into<String>) -> Self {
+        self.cfg.shadow_url = Some(url.into());
+        self
+    }
+
+    pub fn on_shadow_divergence(
+        mut self,
+        hook: impl Fn(&ShadowDivergence) + Send + Sync + 'static,
+    ) -> Self {
+        self.cfg.on_shadow_divergence = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn build(self) -> Config {
+        self.cfg
+    }
+}
+
+/// Parse a dotted version (`v1.4.0`, `2026.3`) into numeric components,
+/// ignoring pre-release and build suffixes.
+fn version_components(version: &str) -> Option<Vec<u64>> {
+    let core = version.trim().trim_start_matches('v');
+    let core = core.split(['-', '+']).next()?;
+    core.split('.').map(|p| p.parse().ok()).collect()
+}
+
+/// Returns true if `got` is at least `required`; missing components count as 0.
+fn version_at_least(got: &str, required: &str) -> bool {
+    let (Some(mut got), Some(mut required)) =
+        (version_components(got), version_components(required))
+    else {
+        return false;
+    };
+    let len = got.len().max(required.len());
+    got.resize(len, 0);
+    required.resize(len, 0);
+    got >= required
+}
+
+/// Check the signature of an approval callback delivered by the sidecar
+/// (see [`Client::register_approval_callback`]).
+///
+/// `signature` is the hex HMAC-SHA256 of the raw request `body` under the
+/// shared callback `key`, optionally prefixed with `sha256=`. The comparison
+/// is constant-time.
+pub fn verify_callback_signature(body: &[u8], signature: &str, key: &str) -> bool {
+    let signature = signature.trim();
+    let hex_sig = signature.strip_prefix("sha256=").unwrap_or(signature);
+    let Ok(expected) = hex::decode(hex_sig) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// The `exp` claim of a JWT, without verifying it; `None` for opaque tokens
+/// and JWTs without an expiry.
+fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+
+    let mut parts = token.split('.');
+    let (Some(_), Some(payload), Some(_), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    let claims: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?)
+            .ok()?;
+    let exp = claims.get("exp")?;
+    let secs = exp.as_i64().or_else(|| exp.as_f64().map(|f| f as i64))?;
+    DateTime::from_timestamp(secs, 0)
+}
+
+/// JSON Schema of [`ToolInvocation`], the `decide` request payload.
+#[cfg(feature = "schema")]
+pub fn invocation_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(ToolInvocation)
+}
+
+/// JSON Schema of [`DecisionRecord`], the `decide` response.
+#[cfg(feature = "schema")]
+pub fn decision_schema() -> schemars::schema::RootSchema {
+    schemars::schema_for!(DecisionRecord)
+}
+
+// ---- Client -----------------------------------------------------------------
+
+/// Async HTTP client for the SkillGate runtime sidecar.
+pub struct Client {
+    cfg: Config,
+    http: HttpClient,
+    /// Base URL of the sidecar requests: [`Config::sidecar_url`], or
+    /// [`UNIX_SOCKET_BASE_URL`] when that names a Unix socket.
+    base_url: String,
+    offline: Option<OfflineBundle>,
+    limiter: Option<Arc<Semaphore>>,
+    queue: Option<DecideQueue>,
+    audit: Option<AuditLog>,
+    clock_offset_ms: AtomicI64,
+    degraded_audit: Option<Arc<DegradedAudit>>,
+    budget_snapshots: Mutex<HashMap<String, BudgetSnapshot>>,
+    server_api_version: Mutex<Option<String>>,
+    api_version_warned: AtomicBool,
+    unhealthy_until: Mutex<Option<Instant>>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    cache: Option<DecisionCache>,
+    budget_tracker: Option<BudgetTracker>,
+    /// Content hash of each resource uploaded by this client, by ref id.
+    uploaded_resources: Mutex<HashMap<String, String>>,
+    latency: LatencyWindow,
+    /// `ETag` of the last [`Client::list_tools`] response.
+    registry_etag: Mutex<Option<HeaderValue>>,
+    /// Set once the sidecar has rejected a binary [`Config::wire_format`].
+    binary_rejected: AtomicBool,
+    /// Set once the sidecar has turned out to have no batch route.
+    batch_unsupported: AtomicBool,
+    /// `exp` of [`Config::slt`], if it is a JWT.
+    slt_expiry: Option<DateTime<Utc>>,
+    slt_expiry_warned: AtomicBool,
+}
+
+/// Last-known-good budgets for a workspace and when they were received.
+type BudgetSnapshot = (Instant, HashMap<String, BudgetStatus>);
+
+/// One item of a partial `/v1/decide/batch` response: `{"ok": record}` or
+/// `{"error": ...}`. Records are parsed per item so that a malformed one only
+/// fails its own invocation.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum BatchResult {
+    Ok(serde_json::Value),
+    Error(serde_json::Value),
+}
+
+impl Client {
+    /// Create a new client with the given config.
+    ///
+    /// Panics if the config is invalid; see [`Client::try_new`].
+    pub fn new(cfg: Config) -> Self {
+        Self::try_new(cfg).expect("failed to build SkillGate client")
+    }
+
+    /// Create a new client, returning an error if `default_headers` contains
+    /// an invalid or `Authorization` header, `sign_requests` is set without a
+    /// key, `proxy` is not a valid URL, a Unix socket `sidecar_url` is
+    /// relative or combined with an option it cannot serve, `queue` is set
+    /// without `max_concurrent` or blocks with a capacity of 0,
+    /// `offline_bundle` is set without `offline_bundle_keys` or fails its
+    /// signature check, or `offline_bundle` or `audit_log_path` cannot be
+    /// opened.
+    pub fn try_new(cfg: Config) -> Result<Self, Error> {
+        if cfg.sign_requests && cfg.client_signing_key.is_none() {
+            return Err(Error::InvalidConfig(
+                "sign_requests requires client_signing_key".into(),
+            ));
+        }
+        let mut headers = HeaderMap::new();
+        for (name, value) in &cfg.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::InvalidConfig(format!("header name {name:?}: {e}")))?;
+            if name == AUTHORIZATION {
+                return Err(Error::InvalidConfig(
+                    "Authorization cannot be set via default_headers; use slt".into(),
+                ));
+            }
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::InvalidConfig(format!("header {name}: {e}")))?;
+            headers.insert(name, value);
+        }
+        let builder = HttpClient::builder();
+        #[cfg(feature = "rustls-tls")]
+        let builder = builder.use_rustls_tls();
+        let (builder, base_url) = match cfg.sidecar_url.strip_prefix("unix://") {
+            Some(socket) => (
+                Self::unix_socket(&cfg, builder, socket)?,
+                UNIX_SOCKET_BASE_URL.into(),
+            ),
+            None => (Self::apply_proxy(&cfg, builder)?, cfg.sidecar_url.clone()),
+        };
+        let http = builder
+            .timeout(cfg.timeout)
+            .user_agent(USER_AGENT)
+            .default_headers(headers)
+            .build()?;
+        let offline = match (cfg.offline_bundle.as_deref(), &cfg.offline_bundle_keys) {
+            (None, _) => None,
+            (Some(path), Some(keys)) => Some(OfflineBundle::load(path, keys)?),
+            (Some(_), None) => {
+                return Err(Error::InvalidConfig(
+                    "offline_bundle requires offline_bundle_keys".into(),
+                ))
+            }
+        };
+        let limiter = cfg.max_concurrent.map(|n| Arc::new(Semaphore::new(n)));
+        if cfg.queue.is_some() && limiter.is_none() {
+            return Err(Error::InvalidConfig("queue requires max_concurrent".into()));
+        }
+        if cfg
+            .queue
+            .is_some_and(|q| q.capacity == 0 && q.overflow == Overflow::Block)
+        {
+            return Err(Error::InvalidConfig(
+                "a blocking queue needs a capacity of at least 1".into(),
+            ));
+        }
+        let queue = cfg.queue.map(DecideQueue::new);
+        let audit = cfg
+            .audit_log_path
+            .as_deref()
+            .map(AuditLog::open)
+            .transpose()?;
+        let degraded_audit = cfg.degraded_audit.clone().map(DegradedAudit::new);
+        let latency = LatencyWindow::new(cfg.latency_window);
+        let slt_expiry = cfg.slt.as_deref().and_then(jwt_expiry);
+        let cache = cfg.cache.map(DecisionCache::new);
+        let budget_tracker = cfg
+            .budget_low_water
+            .map(|low_water| BudgetTracker::new(low_water, cfg.on_budget_low.clone()));
+        let breaker = cfg
+            .circuit_breaker
+            .map(|breaker| Arc::new(CircuitBreaker::new(breaker)));
+        let client = Self {
+            cfg,
+            http,
+            base_url,
+            offline,
+            limiter,
+            queue,
+            audit,
+            clock_offset_ms: AtomicI64::new(0),
+            degraded_audit,
+            budget_snapshots: Mutex::new(HashMap::new()),
+            server_api_version: Mutex::new(None),
+            api_version_warned: AtomicBool::new(false),
+            unhealthy_until: Mutex::new(None),
+            breaker,
+            cache,
+            budget_tracker,
+            uploaded_resources: Mutex::new(HashMap::new()),
+            latency,
+            registry_etag: Mutex::new(None),
+            binary_rejected: AtomicBool::new(false),
+            batch_unsupported: AtomicBool::new(false),
+            slt_expiry,
+            slt_expiry_warned: AtomicBool::new(false),
+        };
+        if let Some(keyring) = &client.cfg.verify_evidence {
+            keyring.bind_sidecar(
+                &client.http,
+                client.endpoint("/v1/keys"),
+                client.cfg.max_response_bytes,
+            );
+        }
+        Ok(client)
+    }
+
+    /// Route every request through the Unix socket at `path`, the rest of a
+    /// `unix://` [`Config::sidecar_url`].
+    #[cfg(unix)]
+    fn unix_socket(
+        cfg: &Config,
+        builder: reqwest::ClientBuilder,
+        path: &str,
+    ) -> Result<reqwest::ClientBuilder, Error> {
+        if !path.starts_with('/') {
+            return Err(Error::InvalidConfig(format!(
+                "sidecar_url {:?}: socket path must be absolute",
+                cfg.sidecar_url
+            )));
+        }
+        let unsupported = [
+            ("sidecar_urls", !cfg.sidecar_urls.is_empty()),
+            ("shadow_url", cfg.shadow_url.is_some()),
+            ("proxy", cfg.proxy.is_some()),
+        ];
+        if let Some((option, _)) = unsupported.iter().find(|(_, set)| *set) {
+            return Err(Error::InvalidConfig(format!(
+                "{option} cannot be used with a Unix socket sidecar_url"
+            )));
+        }
+        Ok(builder.unix_socket(path))
+    }
+
+    #[cfg(not(unix))]
+    fn unix_socket(
+        _cfg: &Config,
+        _builder: reqwest::ClientBuilder,
+        _path: &str,
+    ) -> Result<reqwest::ClientBuilder, Error> {
+        Err(Error::InvalidConfig(
+            "Unix socket sidecar URLs are only supported on Unix".into(),
+        ))
+    }
+
+    fn apply_proxy(
+        cfg: &Config,
+        builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, Error> {
+        if cfg.proxy.is_none() && cfg.no_proxy.is_none() {
+            return Ok(builder);
+        }
+        let mut builder = builder.no_proxy();
+        let no_proxy = cfg.no_proxy.as_deref();
+        if no_proxy.map(str::trim) == Some("*") {
+            return Ok(builder);
+        }
+        let bypass = || no_proxy.and_then(NoProxy::from_string);
+        let invalid = |e: reqwest::Error| Error::InvalidConfig(format!("proxy: {e}"));
+
+        if let Some(url) = &cfg.proxy {
+            let proxy = Proxy::all(url).map_err(invalid)?.no_proxy(bypass());
+            return Ok(builder.proxy(proxy));
+        }
+        let env = |names: [&str; 2]| names.into_iter().find_map(|n| std::env::var(n).ok());
+        if let Some(url) = env(["HTTP_PROXY", "http_proxy"]) {
+            builder = builder.proxy(Proxy::http(&url).map_err(invalid)?.no_proxy(bypass()));
+        }
+        if let Some(url) = env(["HTTPS_PROXY", "https_proxy"]) {
+            builder = builder.proxy(Proxy::https(&url).map_err(invalid)?.no_proxy(bypass()));
+        }
+        Ok(builder)
+    }
+
+    /// Join a base URL, the configured path prefix and an endpoint path
+    /// without doubled or missing slashes.
+    fn endpoint_at(&self, base_url: &str, path: &str) -> String {
+        let prefix = self.cfg.path_prefix.trim_matches('/');
+        let base = base_url.trim_end_matches('/');
+        let path = path.trim_start_matches('/');
+        if prefix.is_empty() {
+            format!("{base}/{path}")
+        } else {
+            format!("{base}/{prefix}/{path}")
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        self.endpoint_at(&self.base_url, path)
+    }
+
+    /// The endpoint for `segments`, each percent-encoded as a single path
+    /// segment, so ids from callers cannot add segments or a query.
+    fn endpoint_segments(&self, segments: &[&str]) -> String {
+        let base = self.endpoint("/");
+        let mut url = match reqwest::Url::parse(&base) {
+            Ok(url) if !url.cannot_be_a_base() => url,
+            // Fails with a proper error when the request is built.
+            _ => return base + &segments.join("/"),
+        };
+        url.path_segments_mut()
+            .expect("base URL")
+            .pop_if_empty()
+            .extend(segments);
+        url.into()
+    }
+
+    fn auth_header(&self) -> Option<String> {
+        self.cfg.slt.as_ref().map(|t| format!("Bearer {t}"))
+    }
+
+    /// Enforce [`Config::slt_expiry_warning`] before a `decide` call.
+    fn check_slt_expiry(&self) -> Result<(), Error> {
+        let (Some(warning), Some(expires_at)) = (self.cfg.slt_expiry_warning, self.slt_expiry)
+        else {
+            return Ok(());
+        };
+        let now = self.now();
+        if now >= expires_at {
+            return Err(Error::SltExpired {
+                expired_at: expires_at,
+            });
+        }
+        let warning = chrono::Duration::from_std(warning).unwrap_or(chrono::Duration::MAX);
+        if expires_at - now <= warning && !self.slt_expiry_warned.swap(true, Ordering::Relaxed) {
+            tracing::warn!(%expires_at, "session license token expires soon");
+            if let Some(hook) = &self.cfg.on_slt_expiring {
+                hook(expires_at);
+            }
+        }
+        Ok(())
+    }
+
+    fn server_request_id(resp: &reqwest::Response) -> Option<String> {
+        resp.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// Enforce [`Config::verify_evidence`] on a decision from the sidecar.
+    async fn check_evidence(&self, record: &DecisionRecord) -> Result<(), Error> {
+        let Some(keyring) = &self.cfg.verify_evidence else {
+            return Ok(());
+        };
+        record.verify_with(keyring).await.inspect_err(|e| {
+            tracing::warn!(
+                invocation_id = %record.invocation_id,
+                key_id = %record.evidence.key_id,
+                error = %e,
+                "rejecting decision with unverifiable evidence"
+            );
+        })
+    }
+
+    fn check_policy_version(&self, record: &DecisionRecord) -> Result<(), Error> {
+        match &self.cfg.min_policy_version {
+            Some(required) if !version_at_least(&record.policy_version, required) => {
+                Err(Error::PolicyVersionTooOld {
+                    got: record.policy_version.clone(),
+                    required: required.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read and deserialize a JSON response body, enforcing
+    /// [`Config::max_response_bytes`].
+    async fn read_json<T: serde::de::DeserializeOwned>(
+        &self,
+        resp: reqwest::Response,
+    ) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.read_body(resp).await?)?)
+    }
+
+    /// Read a response body, enforcing [`Config::max_response_bytes`].
+    async fn read_body(&self, resp: reqwest::Response) -> Result<Vec<u8>, Error> {
+        Self::read_limited(resp, self.cfg.max_response_bytes).await
+    }
+
+    /// Read a response body of at most `limit` bytes, failing with
+    /// [`Error::ResponseTooLarge`] beyond that.
+    pub(crate) async fn read_limited(
+        mut resp: reqwest::Response,
+        limit: Option<usize>,
+    ) -> Result<Vec<u8>, Error> {
+        let Some(limit) = limit else {
+            return Ok(resp.bytes().await?.to_vec());
+        };
+        let too_large = |len: usize| len > limit;
+        if resp
+            .content_length()
+            .is_some_and(|len| too_large(len as usize))
+        {
+            return Err(Error::ResponseTooLarge { limit });
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            if too_large(body.len() + chunk.len()) {
+                return Err(Error::ResponseTooLarge { limit });
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Record the `X-SkillGate-Api-Version` of a response and check it
+    /// against [`SUPPORTED_API_MAJOR`]. Responses without the header (older
+    /// sidecars) are accepted.
+    fn check_api_version(&self, headers: &HeaderMap) -> Result<(), Error> {
+        let Some(version) = headers
+            .get(API_VERSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        else {
+            return Ok(());
+        };
+        *self.server_api_version.lock().unwrap() = Some(version.to_string());
+        let major = version_components(version).and_then(|c| c.first().copied());
+        if major == Some(SUPPORTED_API_MAJOR) {
+            return Ok(());
+        }
+        if self.cfg.strict_version {
+            return Err(Error::ApiVersionUnsupported {
+                got: version.to_string(),
+                supported: SUPPORTED_API_MAJOR,
+            });
+        }
+        if !self.api_version_warned.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                server_api_version = version,
+                supported = SUPPORTED_API_MAJOR,
+                "sidecar API version is not supported by this client"
+            );
+        }
+        Ok(())
+    }
+
+    /// Consume a non-success response into [`Error::SidecarError`].
+    async fn status_error(&self, resp: reqwest::Response) -> Error {
+        Self::sidecar_error(resp, self.cfg.max_response_bytes).await
+    }
+
+    /// Consume a non-success response into [`Error::SidecarError`], keeping
+    /// the first `limit` bytes of the body.
+    pub(crate) async fn sidecar_error(mut resp: reqwest::Response, limit: Option<usize>) -> Error {
+        let status = resp.status().as_u16();
+        let headers = resp.headers().clone();
+        let mut body = Vec::new();
+        while let Ok(Some(chunk)) = resp.chunk().await {
+            body.extend_from_slice(&chunk);
+            if let Some(limit) = limit.filter(|&limit| body.len() >= limit) {
+                body.truncate(limit);
+                break;
+            }
+        }
+        let body = String::from_utf8_lossy(&body).into_owned();
+        tracing::debug!(
+            status,
+            server_request_id = ?headers.get(REQUEST_ID_HEADER),
+            "sidecar returned error status"
+        );
+        Error::SidecarError {
+            status,
+            body,
+            headers,
+        }
+    }
+
+    fn redact_params<'a>(
+        &self,
+        params: impl IntoIterator<Item = (&'a String, &'a mut serde_json::Value)>,
+    ) {
+        if self.cfg.redact_param_keys.is_empty() {
+            return;
+        }
+        for (key, value) in params {
+            if self
+                .cfg
+                .redact_param_keys
+                .iter()
+                .any(|k| k.eq_ignore_ascii_case(key))
+            {
+                *value = serde_json::Value::String(REDACTED.into());
+            }
+        }
+    }
+
+    fn degraded_allow(invocation_id: &str) -> DecisionRecord {
+        let mut record = DecisionRecord::new(invocation_id, "ALLOW", FAIL_OPEN_CODE);
+        record.reason_codes = vec!["enforcer_unavailable_fail_open".into()];
+        record.degraded = true;
+        record.license_mode = "offline".into();
+        record
+    }
+
+    fn decide_body(&self, invocation: &mut ToolInvocation) -> Result<serde_json::Value, Error> {
+        let truncated = self.prepare_decide(&mut invocation.request)?;
+        let body = DecideBody::new(
+            self.cfg.request_envelope,
+            self.cfg.timestamp_format,
+            invocation,
+            truncated,
+        );
+        Ok(serde_json::to_value(body)?)
+    }
+
+    /// Whether [`prepare_decide`](Self::prepare_decide) would change or reject
+    /// `request`, other than by [`Config::param_limits`].
+    fn needs_prepare(&self, request: &ToolRequest) -> bool {
+        let redacts = request.params.iter().any(|(key, value)| {
+            value.as_str() != Some(REDACTED)
+                && self
+                    .cfg
+                    .redact_param_keys
+                    .iter()
+                    .any(|k| k.eq_ignore_ascii_case(key))
+        });
+        let refs = &request.resource_refs;
+        let mut seen = HashSet::with_capacity(refs.len());
+        redacts
+            || !refs.iter().all(|r| seen.insert(r))
+            || self
+                .cfg
+                .max_resource_refs
+                .is_some_and(|max| refs.len() > max)
+    }
+
+    /// Apply redaction, param limits and resource-ref limits. Returns the
+    /// number of refs dropped by truncation.
+    fn prepare_decide(&self, request: &mut ToolRequest) -> Result<usize, Error> {
+        self.redact_params(request.params.iter_mut());
+        self.check_param_limits(request)?;
+        self.limit_resource_refs(&mut request.resource_refs)
+    }
+
+    /// Enforce [`Config::param_limits`].
+    fn check_param_limits(&self, request: &ToolRequest) -> Result<(), Error> {
+        let Some(limits) = &self.cfg.param_limits else {
+            return Ok(());
+        };
+        let params = &request.params;
+        if let Some(limit) = limits.max_param_count {
+            if params.len() > limit {
+                return Err(Error::TooManyParams {
+                    count: params.len(),
+                    limit,
+                });
+            }
+        }
+        if let Some(limit) = limits.max_param_bytes {
+            // Sorted, so the reported key does not depend on map order.
+            let mut keys: Vec<_> = params.keys().collect();
+            keys.sort_unstable();
+            for key in keys {
+                let size = wire::serialized_len(&params[key]);
+                if size > limit {
+                    return Err(Error::ParamTooLarge {
+                        key: key.clone(),
+                        size,
+                        limit,
+                    });
+                }
+            }
+        }
+        if let Some(limit) = limits.max_total_bytes {
+            let size = wire::serialized_len(params);
+            if size > limit {
+                return Err(Error::ParamsTooLarge { size, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the body for this request should be streamed rather than
+    /// buffered; see [`Config::stream_body_threshold`].
+    fn streams_body(&self, request: &ToolRequest) -> bool {
+        match self.cfg.stream_body_threshold {
+            Some(threshold)
+                if !self.cfg.sign_requests && self.wire_format() == WireFormat::Json =>
+            {
+                wire::serialized_len(&request.params) > threshold
+            }
+            _ => false,
+        }
+    }
+
+    /// De-duplicate refs (keeping first occurrences) and apply
+    /// [`Config::max_resource_refs`]. Returns the number of refs dropped by
+    /// truncation.
+    fn limit_resource_refs(&self, refs: &mut Vec<String>) -> Result<usize, Error> {
+        let mut seen = HashSet::with_capacity(refs.len());
+        refs.retain(|r| seen.insert(r.clone()));
+        let Some(limit) = self.cfg.max_resource_refs else {
+            return Ok(0);
+        };
+        if refs.len() <= limit {
+            return Ok(0);
+        }
+        match self.cfg.resource_refs_overflow {
+            RefsOverflow::Truncate => {
+                let dropped = refs.len() - limit;
+                refs.truncate(limit);
+                Ok(dropped)
+            }
+            RefsOverflow::Error => Err(Error::TooManyResourceRefs {
+                count: refs.len(),
+                limit,
+            }),
+        }
+    }
+
+    /// Attach a JSON body, signing it when [`Config::sign_requests`] is set.
+    fn with_body(
+        &self,
+        req: reqwest::RequestBuilder,
+        body: &serde_json::Value,
+    ) -> reqwest::RequestBuilder {
+        let key = match &self.cfg.client_signing_key {
+            Some(key) if self.cfg.sign_requests => key,
+            _ => return req.json(body),
+        };
+        let canonical = canonical::to_canonical_string(body);
+        let nonce = Uuid::new_v4().simple().to_string();
+        let timestamp = Utc::now().timestamp().to_string();
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(format!("{nonce}.{timestamp}.{canonical}").as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+        req.header(CONTENT_TYPE, "application/json")
+            .header(NONCE_HEADER, nonce)
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, signature)
+            .body(canonical)
+    }
+
+    /// The [`Config::wire_format`] to send `decide` bodies in: JSON when
+    /// signing or once the sidecar has rejected a binary format.
+    fn wire_format(&self) -> WireFormat {
+        if self.cfg.sign_requests || self.binary_rejected.load(Ordering::Relaxed) {
+            WireFormat::Json
+        } else {
+            self.cfg.wire_format
+        }
+    }
+
+    /// Attach a `decide` body in `format` and the per-invocation headers.
+    fn encode_decide(
+        &self,
+        req: reqwest::RequestBuilder,
+        invocation: &ToolInvocation,
+        body: &DecideBody<'_>,
+        format: WireFormat,
+    ) -> Result<reqwest::RequestBuilder, Error> {
+        if format == WireFormat::Json {
+            return Ok(self.decide_request(req, invocation, &serde_json::to_value(body)?));
+        }
+        let req = req
+            .header(CONTENT_TYPE, format.content_type())
+            .header(ACCEPT, format.content_type())
+            .body(format.encode(body)?);
+        Ok(self.decide_headers(req, &invocation.invocation_id, &invocation.actor))
+    }
+
+    /// Replace the `Authorization` header of a built-up request, if `auth`
+    /// is given.
+    fn with_auth(
+        req: reqwest::RequestBuilder,
+        auth: Option<&HeaderValue>,
+    ) -> Result<reqwest::RequestBuilder, Error> {
+        let Some(auth) = auth else {
+            return Ok(req);
+        };
+        let (http, request) = req.build_split();
+        let mut request = request?;
+        request.headers_mut().insert(AUTHORIZATION, auth.clone());
+        Ok(reqwest::RequestBuilder::from_parts(http, request))
+    }
+
+    /// Add the body and per-invocation headers shared by all decide calls.
+    fn decide_request(
+        &self,
+        req: reqwest::RequestBuilder,
+        invocation: &ToolInvocation,
+        body: &serde_json::Value,
+    ) -> reqwest::RequestBuilder {
+        self.decide_headers(
+            self.with_body(req, body),
+            &invocation.invocation_id,
+            &invocation.actor,
+        )
+    }
+
+    /// Add the auth and routing headers shared by all decide calls.
+    fn decide_headers(
+        &self,
+        mut req: reqwest::RequestBuilder,
+        invocation_id: &str,
+        actor: &Actor,
+    ) -> reqwest::RequestBuilder {
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        if let Some(language) = &self.cfg.accept_language {
+            req = req.header(ACCEPT_LANGUAGE, language);
+        }
+        let workspace = &actor.workspace_id;
+        if self.cfg.workspace_header && !workspace.is_empty() {
+            match HeaderValue::from_str(workspace) {
+                Ok(value) => req = req.header(WORKSPACE_HEADER, value),
+                Err(_) => tracing::warn!(
+                    invocation_id,
+                    "workspace id is not a valid header value, omitting routing header"
+                ),
+            }
+        }
+        req
+    }
+
+    /// Resolve a decision for an invocation the sidecar could not be reached
+    /// for; `err` is the [`Error::EnforcerUnavailable`] returned when failing
+    /// closed.
+    fn unavailable(
+        &self,
+        invocation: &ToolInvocation,
+        err: Error,
+    ) -> Result<DecisionRecord, Error> {
+        self.degrade(invocation, &err);
+        if let Some(record) = self.offline.as_ref().and_then(|b| b.evaluate(invocation)) {
+            return Ok(record);
+        }
+        if self.fail_mode(invocation) == FailMode::Open {
+            let mut record = Self::degraded_allow(&invocation.invocation_id);
+            self.apply_budget_snapshot(&invocation.actor.workspace_id, &mut record);
+            if let Some(audit) = &self.degraded_audit {
+                audit.enqueue(invocation, &record, || degraded_audit::Sink {
+                    http: self.http.clone(),
+                    url: self.endpoint("/v1/audit"),
+                    auth: self.auth_header(),
+                });
+            }
+            return Ok(record);
+        }
+        Err(err)
+    }
+
+    /// [`Config::fail_mode_resolver`]'s choice for `invocation`, falling back
+    /// to [`Config::fail_open`].
+    fn fail_mode(&self, invocation: &ToolInvocation) -> FailMode {
+        match &self.cfg.fail_mode_resolver {
+            Some(resolver) => resolver(invocation),
+            None if self.cfg.fail_open => FailMode::Open,
+            None => FailMode::Closed,
+        }
+    }
+
+    /// Take a [`Config::max_concurrent`] permit, waiting in [`Config::queue`]
+    /// when one is configured.
+    async fn acquire_permit(&self) -> Result<Option<SemaphorePermit<'_>>, Error> {
+        let Some(limiter) = &self.limiter else {
+            return Ok(None);
+        };
+        match &self.queue {
+            Some(queue) => queue.acquire(limiter).await.map(Some),
+            None => Ok(Some(
+                limiter.acquire().await.expect("limiter is never closed"),
+            )),
+        }
+    }
+
+    fn queue_fails_open(&self) -> bool {
+        self.queue
+            .as_ref()
+            .is_some_and(|queue| queue.overflow() == Overflow::FailOpen)
+    }
+
+    fn queue_overflow_allow(invocation_id: &str) -> DecisionRecord {
+        let mut record = Self::degraded_allow(invocation_id);
+        record.reason_codes = vec!["queue_overflow_fail_open".into()];
+        record
+    }
+
+    /// Apply [`Config::rate_limit`], waiting at most the request timeout.
+    async fn take_rate_limit_token(&self) -> Result<(), Error> {
+        match &self.cfg.rate_limit {
+            Some(limit) => limit
+                .acquire(self.cfg.timeout)
+                .await
+                .map_err(|retry_after| Error::RateLimited { retry_after }),
+            None => Ok(()),
+        }
+    }
+
+    /// The error to fail a request with, unsent, while
+    /// [`Config::health_gate`] or [`Config::circuit_breaker`] says to skip
+    /// the sidecar.
+    fn short_circuit(&self) -> Option<Error> {
+        let reason = if self.health_gated() {
+            "sidecar marked unhealthy by health gate"
+        } else if self.breaker_open() {
+            "circuit breaker open"
+        } else {
+            return None;
+        };
+        Some(Error::EnforcerUnavailable {
+            reason: reason.into(),
+            source: None,
+        })
+    }
+
+    /// Whether [`Config::health_gate`] says to skip the sidecar for now.
+    fn health_gated(&self) -> bool {
+        self.cfg.health_gate.is_some()
+            && self
+                .unhealthy_until
+                .lock()
+                .unwrap()
+                .is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Whether the circuit breaker rejects requests, starting the recovery
+    /// probe once the cooldown is over.
+    fn breaker_open(&self) -> bool {
+        let Some(breaker) = &self.breaker else {
+            return false;
+        };
+        match breaker.admit() {
+            Admission::Allow => false,
+            Admission::Reject => true,
+            Admission::Probe => {
+                let probe = self
+                    .http
+                    .get(self.endpoint("/v1/health"))
+                    .timeout(self.cfg.timeout);
+                let breaker = breaker.clone();
+                tokio::spawn(async move {
+                    let healthy =
+                        matches!(probe.send().await, Ok(resp) if resp.status() == StatusCode::OK);
+                    breaker.record(healthy);
+                });
+                true
+            }
+        }
+    }
+
+    /// Update the [`Config::health_gate`] and circuit breaker state after
+    /// reaching (or failing to reach) the primary sidecar.
+    fn record_health(&self, reachable: bool) {
+        if let Some(breaker) = &self.breaker {
+            breaker.record(reachable);
+        }
+        let Some(window) = self.cfg.health_gate else {
+            return;
+        };
+        *self.unhealthy_until.lock().unwrap() = (!reachable).then(|| Instant::now() + window);
+    }
+
+    /// Run [`Config::on_degrade`], if set.
+    fn degrade(&self, invocation: &ToolInvocation, err: &Error) {
+        if let Some(hook) = &self.cfg.on_degrade {
+            hook(invocation, err);
+        }
+    }
+
+    fn client_error_deny(invocation_id: &str, err: &Error) -> DecisionRecord {
+        let reason = if err.is_connect() || err.is_timeout() {
+            "enforcer_unavailable_client_deny"
+        } else {
+            "client_error_deny"
+        };
+        let mut record = DecisionRecord::new(invocation_id, "DENY", "SG_DENY_CLIENT_ERROR");
+        record.reason_codes = vec![reason.into()];
+        record.degraded = true;
+        record
+            .extra
+            .insert("client_error".into(), err.to_string().into());
+        record
+    }
+
+    /// Send a `ToolInvocation` to the sidecar for an enforcement decision.
+    ///
+    /// If the sidecar is unreachable, the offline bundle (when configured) is
+    /// consulted first. Otherwise returns [`Error::EnforcerUnavailable`]
+    /// when the invocation fails closed per [`Config::fail_mode_resolver`]
+    /// or, without one, `fail_open`.
+    ///
+    /// Params listed in [`Config::redact_param_keys`] are masked before the
+    /// body is serialized, then checked against [`Config::param_limits`], and
+    /// `resource_refs` are de-duplicated and limited per
+    /// [`Config::max_resource_refs`]. Returns [`Error::PolicyVersionTooOld`] when the
+    /// sidecar's decision predates [`Config::min_policy_version`], and an
+    /// evidence error when it fails [`Config::verify_evidence`].
+    ///
+    /// Every error is wrapped in [`Error::Request`] carrying the invocation
+    /// and session ids; match on [`Error::root`] for the cause.
+    ///
+    /// The future is cancellation-safe: dropping it (e.g. from a losing
+    /// `select!` branch) aborts the HTTP request and releases the
+    /// [`Config::max_concurrent`] permit, and no client state is touched until
+    /// the response has been fully read.
+    pub async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+        let started = Instant::now();
+        let invocation_id = invocation.invocation_id.clone();
+        let session_id = invocation.actor.session_id.clone();
+        let result = self.send_decide(Cow::Owned(invocation), None).await;
+        self.finish_decide(result, started, invocation_id, session_id)
+    }
+
+    /// Like [`Client::decide`], but authenticates this one call with `slt`
+    /// instead of [`Config::slt`], e.g. a tenant's own token in a
+    /// multi-tenant gateway. The connection pool and all other settings are
+    /// shared with regular calls.
+    pub async fn decide_as(
+        &self,
+        invocation: ToolInvocation,
+        slt: &str,
+    ) -> Result<DecisionRecord, Error> {
+        let started = Instant::now();
+        let invocation_id = invocation.invocation_id.clone();
+        let session_id = invocation.actor.session_id.clone();
+        let result = async {
+            let mut auth = HeaderValue::from_str(&format!("Bearer {slt}"))
+                .map_err(|_| Error::InvalidConfig("slt is not a valid header value".into()))?;
+            auth.set_sensitive(true);
+            self.send_decide(Cow::Owned(invocation), Some(&auth)).await
+        }
+        .await;
+        self.finish_decide(result, started, invocation_id, session_id)
+    }
+
+    /// Like [`Client::decide`], but borrows the invocation so it can be
+    /// logged or retried afterwards without cloning it up front.
+    ///
+    /// The invocation is still copied internally when the request body has
+    /// to differ from it (clock sync, redaction, resource-ref limits) or is
+    /// streamed.
+    pub async fn decide_ref(&self, invocation: &ToolInvocation) -> Result<DecisionRecord, Error> {
+        let started = Instant::now();
+        let result = self.send_decide(Cow::Borrowed(invocation), None).await;
+        self.finish_decide(
+            result,
+            started,
+            invocation.invocation_id.clone(),
+            invocation.actor.session_id.clone(),
+        )
+    }
+
+    /// Decide a tool call in the session described by `prepared`, serializing
+    /// only `tool` and `request`. A fresh invocation id and timestamp are
+    /// generated. Behaves like [`Client::decide`] in every other respect.
+    ///
+    /// Requests that must be signed, streamed, intercepted, cached or sent in
+    /// a binary format ([`Config::sign_requests`],
+    /// [`Config::stream_body_threshold`], [`Config::request_interceptor`],
+    /// [`Config::cache`], [`Config::wire_format`]) take the regular path,
+    /// since they need the structured invocation.
+    pub async fn decide_prepared(
+        &self,
+        prepared: &PreparedInvocation,
+        tool: Tool,
+        mut request: ToolRequest,
+    ) -> Result<DecisionRecord, Error> {
+        let started = Instant::now();
+        let invocation_id = Uuid::new_v4().to_string();
+        let timestamp = if self.cfg.sync_clock {
+            self.now()
+        } else {
+            Utc::now()
+        };
+        if self.cfg.sign_requests
+            || self.cfg.request_interceptor.is_some()
+            || self.cache.is_some()
+            || self.wire_format() != WireFormat::Json
+            || self.streams_body(&request)
+        {
+            let invocation = prepared.invocation(invocation_id, timestamp, tool, request);
+            return self.decide(invocation).await;
+        }
+
+        let session_id = prepared.actor().session_id.clone();
+        let result = async {
+            self.check_slt_expiry()?;
+            self.take_rate_limit_token().await?;
+            let _permit = match self.acquire_permit().await {
+                Err(Error::QueueFull) if self.queue_fails_open() => {
+                    return Ok(Self::queue_overflow_allow(&invocation_id));
+                }
+                permit => permit?,
+            };
+            let truncated = self.prepare_decide(&mut request)?;
+            let body = prepared.render(
+                self.cfg.request_envelope,
+                self.cfg.timestamp_format,
+                &invocation_id,
+                &timestamp,
+                &tool,
+                &request,
+                truncated,
+            )?;
+            let req = self
+                .http
+                .post(self.endpoint("/v1/decide"))
+                .header(CONTENT_TYPE, "application/json")
+                .body(body);
+            let req = self.decide_headers(req, &invocation_id, prepared.actor());
+            let shadow = self.start_shadow(&req);
+            let result = self
+                .exchange_decide(req, Some(&prepared.actor().workspace_id), |e| {
+                    let invocation =
+                        prepared.invocation(invocation_id.clone(), timestamp, tool, request);
+                    self.unavailable(&invocation, e)
+                })
+                .await;
+            Self::finish_shadow(shadow, &result);
+            result
+        }
+        .await;
+        self.finish_decide(result, started, invocation_id, session_id)
+    }
+
+    /// Audit a successful decision, record the call's latency and tag
+    /// failures with the invocation.
+    fn finish_decide(
+        &self,
+        result: Result<DecisionRecord, Error>,
+        started: Instant,
+        invocation_id: String,
+        session_id: String,
+    ) -> Result<DecisionRecord, Error> {
+        let fail_open = result
+            .as_ref()
+            .is_ok_and(|record| record.is_allowed() && record.decision_code == FAIL_OPEN_CODE);
+        self.latency.record(started.elapsed(), fail_open);
+        if let Ok(record) = &result {
+            self.audit(record);
+        }
+        result.map_err(|source| Error::Request {
+            invocation_id,
+            session_id: (!session_id.is_empty()).then_some(session_id),
+            source: Box::new(source),
+        })
+    }
+
+    /// Shared by [`Client::decide`] and [`Client::decide_ref`]. A borrowed
+    /// invocation is only cloned when the body differs from it (clock sync,
+    /// redaction, ref limits, interceptor) or is streamed. `auth` replaces
+    /// the configured `Authorization` header.
+    async fn send_decide(
+        &self,
+        mut invocation: Cow<'_, ToolInvocation>,
+        auth: Option<&HeaderValue>,
+    ) -> Result<DecisionRecord, Error> {
+        if auth.is_none() {
+            self.check_slt_expiry()?;
+        }
+        // Calls with their own token are not cached, so that one tenant's
+        // decisions are never served to another.
+        let fingerprint = match &self.cache {
+            Some(cache) if auth.is_none() => {
+                let fingerprint = invocation.fingerprint();
+                if let Some(record) = cache.get(&fingerprint, self.now()) {
+                    return Ok(record);
+                }
+                Some(fingerprint)
+            }
+            _ => None,
+        };
+        self.take_rate_limit_token().await?;
+        let _permit = match self.acquire_permit().await {
+            Err(Error::QueueFull) if self.queue_fails_open() => {
+                return Ok(Self::queue_overflow_allow(&invocation.invocation_id));
+            }
+            permit => permit?,
+        };
+        if self.cfg.sync_clock {
+            let offset = self.clock_offset();
+            if !offset.is_zero() {
+                invocation.to_mut().timestamp += offset;
+            }
+        }
+        let truncated = match &invocation {
+            Cow::Borrowed(borrowed) if !self.needs_prepare(&borrowed.request) => {
+                self.check_param_limits(&borrowed.request)?;
+                0
+            }
+            _ => self.prepare_decide(&mut invocation.to_mut().request)?,
+        };
+        if let Some(interceptor) = &self.cfg.request_interceptor {
+            interceptor(invocation.to_mut());
+        }
+        let format = self.wire_format();
+        let req = self.http.post(self.endpoint("/v1/decide"));
+        // Shared with the serializer thread when the body is streamed.
+        let shared: Arc<ToolInvocation>;
+        let (req, invocation) = if self.streams_body(&invocation.request) {
+            shared = Arc::new(invocation.into_owned());
+            let body = wire::streaming_body(
+                self.cfg.request_envelope,
+                self.cfg.timestamp_format,
+                shared.clone(),
+                truncated,
+            );
+            let req = req.header(CONTENT_TYPE, "application/json").body(body);
+            let req = self.decide_headers(req, &shared.invocation_id, &shared.actor);
+            (req, &*shared)
+        } else {
+            let body = DecideBody::new(
+                self.cfg.request_envelope,
+                self.cfg.timestamp_format,
+                &invocation,
+                truncated,
+            );
+            (
+                self.encode_decide(req, &invocation, &body, format)?,
+                &*invocation,
+            )
+        };
+        let req = Self::with_auth(req, auth)?;
+        let shadow = self.start_shadow(&req);
+        let result = self
+            .exchange_decide(req, Some(&invocation.actor.workspace_id), |e| {
+                self.unavailable(invocation, e)
+            })
+            .await;
+        Self::finish_shadow(shadow, &result);
+        let result = match result {
+            Err(Error::SidecarError { status: 415, .. }) if format != WireFormat::Json => {
+                tracing::warn!(?format, "sidecar rejected binary wire format, using JSON");
+                self.binary_rejected.store(true, Ordering::Relaxed);
+                let body = DecideBody::new(
+                    self.cfg.request_envelope,
+                    self.cfg.timestamp_format,
+                    invocation,
+                    truncated,
+                );
+                let req = self.http.post(self.endpoint("/v1/decide"));
+                let req = self.encode_decide(req, invocation, &body, WireFormat::Json)?;
+                let req = Self::with_auth(req, auth)?;
+                self.exchange_decide(req, Some(&invocation.actor.workspace_id), |e| {
+                    self.unavailable(invocation, e)
+                })
+                .await
+            }
+            result => result,
+        };
+        if let (Some(cache), Some(fingerprint), Ok(record)) = (&self.cache, fingerprint, &result) {
+            cache.insert(fingerprint, record);
+        }
+        result
+    }
+
+    /// Send a copy of `req` to [`Config::shadow_url`] on a background task.
+    /// The returned sender takes the authoritative decision to compare
+    /// against; dropping it abandons the comparison.
+    fn start_shadow(
+        &self,
+        req: &reqwest::RequestBuilder,
+    ) -> Option<oneshot::Sender<DecisionRecord>> {
+        let shadow_url = self.cfg.shadow_url.as_deref()?;
+        // Streamed bodies cannot be cloned.
+        let mut request = req.try_clone()?.build().ok()?;
+        let url = self.endpoint_at(shadow_url, "/v1/decide");
+        *request.url_mut() = match url.parse() {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::warn!(url, error = %e, "invalid shadow sidecar URL");
+                return None;
+            }
+        };
+        *request.timeout_mut() = Some(self.cfg.timeout);
+        let http = self.http.clone();
+        let hook = self.cfg.on_shadow_divergence.clone();
+        let limit = self.cfg.max_response_bytes;
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let shadow = async {
+                let resp = http.execute(request).await?;
+                if !resp.status().is_success() {
+                    return Err(Self::sidecar_error(resp, limit).await);
+                }
+                let format = resp
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(WireFormat::from_content_type)
+                    .unwrap_or_default();
+                format.decode::<DecisionRecord>(&Self::read_limited(resp, limit).await?)
+            };
+            let shadow = match shadow.await {
+                Ok(shadow) => shadow,
+                Err(e) => {
+                    tracing::debug!(error = %e, "shadow decide failed");
+                    return;
+                }
+            };
+            let Ok(authoritative) = rx.await else {
+                return;
+            };
+            let differences: Vec<_> = ReplayDifference::between(&authoritative, &shadow)
+                .into_iter()
+                .filter(|d| !matches!(d, ReplayDifference::PolicyVersion { .. }))
+                .collect();
+            if differences.is_empty() {
+                return;
+            }
+            let divergence = ShadowDivergence {
+                authoritative,
+                shadow,
+                differences,
+            };
+            match hook {
+                Some(hook) => hook(&divergence),
+                None => tracing::info!(
+                    invocation_id = %divergence.authoritative.invocation_id,
+                    differences = ?divergence.differences,
+                    "shadow sidecar decision diverged"
+                ),
+            }
+        });
+        Some(tx)
+    }
+
+    /// Hand the authoritative decision to a [`start_shadow`](Self::start_shadow)
+    /// task. Locally resolved (degraded) decisions are not compared.
+    fn finish_shadow(
+        shadow: Option<oneshot::Sender<DecisionRecord>>,
+        result: &Result<DecisionRecord, Error>,
+    ) {
+        if let (Some(shadow), Ok(record)) = (shadow, result) {
+            if !record.degraded {
+                let _ = shadow.send(record.clone());
+            }
+        }
+    }
+
+    /// Send a prepared `decide` request and process the response.
+    /// `unavailable` resolves transport failures and empty decisions. The
+    /// decision's budgets are stored for `workspace_id`; dry runs pass `None`
+    /// to leave the budget state untouched.
+    async fn exchange_decide(
+        &self,
+        req: reqwest::RequestBuilder,
+        workspace_id: Option<&str>,
+        unavailable: impl FnOnce(Error) -> Result<DecisionRecord, Error>,
+    ) -> Result<DecisionRecord, Error> {
+        if let Some(err) = self.short_circuit() {
+            return unavailable(err);
+        }
+        let sent = self
+            .send_with_retry(req, |req| async {
+                if !self.cfg.log_bodies.enabled() {
+                    return req.send().await;
+                }
+                let (http, request) = req.build_split();
+                let request = request?;
+                body_log::log_request(
+                    self.cfg.log_bodies,
+                    &self.cfg.redact_param_keys,
+                    request.headers(),
+                    request.body().and_then(reqwest::Body::as_bytes),
+                );
+                http.execute(request).await
+            })
+            .await;
+        self.record_health(sent.is_ok());
+        match sent {
+            Err(e) => unavailable(Error::unreachable(e)),
+            Ok(resp) => {
+                self.check_api_version(resp.headers())?;
+                if !resp.status().is_success() {
+                    return Err(self.status_error(resp).await);
+                }
+                let request_id = Self::server_request_id(&resp);
+                let status = resp.status().as_u16();
+                let format = resp
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(WireFormat::from_content_type)
+                    .unwrap_or_default();
+                let body = self.read_body(resp).await?;
+                if self.cfg.log_bodies.enabled() {
+                    body_log::log_response(
+                        self.cfg.log_bodies,
+                        &self.cfg.redact_param_keys,
+                        status,
+                        &body,
+                    );
+                }
+                let mut record: DecisionRecord = if format == WireFormat::Json {
+                    if is_empty_decision(&body) {
+                        tracing::warn!(server_request_id = ?request_id, "sidecar returned an empty decision");
+                        return unavailable(Error::EmptyDecision);
+                    }
+                    serde_json::from_slice(&body)?
+                } else {
+                    let value: serde_json::Value = format.decode(&body)?;
+                    if value.is_null() || value.as_object().is_some_and(|o| o.is_empty()) {
+                        tracing::warn!(server_request_id = ?request_id, "sidecar returned an empty decision");
+                        return unavailable(Error::EmptyDecision);
+                    }
+                    serde_json::from_value(value)?
+                };
+                tracing::debug!(
+                    invocation_id = %record.invocation_id,
+                    decision = %record.decision,
+                    server_request_id = ?request_id,
+                    "decision received"
+                );
+                record.server_request_id = request_id;
+                self.check_policy_version(&record)?;
+                self.check_evidence(&record).await?;
+                if let Some(workspace_id) = workspace_id {
+                    self.store_budgets(workspace_id, &record.budgets);
+                }
+                Ok(record)
+            }
+        }
+    }
+
+    /// Send `req` with `send`, retrying per [`Config::retry`]. Requests
+    /// whose body cannot be cloned are sent once.
+    async fn send_with_retry<F, Fut>(
+        &self,
+        mut req: reqwest::RequestBuilder,
+        send: F,
+    ) -> Result<reqwest::Response, reqwest::Error>
+    where
+        F: Fn(reqwest::RequestBuilder) -> Fut,
+        Fut: Future<Output = Result<reqwest::Response, reqwest::Error>>,
+    {
+        let Some(policy) = &self.cfg.retry else {
+            return send(req).await;
+        };
+        let mut attempt = 1;
+        loop {
+            let retry = req.try_clone();
+            let result = send(req).await;
+            let (Some(retry), Some(delay)) = (retry, policy.delay_after(attempt, &result)) else {
+                return result;
+            };
+            match &result {
+                Ok(resp) => tracing::debug!(
+                    attempt,
+                    status = resp.status().as_u16(),
+                    ?delay,
+                    "retrying sidecar request"
+                ),
+                Err(e) => tracing::debug!(attempt, error = %e, ?delay, "retrying sidecar request"),
+            }
+            tokio::time::sleep(delay).await;
+            req = retry;
+            attempt += 1;
+        }
+    }
+
+    fn audit(&self, record: &DecisionRecord) {
+        if let Some(audit) = &self.audit {
+            audit.record(record);
+        }
+    }
+
+    /// Update the clock offset from a health response's `X-SkillGate-Time`
+    /// (RFC 3339) or, failing that, `Date` header. The local reference time is
+    /// the midpoint of the round trip. `Date` only has one-second resolution,
+    /// so offsets under two seconds derived from it are treated as zero.
+    fn record_server_time(&self, sent_at: DateTime<Utc>, headers: &HeaderMap) {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let (server_time, coarse) =
+            match header(SERVER_TIME_HEADER).and_then(|v| DateTime::parse_from_rfc3339(v).ok()) {
+                Some(t) => (t.with_timezone(&Utc), false),
+                None => match header("Date").and_then(|v| DateTime::parse_from_rfc2822(v).ok()) {
+                    Some(t) => (t.with_timezone(&Utc), true),
+                    None => return,
+                },
+            };
+        let received_at = Utc::now();
+        let local = sent_at + (received_at - sent_at) / 2;
+        let mut offset = (server_time - local).num_milliseconds();
+        if coarse && offset.abs() < 2_000 {
+            offset = 0;
+        }
+        self.clock_offset_ms.store(offset, Ordering::Relaxed);
+        tracing::debug!(offset_ms = offset, "sidecar clock offset updated");
+    }
+
+    /// Estimated sidecar clock minus local clock, as measured by the last
+    /// [`Client::health`] or [`Client::warmup`] with [`Config::sync_clock`]
+    /// enabled. Zero until measured.
+    pub fn clock_offset(&self) -> chrono::Duration {
+        chrono::Duration::milliseconds(self.clock_offset_ms.load(Ordering::Relaxed))
+    }
+
+    /// Current time corrected by [`Client::clock_offset`].
+    pub fn now(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset()
+    }
+
+    /// API version the sidecar last reported in `X-SkillGate-Api-Version`,
+    /// or `None` if no response has carried it yet.
+    pub fn server_api_version(&self) -> Option<String> {
+        self.server_api_version.lock().unwrap().clone()
+    }
+
+    /// Calls currently waiting in [`Config::queue`] for a
+    /// [`Config::max_concurrent`] permit. Always 0 without a queue.
+    pub fn queue_depth(&self) -> usize {
+        self.queue.as_ref().map_or(0, DecideQueue::depth)
+    }
+
+    /// Latency percentiles and fail-open rate over the last
+    /// [`Config::latency_window`] `decide` calls. All zero until a call
+    /// completes or when tracking is disabled.
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.stats()
+    }
+
+    /// Hit and miss counts of [`Config::cache`], or `None` without one.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(DecisionCache::stats)
+    }
+
+    /// The budgets mirrored per [`Config::budget_low_water`], or `None`
+    /// without it.
+    pub fn budget_tracker(&self) -> Option<&BudgetTracker> {
+        self.budget_tracker.as_ref()
+    }
+
+    /// State of [`Config::circuit_breaker`], or `None` without one.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.breaker.as_ref().map(|breaker| breaker.state())
+    }
+
+    /// Delivery counters for [`Config::degraded_audit`], or `None` when it is
+    /// not configured.
+    pub fn degraded_audit_stats(&self) -> Option<DegradedAuditStats> {
+        self.degraded_audit.as_ref().map(|a| a.stats())
+    }
+
+    /// Wait until every audit entry recorded so far has been written to
+    /// [`Config::audit_log_path`]. A no-op when no audit log is configured.
+    pub async fn flush_audit_log(&self) -> Result<(), Error> {
+        match &self.audit {
+            Some(audit) => audit.flush().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Take in budgets fresh from the sidecar: the last-known-good snapshot
+    /// for degraded decisions and the [`BudgetTracker`].
+    fn store_budgets(&self, workspace_id: &str, budgets: &HashMap<String, BudgetStatus>) {
+        if let Some(tracker) = &self.budget_tracker {
+            tracker.observe(budgets);
+        }
+        if self.cfg.budget_snapshot_ttl.is_none() || budgets.is_empty() {
+            return;
+        }
+        self.budget_snapshots
+            .lock()
+            .unwrap()
+            .insert(workspace_id.to_string(), (Instant::now(), budgets.clone()));
+    }
+
+    fn apply_budget_snapshot(&self, workspace_id: &str, record: &mut DecisionRecord) {
+        let Some(ttl) = self.cfg.budget_snapshot_ttl else {
+            return;
+        };
+        let snapshots = self.budget_snapshots.lock().unwrap();
+        if let Some((taken_at, budgets)) = snapshots.get(workspace_id) {
+            if taken_at.elapsed() <= ttl {
+                record.budgets = budgets.clone();
+                record.extra.insert("budgets_stale".into(), true.into());
+                record.extra.insert(
+                    "budgets_age_ms".into(),
+                    (taken_at.elapsed().as_millis() as u64).into(),
+                );
+            }
+        }
+    }
+
+    /// Ask whether the configured agent may use `capability` in `repo` right
+    /// now, returning `true` for an ALLOW (including a fail-open degraded
+    /// one).
+    ///
+    /// The invocation is built from [`Config::invocation_defaults`], with a
+    /// tool named after the capability and no params.
+    pub async fn check_capability(&self, capability: &str, repo: &str) -> Result<bool, Error> {
+        let defaults = &self.cfg.invocation_defaults;
+        let tool = Tool {
+            name: capability.to_string(),
+            provider: defaults.tool_provider.clone(),
+            capabilities: vec![capability.to_string()],
+            risk_class: defaults.risk_class.clone(),
+        };
+        let context = ExecutionContext {
+            repo: repo.to_string(),
+            ..defaults.context.clone()
+        };
+        let invocation = self.default_invocation(tool, ToolRequest::default(), context);
+        Ok(self.decide(invocation).await?.is_allowed())
+    }
+
+    /// Decide a call made by the configured default actor and agent
+    /// ([`Config::invocation_defaults`]) in `context`, e.g. when one process
+    /// runs the same tool against several environments.
+    pub async fn decide_with_context_override(
+        &self,
+        tool: Tool,
+        request: ToolRequest,
+        context: ExecutionContext,
+    ) -> Result<DecisionRecord, Error> {
+        self.decide(self.default_invocation(tool, request, context))
+            .await
+    }
+
+    /// A fresh invocation from [`Config::invocation_defaults`]' actor and agent.
+    fn default_invocation(
+        &self,
+        tool: Tool,
+        request: ToolRequest,
+        context: ExecutionContext,
+    ) -> ToolInvocation {
+        let defaults = &self.cfg.invocation_defaults;
+        ToolInvocation {
+            invocation_id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            actor: defaults.actor.clone(),
+            agent: defaults.agent.clone(),
+            tool,
+            request,
+            context,
+        }
+    }
+
+    /// Run [`Client::decide`] for each invocation, at most `concurrency`
+    /// (minimum 1) at a time, returning results in input order.
+    ///
+    /// Uses the single-decision endpoint, so it works against sidecars
+    /// without a batch route.
+    pub async fn decide_many(
+        &self,
+        invocations: impl IntoIterator<Item = ToolInvocation>,
+        concurrency: usize,
+    ) -> Vec<Result<DecisionRecord, Error>> {
+        let mut results: Vec<_> = stream::iter(invocations.into_iter().enumerate())
+            .map(|(i, invocation)| async move { (i, self.decide(invocation).await) })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        results.sort_unstable_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Decide several invocations with one `POST /v1/decide/batch`, asking
+    /// the sidecar for per-item results so that one bad invocation does not
+    /// fail the rest.
+    ///
+    /// Returns one result per invocation, in input order. Items the sidecar
+    /// rejects fail with [`Error::BatchItem`], and items the client rejects
+    /// (e.g. [`Config::max_resource_refs`]) are not sent; either way the
+    /// error is wrapped in [`Error::Request`] like a [`Client::decide`]
+    /// failure. If the sidecar is unreachable, every item is resolved as in
+    /// [`Client::decide`] (offline bundle, fail-open). The outer error is for
+    /// failures of the batch as a whole: an error status, a malformed
+    /// response envelope, the rate limit or the queue.
+    ///
+    /// The batch takes a single rate-limit token and concurrency permit.
+    /// Bodies are never streamed.
+    pub async fn decide_batch_partial(
+        &self,
+        mut invocations: Vec<ToolInvocation>,
+    ) -> Result<Vec<Result<DecisionRecord, Error>>, Error> {
+        let started = Instant::now();
+        self.check_slt_expiry()?;
+        self.take_rate_limit_token().await?;
+        let mut results: Vec<Option<Result<DecisionRecord, Error>>> =
+            invocations.iter().map(|_| None).collect();
+        let _permit = match self.acquire_permit().await {
+            Err(Error::QueueFull) if self.queue_fails_open() => {
+                for (invocation, result) in invocations.iter().zip(&mut results) {
+                    *result = Some(Ok(Self::queue_overflow_allow(&invocation.invocation_id)));
+                }
+                None
+            }
+            permit => permit?,
+        };
+
+        // Positions of the invocations that made it into the request body.
+        let mut sent = Vec::with_capacity(invocations.len());
+        let mut bodies = Vec::with_capacity(invocations.len());
+        for (index, invocation) in invocations.iter_mut().enumerate() {
+            if results[index].is_some() {
+                continue;
+            }
+            if self.cfg.sync_clock {
+                invocation.timestamp += self.clock_offset();
+            }
+            if let Some(interceptor) = &self.cfg.request_interceptor {
+                interceptor(invocation);
+            }
+            match self.decide_body(invocation) {
+                Ok(body) => {
+                    sent.push(index);
+                    bodies.push(body);
+                }
+                Err(err) => results[index] = Some(Err(err)),
+            }
+        }
+
+        if let Some(&first) = sent.first() {
+            let first = &invocations[first];
+            let body = serde_json::json!({ "invocations": bodies, "partial": true });
+            let req = self.with_body(self.http.post(self.endpoint("/v1/decide/batch")), &body);
+            let req = self.decide_headers(req, &first.invocation_id, &first.actor);
+            match self.exchange_batch(req, sent.len()).await {
+                Ok((request_id, items)) => {
+                    for (&index, item) in sent.iter().zip(items) {
+                        let invocation = &invocations[index];
+                        let result =
+                            self.batch_item(index, item, invocation)
+                                .await
+                                .map(|mut record| {
+                                    record.server_request_id.clone_from(&request_id);
+                                    record
+                                });
+                        results[index] = Some(result);
+                    }
+                }
+                Err(Error::EnforcerUnavailable { reason, .. }) => {
+                    for &index in &sent {
+                        let err = Error::EnforcerUnavailable {
+                            reason: reason.clone(),
+                            source: None,
+                        };
+                        results[index] = Some(self.unavailable(&invocations[index], err));
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(invocations
+            .into_iter()
+            .zip(results)
+            .map(|(invocation, result)| {
+                let result = result.expect("every batch item is resolved");
+                self.finish_decide(
+                    result,
+                    started,
+                    invocation.invocation_id,
+                    invocation.actor.session_id,
+                )
+            })
+            .collect())
+    }
+
+    /// Decide several invocations at once, for planners that produce many
+    /// tool calls in one step. Returns one result per invocation, in input
+    /// order.
+    ///
+    /// Sends a single [`Client::decide_batch_partial`] request. If the
+    /// sidecar has no batch route (404, 405 or 501), the invocations are
+    /// decided with concurrent single calls instead, as in
+    /// [`Client::decide_many`], and so are all later batches of this client.
+    /// Any other failure of the batch as a whole fails every item with it.
+    pub async fn decide_batch(
+        &self,
+        invocations: Vec<ToolInvocation>,
+    ) -> Vec<Result<DecisionRecord, Error>> {
+        let concurrency = invocations.len();
+        if self.batch_unsupported.load(Ordering::Relaxed) {
+            return self.decide_many(invocations, concurrency).await;
+        }
+        // `decide_batch_partial` adjusts the invocations it sends, so the
+        // fallback starts from the originals.
+        match self.decide_batch_partial(invocations.clone()).await {
+            Ok(results) => results,
+            Err(err) if matches!(err.status(), Some(404 | 405 | 501)) => {
+                tracing::info!(
+                    status = err.status(),
+                    "sidecar has no batch route, deciding one at a time"
+                );
+                self.batch_unsupported.store(true, Ordering::Relaxed);
+                self.decide_many(invocations, concurrency).await
+            }
+            Err(err) => invocations
+                .into_iter()
+                .enumerate()
+                .map(|(index, invocation)| {
+                    let session_id = invocation.actor.session_id;
+                    Err(Error::Request {
+                        invocation_id: invocation.invocation_id,
+                        session_id: (!session_id.is_empty()).then_some(session_id),
+                        source: Box::new(Self::batch_failure(&err, index)),
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// A copy of `err`, a failure of the whole batch, for the item at
+    /// `index`. Errors that cannot be copied are reported as
+    /// [`Error::BatchItem`] with their message.
+    fn batch_failure(err: &Error, index: usize) -> Error {
+        match err {
+            Error::SidecarError {
+                status,
+                body,
+                headers,
+            } => Error::SidecarError {
+                status: *status,
+                body: body.clone(),
+                headers: headers.clone(),
+            },
+            Error::SltExpired { expired_at } => Error::SltExpired {
+                expired_at: *expired_at,
+            },
+            Error::RateLimited { retry_after } => Error::RateLimited {
+                retry_after: *retry_after,
+            },
+            Error::QueueFull => Error::QueueFull,
+            Error::ApiVersionUnsupported { got, supported } => Error::ApiVersionUnsupported {
+                got: got.clone(),
+                supported: *supported,
+            },
+            err => Error::BatchItem {
+                index,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    /// Send a partial `/v1/decide/batch` request and split the response
+    /// into its `expected` items. Returns the server request id alongside.
+    async fn exchange_batch(
+        &self,
+        req: reqwest::RequestBuilder,
+        expected: usize,
+    ) -> Result<(Option<String>, Vec<BatchResult>), Error> {
+        #[derive(Deserialize)]
+        struct BatchResponse {
+            results: Vec<BatchResult>,
+        }
+
+        if let Some(err) = self.short_circuit() {
+            return Err(err);
+        }
+        let sent = req.send().await;
+        self.record_health(sent.is_ok());
+        let resp = sent.map_err(Error::unreachable)?;
+        self.check_api_version(resp.headers())?;
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        let request_id = Self::server_request_id(&resp);
+        let results = self.read_json::<BatchResponse>(resp).await?.results;
+        if results.len() != expected {
+            return Err(Error::Json(serde::de::Error::custom(format!(
+                "batch response has {} results for {expected} invocations",
+                results.len()
+            ))));
+        }
+        Ok((request_id, results))
+    }
+
+    /// Resolve one item of a partial batch response for the invocation at
+    /// `index`.
+    async fn batch_item(
+        &self,
+        index: usize,
+        item: BatchResult,
+        invocation: &ToolInvocation,
+    ) -> Result<DecisionRecord, Error> {
+        let record = match item {
+            BatchResult::Error(error) => {
+                let message = match &error {
+                    serde_json::Value::String(message) => message.clone(),
+                    _ => match error.get("message").and_then(|m| m.as_str()) {
+                        Some(message) => message.to_string(),
+                        None => error.to_string(),
+                    },
+                };
+                return Err(Error::BatchItem { index, message });
+            }
+            BatchResult::Ok(record) => record,
+        };
+        if record.is_null() || record.as_object().is_some_and(|o| o.is_empty()) {
+            return self.unavailable(invocation, Error::EmptyDecision);
+        }
+        let record: DecisionRecord = serde_json::from_value(record)?;
+        self.check_policy_version(&record)?;
+        self.check_evidence(&record).await?;
+        self.store_budgets(&invocation.actor.workspace_id, &record.budgets);
+        Ok(record)
+    }
+
+    /// Like [`Client::decide`], but never fails: any [`enum@Error`] is turned into
+    /// a synthetic, `degraded` DENY with decision code `SG_DENY_CLIENT_ERROR`
+    /// and the error text under `extra["client_error"]`. A degraded ALLOW from
+    /// `fail_open` is still returned as-is.
+    pub async fn decide_or_deny(&self, invocation: ToolInvocation) -> DecisionRecord {
+        let invocation_id = invocation.invocation_id.clone();
+        // Kept only for the degrade hook, which needs the whole invocation.
+        let for_hook = self.cfg.on_degrade.is_some().then(|| invocation.clone());
+        match self.decide(invocation).await {
+            Ok(record) => record,
+            Err(err) => {
+                tracing::debug!(invocation_id, error = %err, "decide failed, denying");
+                // Failures resolved in `unavailable` already ran the hook.
+                if let Some(invocation) = &for_hook {
+                    if !matches!(
+                        err.root(),
+                        Error::EnforcerUnavailable { .. } | Error::EmptyDecision
+                    ) {
+                        self.degrade(invocation, &err);
+                    }
+                }
+                let record = Self::client_error_deny(&invocation_id, &err);
+                self.audit(&record);
+                record
+            }
+        }
+    }
+
+    /// Like [`Client::decide`], but never fails: when neither
+    /// [`Config::cache`] nor the sidecar has a decision, `fallback` supplies
+    /// one. It is called for any [`enum@Error`] and in place of the generic
+    /// degraded ALLOW of `fail_open` (including queue overflow), so each
+    /// call site can set its own degradation policy. Offline bundle
+    /// decisions are still returned as-is.
+    pub async fn decide_cached_or(
+        &self,
+        invocation: ToolInvocation,
+        fallback: impl FnOnce() -> DecisionRecord,
+    ) -> DecisionRecord {
+        let invocation_id = invocation.invocation_id.clone();
+        match self.decide(invocation).await {
+            Ok(record) if record.decision_code != FAIL_OPEN_CODE => record,
+            Ok(_) => {
+                tracing::debug!(
+                    invocation_id,
+                    "no decision from the sidecar, using fallback"
+                );
+                fallback()
+            }
+            Err(err) => {
+                tracing::debug!(invocation_id, error = %err, "decide failed, using fallback");
+                fallback()
+            }
+        }
+    }
+
+    /// Decide an invocation given as raw JSON, for callers that assemble it
+    /// outside Rust or need fields [`ToolInvocation`] does not model.
+    ///
+    /// `invocation` is sent as-is inside the configured
+    /// [`Config::request_envelope`], except that params listed in
+    /// [`Config::redact_param_keys`] are masked. `invocation_id`,
+    /// `actor.workspace_id` and `actor.session_id` are read from it for
+    /// headers and error context. Auth, signing, rate limiting, the
+    /// concurrency limit, the audit log and latency tracking behave as in
+    /// [`Client::decide`]; param and resource-ref limits, clock sync,
+    /// timestamp format and the request interceptor do not apply.
+    ///
+    /// When the sidecar is unreachable, an invocation that parses as a
+    /// [`ToolInvocation`] is resolved exactly like [`Client::decide`]
+    /// (offline bundle, fail-open, degrade hook). Anything else can only fail
+    /// open or closed.
+    pub async fn decide_json(
+        &self,
+        mut invocation: serde_json::Value,
+    ) -> Result<DecisionRecord, Error> {
+        let started = Instant::now();
+        let text = |pointer| {
+            invocation
+                .pointer(pointer)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let invocation_id = text("/invocation_id");
+        let session_id = text("/actor/session_id");
+        let result =
+            async {
+                if !invocation.is_object() {
+                    return Err(Error::Json(serde::de::Error::custom(
+                        "invocation must be a JSON object",
+                    )));
+                }
+                self.check_slt_expiry()?;
+                self.take_rate_limit_token().await?;
+                let _permit = match self.acquire_permit().await {
+                    Err(Error::QueueFull) if self.queue_fails_open() => {
+                        return Ok(Self::queue_overflow_allow(&invocation_id));
+                    }
+                    permit => permit?,
+                };
+                if let Some(serde_json::Value::Object(params)) =
+                    invocation.pointer_mut("/request/params")
+                {
+                    self.redact_params(params.iter_mut());
+                }
+                let actor: Actor = invocation
+                    .get("actor")
+                    .and_then(|actor| serde_json::from_value(actor.clone()).ok())
+                    .unwrap_or_default();
+                let body = match self.cfg.request_envelope {
+                    Envelope::Legacy => serde_json::json!({
+                        "invocation_id": invocation_id,
+                        "tool_invocation": invocation,
+                    }),
+                    Envelope::Flat => invocation.clone(),
+                };
+                let req = self.with_body(self.http.post(self.endpoint("/v1/decide")), &body);
+                let req = self.decide_headers(req, &invocation_id, &actor);
+                self.exchange_decide(req, Some(&actor.workspace_id), |e| {
+                    match serde_json::from_value::<ToolInvocation>(invocation.clone()) {
+                        Ok(typed) => self.unavailable(&typed, e),
+                        Err(_) if self.cfg.fail_open => Ok(Self::degraded_allow(&invocation_id)),
+                        Err(_) => Err(e),
+                    }
+                })
+                .await
+            }
+            .await;
+        self.finish_decide(result, started, invocation_id, session_id)
+    }
+
+    /// Stream interim and final decisions for an invocation from
+    /// `/v1/decide/stream` (server-sent events).
+    ///
+    /// Yields every record the sidecar emits, e.g. one or more `PENDING`
+    /// records followed by the terminal decision, after which the stream ends.
+    /// The whole exchange is bounded by [`Config::stream_timeout`] rather than
+    /// the per-request `timeout`. The terminal item is finished as in
+    /// [`Client::decide`]: rate limit, `max_concurrent` permit, latency
+    /// sample, audit and [`Error::Request`] wrapping. Sidecar unavailability,
+    /// including a connection that drops or closes before the terminal
+    /// decision, is resolved exactly as in [`Client::decide`] and ends the
+    /// stream with a single resolved item.
+    pub fn decide_stream(
+        &self,
+        mut invocation: ToolInvocation,
+    ) -> impl Stream<Item = Result<DecisionRecord, Error>> + '_ {
+        let started = Instant::now();
+        let body = self
+            .check_slt_expiry()
+            .and_then(|()| self.decide_body(&mut invocation));
+        let req = body.map(|body| {
+            let req = self
+                .http
+                .post(self.endpoint("/v1/decide/stream"))
+                .timeout(self.cfg.stream_timeout)
+                .header("Accept", "text/event-stream");
+            self.decide_request(req, &invocation, &body)
+        });
+
+        struct Open<'a> {
+            resp: reqwest::Response,
+            parser: SseParser,
+            queue: VecDeque<DecisionRecord>,
+            invocation: ToolInvocation,
+            _permit: Option<SemaphorePermit<'a>>,
+        }
+
+        enum State<'a> {
+            Start(Box<(Result<reqwest::RequestBuilder, Error>, ToolInvocation)>),
+            Open(Box<Open<'a>>),
+            Done,
+        }
+
+        stream::unfold(
+            State::Start(Box::new((req, invocation))),
+            move |state| async move {
+                let finish = |result, invocation: &ToolInvocation| {
+                    self.finish_decide(
+                        result,
+                        started,
+                        invocation.invocation_id.clone(),
+                        invocation.actor.session_id.clone(),
+                    )
+                };
+                let mut open = match state {
+                    State::Done => return None,
+                    State::Open(open) => open,
+                    State::Start(start) => {
+                        let (req, invocation) = *start;
+                        // `Err` carries the single, terminal result.
+                        let opened = async {
+                            let req = req.map_err(Err)?;
+                            self.take_rate_limit_token().await.map_err(Err)?;
+                            let permit = match self.acquire_permit().await {
+                                Err(Error::QueueFull) if self.queue_fails_open() => {
+                                    return Err(Ok(Self::queue_overflow_allow(
+                                        &invocation.invocation_id,
+                                    )));
+                                }
+                                permit => permit.map_err(Err)?,
+                            };
+                            match req.send().await {
+                                Err(e) => Err(self.unavailable(&invocation, Error::unreachable(e))),
+                                Ok(resp) if !resp.status().is_success() => {
+                                    Err(Err(self.status_error(resp).await))
+                                }
+                                Ok(resp) => Ok((resp, permit)),
+                            }
+                        }
+                        .await;
+                        match opened {
+                            Ok((resp, permit)) => Box::new(Open {
+                                resp,
+                                parser: SseParser::default(),
+                                queue: VecDeque::new(),
+                                invocation,
+                                _permit: permit,
+                            }),
+                            Err(result) => return Some((finish(result, &invocation), State::Done)),
+                        }
+                    }
+                };
+                let request_id = Self::server_request_id(&open.resp);
+                loop {
+                    if let Some(record) = open.queue.pop_front() {
+                        if record.decision_typed() == Decision::Pending {
+                            return Some((Ok(record), State::Open(open)));
+                        }
+                        let result = self.check_evidence(&record).await.map(|()| {
+                            self.store_budgets(
+                                &open.invocation.actor.workspace_id,
+                                &record.budgets,
+                            );
+                            record
+                        });
+                        return Some((finish(result, &open.invocation), State::Done));
+                    }
+                    let err = match open.resp.chunk().await {
+                        Ok(Some(chunk)) => {
+                            let events = open.parser.push(&chunk);
+                            if let Some(limit) = self.cfg.max_response_bytes {
+                                if open.parser.pending_len() > limit {
+                                    let err = Error::ResponseTooLarge { limit };
+                                    return Some((finish(Err(err), &open.invocation), State::Done));
+                                }
+                            }
+                            for event in events {
+                                let record = serde_json::from_str::<DecisionRecord>(&event.data)
+                                    .map_err(Error::from)
+                                    .and_then(|mut record| {
+                                        record.server_request_id = request_id.clone();
+                                        self.check_policy_version(&record)?;
+                                        Ok(record)
+                                    });
+                                match record {
+                                    Ok(record) => open.queue.push_back(record),
+                                    Err(e) => {
+                                        return Some((
+                                            finish(Err(e), &open.invocation),
+                                            State::Done,
+                                        ))
+                                    }
+                                }
+                            }
+                            continue;
+                        }
+                        Ok(None) => {
+                            tracing::warn!(server_request_id = ?request_id, "decision stream ended without a terminal decision");
+                            Error::EmptyDecision
+                        }
+                        Err(e) => Error::unreachable(e),
+                    };
+                    let result = self.unavailable(&open.invocation, err);
+                    return Some((finish(result, &open.invocation), State::Done));
+                }
+            },
+        )
+    }
+
+    /// Follow the workspace's decision and audit events from `/v1/events`
+    /// (server-sent events).
+    ///
+    /// Unlike [`Client::decide_stream`] the subscription is not tied to an
+    /// invocation and does not end on its own. When the connection drops, or
+    /// stays silent for [`Config::stream_timeout`], the client reconnects
+    /// after a backoff (100 ms doubling up to 5 s, reset once events flow
+    /// again) and sends the last seen event id as `Last-Event-ID` so no
+    /// events are missed. Connection failures and 429/5xx responses are
+    /// retried; any other error status is yielded and ends the stream.
+    pub fn subscribe_events<'a>(
+        &'a self,
+        workspace_id: &str,
+    ) -> impl Stream<Item = Result<AuditEvent, Error>> + 'a {
+        struct State {
+            workspace_id: String,
+            conn: Option<(reqwest::Response, SseParser)>,
+            queue: VecDeque<AuditEvent>,
+            last_event_id: Option<String>,
+            delay: Duration,
+            reconnecting: bool,
+        }
+
+        const INITIAL_DELAY: Duration = Duration::from_millis(100);
+        const MAX_DELAY: Duration = Duration::from_secs(5);
+
+        let state = State {
+            workspace_id: workspace_id.to_string(),
+            conn: None,
+            queue: VecDeque::new(),
+            last_event_id: None,
+            delay: INITIAL_DELAY,
+            reconnecting: false,
+        };
+        stream::unfold(Some(state), move |state| async move {
+            let mut state = state?;
+            loop {
+                if let Some(event) = state.queue.pop_front() {
+                    return Some((Ok(event), Some(state)));
+                }
+                let Some((resp, parser)) = &mut state.conn else {
+                    if state.reconnecting {
+                        tokio::time::sleep(state.delay).await;
+                        state.delay = (state.delay * 2).min(MAX_DELAY);
+                    }
+                    state.reconnecting = true;
+                    let mut req = self
+                        .http
+                        .get(self.endpoint("/v1/events"))
+                        .query(&[("workspace_id", &state.workspace_id)])
+                        // The stream is long-lived; liveness is checked per
+                        // chunk below instead of over the whole response.
+                        .timeout(Duration::MAX)
+                        .header("Accept", "text/event-stream");
+                    if let Some(auth) = self.auth_header() {
+                        req = req.header(AUTHORIZATION, auth);
+                    }
+                    if let Some(id) = &state.last_event_id {
+                        req = req.header("Last-Event-ID", id);
+                    }
+                    match tokio::time::timeout(self.cfg.timeout, req.send()).await {
+                        Ok(Ok(resp)) if resp.status().is_success() => {
+                            state.conn = Some((resp, SseParser::default()));
+                        }
+                        Ok(Ok(resp)) => {
+                            let err = self.status_error(resp).await;
+                            let retry = matches!(err.status(), Some(429 | 500..=599));
+                            if !retry {
+                                return Some((Err(err), None));
+                            }
+                            tracing::debug!(error = %err, "event subscription rejected, retrying");
+                        }
+                        Ok(Err(e)) => {
+                            tracing::debug!(error = %e, "event subscription failed, retrying");
+                        }
+                        Err(_) => tracing::debug!("event subscription timed out, retrying"),
+                    }
+                    continue;
+                };
+                match tokio::time::timeout(self.cfg.stream_timeout, resp.chunk()).await {
+                    Ok(Ok(Some(chunk))) => {
+                        let events = parser.push(&chunk);
+                        if let Some(limit) = self.cfg.max_response_bytes {
+                            if parser.pending_len() > limit {
+                                return Some((Err(Error::ResponseTooLarge { limit }), None));
+                            }
+                        }
+                        for event in events {
+                            if event.id.is_some() {
+                                state.last_event_id.clone_from(&event.id);
+                            }
+                            let data = serde_json::from_str(&event.data)
+                                .unwrap_or(serde_json::Value::String(event.data));
+                            state.queue.push_back(AuditEvent {
+                                id: event.id,
+                                kind: event.event.unwrap_or_else(|| "message".into()),
+                                data,
+                            });
+                            state.delay = INITIAL_DELAY;
+                        }
+                    }
+                    Ok(Ok(None)) => {
+                        tracing::debug!("event stream closed, reconnecting");
+                        state.conn = None;
+                    }
+                    Ok(Err(e)) => {
+                        tracing::debug!(error = %e, "event stream failed, reconnecting");
+                        state.conn = None;
+                    }
+                    Err(_) => {
+                        tracing::debug!("event stream idle, reconnecting");
+                        state.conn = None;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Re-send a recorded invocation as a dry run ([`DRY_RUN_HEADER`]) and
+    /// compare the sidecar's decision with `expected`, e.g. to check that a
+    /// historical decision still holds under the current policy.
+    ///
+    /// The invocation is sent unchanged apart from redaction and
+    /// resource-ref limits, with its original id and timestamp. The replay
+    /// is not audited, its budgets do not reach [`Client::budget_tracker`],
+    /// and an unreachable sidecar is always an error: neither the offline
+    /// bundle nor `fail_open` apply.
+    pub async fn replay(
+        &self,
+        invocation: &ToolInvocation,
+        expected: &DecisionRecord,
+    ) -> Result<ReplayResult, Error> {
+        let mut invocation = invocation.clone();
+        let body = self.decide_body(&mut invocation)?;
+        let req = self
+            .http
+            .post(self.endpoint("/v1/decide"))
+            .header(DRY_RUN_HEADER, "true");
+        let replayed = self
+            .exchange_decide(self.decide_request(req, &invocation, &body), None, Err)
+            .await?;
+        Ok(ReplayResult {
+            differences: ReplayDifference::between(expected, &replayed),
+            replayed,
+        })
+    }
+
+    /// Fetch the sidecar's explanation for a previously decided invocation.
+    pub async fn explain(&self, invocation_id: &str) -> Result<DecisionExplanation, Error> {
+        let mut req =
+            self.http
+                .get(self.endpoint_segments(&["v1", "decide", invocation_id, "explain"]));
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        if let Some(language) = &self.cfg.accept_language {
+            req = req.header(ACCEPT_LANGUAGE, language);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        self.read_json(resp).await
+    }
+
+    /// List the tools registered with the sidecar via `GET /v1/registry`.
+    ///
+    /// The response's `ETag` is remembered and sent as `If-None-Match` on the
+    /// next call, so an unchanged registry costs a 304 and yields
+    /// [`RegistryListing::Unchanged`] instead of the full list.
+    pub async fn list_tools(&self) -> Result<RegistryListing, Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Listing {
+            Wrapped { tools: Vec<ToolSummary> },
+            Bare(Vec<ToolSummary>),
+        }
+
+        let mut req = self.http.get(self.endpoint("/v1/registry"));
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        let etag = self.registry_etag.lock().unwrap().clone();
+        if let Some(etag) = etag {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
+
+        let resp = req.send().await?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(RegistryListing::Unchanged);
+        }
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        let etag = resp.headers().get(ETAG).cloned();
+        let tools = match self.read_json(resp).await? {
+            Listing::Wrapped { tools } | Listing::Bare(tools) => tools,
+        };
+        // Only remember the tag once the body it describes has been read.
+        *self.registry_etag.lock().unwrap() = etag;
+        Ok(RegistryListing::Changed(tools))
+    }
+
+    /// Decision counts and budget consumption for `session_id` from
+    /// `/v1/sessions/{session_id}`. Fails with [`Error::NotFound`] for a
+    /// session the sidecar does not know.
+    pub async fn session_summary(&self, session_id: &str) -> Result<SessionSummary, Error> {
+        let mut req = self
+            .http
+            .get(self.endpoint_segments(&["v1", "sessions", session_id]));
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(format!("session {session_id}")));
+        }
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        self.read_json(resp).await
+    }
+
+    /// Current budgets of `workspace_id` by capability, from
+    /// `/v1/budgets`, e.g. to throttle a plan before any of it is decided.
+    /// Fails with [`Error::NotFound`] for a workspace the sidecar does not
+    /// know.
+    ///
+    /// The result also refreshes the last-known-good budgets used by
+    /// degraded decisions ([`Config::budget_snapshot_ttl`]).
+    pub async fn get_budgets(
+        &self,
+        workspace_id: &str,
+    ) -> Result<HashMap<String, BudgetStatus>, Error> {
+        #[derive(Deserialize)]
+        struct BudgetsResponse {
+            budgets: HashMap<String, BudgetStatus>,
+        }
+
+        let mut req = self
+            .http
+            .get(self.endpoint("/v1/budgets"))
+            .query(&[("workspace_id", workspace_id)]);
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(format!("workspace {workspace_id}")));
+        }
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        let budgets = self.read_json::<BudgetsResponse>(resp).await?.budgets;
+        self.store_budgets(workspace_id, &budgets);
+        Ok(budgets)
+    }
+
+    /// Ask whether `capability` has `cost` units of budget left for the
+    /// workspace of [`Config::invocation_defaults`], via
+    /// `POST /v1/budgets/check`. Nothing is consumed; a later decision may
+    /// still be denied if the budget is spent in between.
+    pub async fn check_budget(&self, capability: &str, cost: u64) -> Result<BudgetCheck, Error> {
+        let body = serde_json::json!({
+            "workspace_id": self.cfg.invocation_defaults.actor.workspace_id,
+            "capability": capability,
+            "cost": cost,
+        });
+        let mut req = self
+            .http
+            .post(self.endpoint("/v1/budgets/check"))
+            .json(&body);
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        self.read_json(resp).await
+    }
+
+    /// Register or update a tool AI-BOM in the sidecar registry.
+    /// Best-effort — returns `false` on any connectivity failure.
+    pub async fn register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool {
+        self.try_register_tool(tool_name, metadata).await.is_ok()
+    }
+
+    /// Like [`Client::register_tool`], but keeps retrying with exponential
+    /// backoff (100 ms doubling up to 2 s) until the sidecar accepts the
+    /// registration or `max_wait` has elapsed. Returns the last error on
+    /// timeout. Useful at agent startup when the sidecar may still be booting.
+    pub async fn register_tool_with_retry(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+        max_wait: Duration,
+    ) -> Result<(), Error> {
+        let deadline = tokio::time::Instant::now() + max_wait;
+        let mut delay = Duration::from_millis(100);
+        loop {
+            let err = match self.try_register_tool(tool_name, metadata).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(err);
+            }
+            tracing::debug!(tool_name, error = %err, "tool registration failed, retrying");
+            tokio::time::sleep(delay.min(deadline - now)).await;
+            delay = (delay * 2).min(Duration::from_secs(2));
+        }
+    }
+
+    /// Register many tools in one round-trip via `/v1/registry/bulk`,
+    /// returning `(tool_name, registered)` pairs in input order.
+    ///
+    /// Sidecars without the bulk endpoint (404) are handled by falling back to
+    /// sequential [`Client::register_tool`] calls.
+    pub async fn register_tools(
+        &self,
+        boms: &[(String, HashMap<String, serde_json::Value>)],
+    ) -> Result<Vec<(String, bool)>, Error> {
+        #[derive(Deserialize)]
+        struct BulkResult {
+            name: String,
+            ok: bool,
+        }
+        #[derive(Deserialize)]
+        struct BulkResponse {
+            results: Vec<BulkResult>,
+        }
+
+        let tools: Vec<_> = boms
+            .iter()
+            .map(|(name, metadata)| serde_json::json!({"name": name, "metadata": metadata}))
+            .collect();
+        let mut req = self
+            .http
+            .post(self.endpoint("/v1/registry/bulk"))
+            .json(&serde_json::json!({ "tools": tools }));
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            tracing::debug!("bulk registry endpoint unavailable, registering sequentially");
+            let mut results = Vec::with_capacity(boms.len());
+            for (name, metadata) in boms {
+                results.push((name.clone(), self.register_tool(name, metadata).await));
+            }
+            return Ok(results);
+        }
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        let outcome: HashMap<String, bool> = self
+            .read_json::<BulkResponse>(resp)
+            .await?
+            .results
+            .into_iter()
+            .map(|r| (r.name, r.ok))
+            .collect();
+        Ok(boms
+            .iter()
+            .map(|(name, _)| (name.clone(), outcome.get(name).copied().unwrap_or(false)))
+            .collect())
+    }
+
+    /// Upload the content behind a `resource_ref` so policies can inspect it,
+    /// then refer to it by `ref_id` from any number of `decide` calls.
+    ///
+    /// The body's SHA-256 is sent in `X-SkillGate-Content-Sha256`. Uploading
+    /// the same content under the same id again is a no-op for this client.
+    pub async fn upload_resource(
+        &self,
+        ref_id: &str,
+        content: &[u8],
+        content_type: &str,
+    ) -> Result<(), Error> {
+        let digest = hex::encode(Sha256::digest(content));
+        if self.uploaded_resources.lock().unwrap().get(ref_id) == Some(&digest) {
+            tracing::debug!(ref_id, "resource content unchanged, skipping upload");
+            return Ok(());
+        }
+        let mut req = self
+            .http
+            .put(self.endpoint_segments(&["v1", "resources", ref_id]))
+            .header(CONTENT_TYPE, content_type)
+            .header(CONTENT_SHA256_HEADER, &digest)
+            .body(content.to_vec());
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        self.uploaded_resources
+            .lock()
+            .unwrap()
+            .insert(ref_id.to_string(), digest);
+        Ok(())
+    }
+
+    /// Ask the sidecar to POST the outcome of a pending `REQUIRE_APPROVAL`
+    /// decision to `callback_url` instead of being polled. Verify deliveries
+    /// with [`verify_callback_signature`].
+    pub async fn register_approval_callback(
+        &self,
+        invocation_id: &str,
+        callback_url: &str,
+    ) -> Result<(), Error> {
+        let mut req = self
+            .http
+            .post(self.endpoint_segments(&["v1", "approvals", invocation_id, "callback"]))
+            .json(&serde_json::json!({ "callback_url": callback_url }));
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        Ok(())
+    }
+
+    async fn try_register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> Result<(), Error> {
+        let mut req = self
+            .http
+            .put(self.endpoint_segments(&["v1", "registry", tool_name]))
+            .json(metadata);
+
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+
+        let resp = self
+            .send_with_retry(req, reqwest::RequestBuilder::send)
+            .await?;
+        tracing::debug!(
+            tool_name,
+            status = resp.status().as_u16(),
+            server_request_id = ?Self::server_request_id(&resp),
+            "tool registration response"
+        );
+        if !resp.status().is_success() {
+            return Err(self.status_error(resp).await);
+        }
+        Ok(())
+    }
+
+    /// Returns `Ok(())` if the sidecar is reachable and healthy.
+    pub async fn health(&self) -> Result<(), Error> {
+        self.health_at(&self.cfg.sidecar_url, self.cfg.timeout)
+            .await
+    }
+
+    /// Measure the round-trip time of a minimal `GET /v1/ping`, falling back
+    /// to `/v1/health` on sidecars without the ping route (404). Unlike
+    /// [`Client::health`], only reachability is checked, not the response
+    /// content. Bounded by [`Config::timeout`].
+    pub async fn ping(&self) -> Result<Duration, Error> {
+        let mut last = None;
+        for path in ["/v1/ping", "/v1/health"] {
+            let started = Instant::now();
+            let resp = self
+                .http
+                .get(self.endpoint(path))
+                .timeout(self.cfg.timeout)
+                .send()
+                .await?;
+            let rtt = started.elapsed();
+            if resp.status().is_success() {
+                return Ok(rtt);
+            }
+            if resp.status() != StatusCode::NOT_FOUND {
+                return Err(self.status_error(resp).await);
+            }
+            last = Some(resp);
+        }
+        let resp = last.expect("the health fallback was tried");
+        Err(self.status_error(resp).await)
+    }
+
+    /// Open a pooled connection to the sidecar so the first real `decide`
+    /// does not pay the TCP/TLS handshake inside its tight timeout.
+    ///
+    /// Issues a health check allowed up to [`WARMUP_TIMEOUT`] (or the
+    /// configured timeout, if longer); the connection is kept alive in the
+    /// pool afterwards. Call it during readiness setup, before serving traffic.
+    pub async fn warmup(&self) -> Result<(), Error> {
+        self.health_at(&self.cfg.sidecar_url, self.cfg.timeout.max(WARMUP_TIMEOUT))
+            .await
+    }
+
+    /// Probe `sidecar_url` and every entry of [`Config::sidecar_urls`]
+    /// concurrently, returning `(url, result)` pairs in configuration order.
+    pub async fn health_all(&self) -> Vec<(String, Result<(), Error>)> {
+        let urls: Vec<&String> = std::iter::once(&self.cfg.sidecar_url)
+            .chain(&self.cfg.sidecar_urls)
+            .collect();
+        let results =
+            future::join_all(urls.iter().map(|url| self.health_at(url, self.cfg.timeout))).await;
+        urls.into_iter().cloned().zip(results).collect()
+    }
+
+    /// Poll `/v1/health` every `interval` on a background task and publish
+    /// each transition between healthy and unhealthy on the returned
+    /// channel. The channel starts at [`HealthState::Unknown`] and is only
+    /// updated when the state changes, so consumers can simply await
+    /// [`changed`](watch::Receiver::changed).
+    ///
+    /// Probes are bounded by [`Config::timeout`] and do not feed
+    /// [`Config::health_gate`] or clock sync. The poller exits once every
+    /// receiver has been dropped; dropping the `JoinHandle` does not stop it
+    /// on its own. Must be called from within a Tokio runtime.
+    pub fn health_watch(
+        &self,
+        interval: Duration,
+    ) -> (watch::Receiver<HealthState>, JoinHandle<()>) {
+        let (tx, rx) = watch::channel(HealthState::Unknown);
+        let http = self.http.clone();
+        let url = self.endpoint("/v1/health");
+        let timeout = self.cfg.timeout;
+        let limit = self.cfg.max_response_bytes;
+        let poller = tokio::spawn(async move {
+            loop {
+                let state = match http.get(&url).timeout(timeout).send().await {
+                    Ok(resp) if resp.status() == StatusCode::OK => HealthState::Healthy,
+                    Ok(resp) => HealthState::Unhealthy(Self::sidecar_error(resp, limit).await),
+                    Err(e) => HealthState::Unhealthy(Error::Http(e)),
+                };
+                tx.send_if_modified(|current| {
+                    let changed = std::mem::discriminant(current) != std::mem::discriminant(&state);
+                    if changed {
+                        tracing::info!(healthy = state.is_healthy(), "sidecar health changed");
+                        *current = state;
+                    }
+                    changed
+                });
+                let tick = std::pin::pin!(tokio::time::sleep(interval));
+                let closed = std::pin::pin!(tx.closed());
+                if let Either::Right(_) = future::select(tick, closed).await {
+                    break;
+                }
+            }
+        });
+        (rx, poller)
+    }
+
+    async fn health_at(&self, base_url: &str, timeout: Duration) -> Result<(), Error> {
+        let sent_at = Mutex::new(Utc::now());
+        let url = if base_url == self.cfg.sidecar_url {
+            self.endpoint("/v1/health")
+        } else {
+            self.endpoint_at(base_url, "/v1/health")
+        };
+        let req = self.http.get(url).timeout(timeout);
+        let sent = self
+            .send_with_retry(req, |req| {
+                *sent_at.lock().unwrap() = Utc::now();
+                req.send()
+            })
+            .await;
+        if base_url == self.cfg.sidecar_url {
+            self.record_health(sent.is_ok());
+        }
+        let resp = sent?;
+
+        self.check_api_version(resp.headers())?;
+        if resp.status() != StatusCode::OK {
+            return Err(self.status_error(resp).await);
+        }
+        if self.cfg.sync_clock && base_url == self.cfg.sidecar_url {
+            self.record_server_time(*sent_at.lock().unwrap(), resp.headers());
+        }
+        tracing::debug!(server_request_id = ?Self::server_request_id(&resp), "sidecar healthy");
+        Ok(())
+    }
+}
+
+// ---- Enforcer trait ---------------------------------------------------------
+
+/// Object-safe enforcement interface, so applications can hold an
+/// `Arc<dyn Enforcer>` and swap in a stub for tests.
+#[async_trait]
+pub trait Enforcer: Send + Sync {
+    /// See [`Client::decide`].
+    async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error>;
+
+    /// See [`Client::health`].
+    async fn health(&self) -> Result<(), Error>;
+
+    /// See [`Client::register_tool`].
+    async fn register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool;
+}
+
+#[async_trait]
+impl Enforcer for Client {
+    async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+        Client::decide(self, invocation).await
+    }
+
+    async fn health(&self) -> Result<(), Error> {
+        Client::health(self).await
+    }
+
+    async fn register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool {
+        Client::register_tool(self, tool_name, metadata).await
+    }
+}
+
+// ---- Tests ------------------------------------------------------------------
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{method, path};
+    use futures::StreamExt;
+    use wiremock::matchers::{header, method, path, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
-    fn sample_invocation() -> ToolInvocation {
-        ToolInvocation {
-            invocation_id: "inv-001".into(),
-            timestamp: Utc::now(),
-            actor: Actor {
-                type_: "agent".into(),
-                id: "agent-1".into(),
-                workspace_id: "ws-1".into(),
-                session_id: "sess-1".into(),
-            },
-            agent: Agent {
-                name: "my-agent".into(),
-                version: "1.0.0".into(),
-                framework: "custom".into(),
-                trust_tier: "standard".into(),
-            },
-            tool: Tool {
-                name: "fs.read".into(),
-                provider: "local".into(),
-                capabilities: vec!["fs.read".into()],
-                risk_class: "low".into(),
-            },
-            request: ToolRequest::default(),
-            context: ExecutionContext {
-                repo: "my-repo".into(),
-                environment: "dev".into(),
-                data_classification: "internal".into(),
-                network_zone: "private".into(),
-            },
+    fn sample_invocation() -> ToolInvocation {
+        ToolInvocation {
+            invocation_id: "inv-001".into(),
+            timestamp: Utc::now(),
+            actor: Actor {
+                type_: "agent".into(),
+                id: "agent-1".into(),
+                workspace_id: "ws-1".into(),
+                session_id: "sess-1".into(),
+            },
+            agent: Agent {
+                name: "my-agent".into(),
+                version: "1.0.0".into(),
+                framework: "custom".into(),
+                trust_tier: "standard".into(),
+            },
+            tool: Tool {
+                name: "fs.read".into(),
+                provider: "local".into(),
+                capabilities: vec!["fs.read".into()],
+                risk_class: "low".into(),
+            },
+            request: ToolRequest::default(),
+            context: ExecutionContext {
+                repo: "my-repo".into(),
+                environment: "dev".into(),
+                data_classification: "internal".into(),
+                network_zone: "private".into(),
+            },
+        }
+    }
+
+    fn decision_body() -> serde_json::Value {
+        serde_json::json!({
+            "invocation_id": "inv-001",
+            "decision": "ALLOW",
+            "decision_code": "SG_ALLOW",
+            "reason_codes": [],
+            "policy_version": "1.0.0",
+            "budgets": {},
+            "evidence": {"hash": "abc", "signature": "sig", "key_id": "key1"},
+            "degraded": false,
+            "entitlement_version": "1.0",
+            "license_mode": "online",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_decide_allow() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let decision = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(decision.decision, "ALLOW");
+        assert_eq!(decision.decision_code, "SG_ALLOW");
+    }
+
+    #[tokio::test]
+    async fn test_latency_stats() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_secs(1);
+        let client = Client::new(cfg);
+        client.decide(sample_invocation()).await.unwrap();
+
+        let mut cfg = client.cfg.clone();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.fail_open = true;
+        let fail_open = Client::new(cfg);
+        fail_open.decide(sample_invocation()).await.unwrap();
+
+        let stats = client.latency_stats();
+        assert_eq!(stats.count, 1);
+        assert_eq!(stats.fail_open_rate, 0.0);
+        assert!(stats.p50 > Duration::ZERO && stats.p50 == stats.p99);
+        assert_eq!(fail_open.latency_stats().fail_open_rate, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_fail_closed() {
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        cfg.fail_open = false;
+        let client = Client::new(cfg);
+
+        let result = client.decide(sample_invocation()).await;
+        assert!(matches!(
+            result.map_err(Error::into_root),
+            Err(Error::EnforcerUnavailable { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fail_open() {
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        cfg.fail_open = true;
+        let client = Client::new(cfg);
+
+        let decision = client.decide(sample_invocation()).await.unwrap();
+        assert!(decision.degraded);
+        assert_eq!(decision.decision_code, "SG_ALLOW_DEGRADED_AUDIT_ASYNC");
+    }
+
+    #[tokio::test]
+    async fn test_fail_mode_resolver_overrides_fail_open() {
+        let cfg = Config::builder()
+            .sidecar_url("http://127.0.0.1:19999")
+            .timeout(Duration::from_millis(10))
+            .fail_open(true)
+            .fail_mode_resolver(|invocation| match invocation.tool.risk_class.as_str() {
+                "high" => FailMode::Closed,
+                _ => FailMode::Open,
+            })
+            .build();
+        let client = Client::new(cfg);
+
+        let low = client.decide(sample_invocation()).await.unwrap();
+        assert!(low.degraded);
+        assert_eq!(low.decision, "ALLOW");
+
+        let mut high = sample_invocation();
+        high.tool.risk_class = "high".into();
+        let err = client.decide(high).await.unwrap_err();
+        assert!(matches!(err.root(), Error::EnforcerUnavailable { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_decide_as_overrides_slt() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("authorization", "Bearer tenant-b"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("authorization", "Bearer default"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.slt = Some("default".into());
+        let client = Client::new(cfg);
+        client
+            .decide_as(sample_invocation(), "tenant-b")
+            .await
+            .unwrap();
+        client.decide(sample_invocation()).await.unwrap();
+
+        let err = client
+            .decide_as(sample_invocation(), "bad\ntoken")
+            .await
+            .unwrap_err();
+        assert!(matches!(err.root(), Error::InvalidConfig(_)));
+    }
+
+    #[tokio::test]
+    async fn test_slt_expiry_warns_then_fails_before_sending() {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .expect(3)
+            .mount(&server)
+            .await;
+        let jwt = |exp: DateTime<Utc>| {
+            let claims = URL_SAFE_NO_PAD.encode(format!(r#"{{"exp":{}}}"#, exp.timestamp()));
+            format!("eyJhbGciOiJIUzI1NiJ9.{claims}.c2ln")
+        };
+        let expiries = Arc::new(Mutex::new(Vec::new()));
+        let client_with = |slt: String| {
+            let expiries = expiries.clone();
+            Client::new(
+                Config::builder()
+                    .sidecar_url(server.uri())
+                    .slt(slt)
+                    .slt_expiry_warning(Duration::from_secs(300))
+                    .on_slt_expiring(move |at| expiries.lock().unwrap().push(at))
+                    .build(),
+            )
+        };
+
+        let expires_at = Utc::now() + chrono::Duration::seconds(60);
+        let expiring = client_with(jwt(expires_at));
+        expiring.decide(sample_invocation()).await.unwrap();
+        expiring.decide(sample_invocation()).await.unwrap();
+        assert_eq!(expiries.lock().unwrap().len(), 1);
+        assert_eq!(
+            expiries.lock().unwrap()[0].timestamp(),
+            expires_at.timestamp()
+        );
+
+        let expired = client_with(jwt(Utc::now() - chrono::Duration::seconds(1)));
+        let err = expired.decide(sample_invocation()).await.unwrap_err();
+        assert!(matches!(err.root(), Error::SltExpired { .. }));
+
+        let opaque = client_with("opaque-token".into());
+        opaque.decide(sample_invocation()).await.unwrap();
+        assert_eq!(expiries.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shadow_sidecar_reports_divergence() {
+        let primary = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&primary)
+            .await;
+        let shadow = MockServer::start().await;
+        let mut denied = decision_body();
+        denied["decision"] = "DENY".into();
+        denied["policy_version"] = "2.0.0".into();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(denied)
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .expect(1)
+            .mount(&shadow)
+            .await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let cfg = Config::builder()
+            .sidecar_url(primary.uri())
+            .timeout(Duration::from_secs(1))
+            .shadow_url(shadow.uri())
+            .on_shadow_divergence(move |divergence| {
+                let _ = tx.send(divergence.clone());
+            })
+            .build();
+        let client = Client::new(cfg);
+
+        let started = Instant::now();
+        let record = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        assert!(started.elapsed() < Duration::from_millis(250));
+
+        let divergence = rx.recv().await.unwrap();
+        assert_eq!(divergence.authoritative.invocation_id, "inv-001");
+        assert_eq!(divergence.shadow.decision, "DENY");
+        assert_eq!(
+            divergence.differences,
+            [ReplayDifference::Decision {
+                expected: "ALLOW".into(),
+                actual: "DENY".into()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redact_param_keys() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.redact_param_keys = vec!["api_key".into()];
+        let client = Client::new(cfg);
+
+        let mut invocation = sample_invocation();
+        invocation
+            .request
+            .params
+            .insert("API_KEY".into(), serde_json::json!("sk-secret-123"));
+        invocation
+            .request
+            .params
+            .insert("path".into(), serde_json::json!("/tmp/x"));
+        let original = invocation.clone();
+
+        client.decide(invocation).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+        assert!(!body.contains("sk-secret-123"));
+        assert!(body.contains("/tmp/x"));
+        assert_eq!(original.request.params["API_KEY"], "sk-secret-123");
+    }
+
+    #[tokio::test]
+    async fn test_offline_bundle_eval() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let dir = std::env::temp_dir().join(format!("skillgate-offline-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let bundle = dir.join("bundle.json");
+        let raw = serde_json::json!({
+            "policy_version": "2026.1",
+            "rules": [
+                {"id": "deny-high", "risk_class": "high", "decision": "DENY"},
+                {"id": "allow-low", "risk_class": "low", "decision": "ALLOW"},
+            ],
+        })
+        .to_string();
+        std::fs::write(&bundle, &raw).unwrap();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        std::fs::write(
+            bundle.with_extension("json.sig"),
+            serde_json::json!({
+                "key_id": "bundle",
+                "signature": hex::encode(key.sign(raw.as_bytes()).to_bytes()),
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let keys = Arc::new(Keyring::new());
+        keys.insert("bundle", key.verifying_key());
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        cfg.offline_bundle = Some(bundle.clone());
+        assert!(matches!(
+            Client::try_new(cfg.clone()),
+            Err(Error::InvalidConfig(_))
+        ));
+        cfg.offline_bundle_keys = Some(keys);
+        let client = Client::new(cfg);
+
+        let decision = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(decision.decision, "ALLOW");
+        assert_eq!(decision.decision_code, "SG_ALLOW_OFFLINE_LOCAL_EVAL");
+        assert!(decision.degraded);
+
+        let mut high = sample_invocation();
+        high.tool.risk_class = "high".into();
+        let decision = client.decide(high).await.unwrap();
+        assert_eq!(decision.decision_code, "SG_DENY_OFFLINE_LOCAL_EVAL");
+        assert_eq!(decision.policy_version, "2026.1");
+
+        let mut medium = sample_invocation();
+        medium.tool.risk_class = "medium".into();
+        let result = client.decide(medium).await;
+        assert!(matches!(
+            result.map_err(Error::into_root),
+            Err(Error::EnforcerUnavailable { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_decide_stream_pending_then_final() {
+        let server = MockServer::start().await;
+        let sse = format!("{}data: {}\n\n", pending_event(), decision_body());
+        Mock::given(method("POST"))
+            .and(path("/v1/decide/stream"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let records: Vec<_> = client.decide_stream(sample_invocation()).collect().await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].as_ref().unwrap().decision, "PENDING");
+        assert_eq!(records[1].as_ref().unwrap().decision, "ALLOW");
+    }
+
+    fn pending_event() -> String {
+        let mut pending = decision_body();
+        pending["decision"] = "PENDING".into();
+        pending["decision_code"] = "SG_PENDING_EXTERNAL_CHECK".into();
+        format!("data: {pending}\n\n")
+    }
+
+    #[tokio::test]
+    async fn test_decide_stream_ends_while_pending() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide/stream"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(pending_event(), "text/event-stream"),
+            )
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg.clone());
+        let records: Vec<_> = client.decide_stream(sample_invocation()).collect().await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].as_ref().unwrap().decision, "PENDING");
+        let err = records[1].as_ref().unwrap_err();
+        assert!(matches!(err, Error::Request { invocation_id, .. } if invocation_id == "inv-001"));
+        assert_eq!(err.root(), &Error::EmptyDecision);
+        assert_eq!(client.latency_stats().count, 1);
+
+        cfg.fail_open = true;
+        let client = Client::new(cfg);
+        let records: Vec<_> = client.decide_stream(sample_invocation()).collect().await;
+        let record = records[1].as_ref().unwrap();
+        assert_eq!(record.decision_code, FAIL_OPEN_CODE);
+        assert!(record.degraded);
+    }
+
+    #[tokio::test]
+    async fn test_decide_stream_connection_drops() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                // Read the request up to the end of its JSON body.
+                let mut request = Vec::new();
+                let mut buf = [0; 4096];
+                while !request.ends_with(b"}") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let event = pending_event();
+                let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+                let chunk = format!("{head}{:x}\r\n{event}\r\n", event.len());
+                stream.write_all(chunk.as_bytes()).await.unwrap();
+                // Close without the final chunk.
+            }
+        });
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = format!("http://{addr}");
+        let client = Client::new(cfg.clone());
+        let records: Vec<_> = client.decide_stream(sample_invocation()).collect().await;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].as_ref().unwrap().decision, "PENDING");
+        assert!(matches!(
+            records[1].as_ref().unwrap_err().root(),
+            Error::EnforcerUnavailable { .. }
+        ));
+
+        cfg.fail_open = true;
+        let client = Client::new(cfg);
+        let records: Vec<_> = client.decide_stream(sample_invocation()).collect().await;
+        assert_eq!(records[1].as_ref().unwrap().decision_code, FAIL_OPEN_CODE);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_events_resumes_from_last_event_id() {
+        let server = MockServer::start().await;
+        let first = format!(
+            "id: 1\nevent: decision\ndata: {}\n\n: keepalive\n\nid: 2\ndata: not json\n\n",
+            decision_body()
+        );
+        Mock::given(method("GET"))
+            .and(path("/v1/events"))
+            .and(query_param("workspace_id", "ws-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(first, "text/event-stream"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/events"))
+            .and(header("Last-Event-ID", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw("id: 3\nevent: audit\ndata: {}\n\n", "text/event-stream"),
+            )
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let events: Vec<_> = client
+            .subscribe_events("ws-1")
+            .take(3)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(events[0].kind, "decision");
+        assert_eq!(events[0].decision().unwrap().decision, "ALLOW");
+        assert_eq!(events[1].kind, "message");
+        assert_eq!(events[1].data, serde_json::json!("not json"));
+        assert_eq!(events[1].decision(), None);
+        assert_eq!(events[2].id.as_deref(), Some("3"));
+        assert_eq!(events[2].kind, "audit");
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_events_ends_on_client_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/events"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let events: Vec<_> = client.subscribe_events("ws-1").collect().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].as_ref().unwrap_err().status(), Some(401));
+    }
+
+    #[tokio::test]
+    async fn test_server_request_id() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(REQUEST_ID_HEADER, "srv-42")
+                    .set_body_json(decision_body()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(503).insert_header(REQUEST_ID_HEADER, "srv-43"))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let decision = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(decision.server_request_id.as_deref(), Some("srv-42"));
+
+        let err = client.health().await.unwrap_err();
+        assert!(err.to_string().contains("srv-43"));
+    }
+
+    #[tokio::test]
+    async fn test_health_all() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.sidecar_urls = vec!["http://127.0.0.1:19999".into()];
+        cfg.timeout = Duration::from_millis(50);
+        let client = Client::new(cfg);
+
+        let results = client.health_all().await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, server.uri());
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0, "http://127.0.0.1:19999");
+        assert!(results[1].1.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_path_prefix() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/skillgate/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = format!("{}/", server.uri());
+        cfg.path_prefix = "/skillgate/".into();
+        let client = Client::new(cfg);
+
+        assert_eq!(
+            client.endpoint("/v1/health"),
+            format!("{}/skillgate/v1/health", server.uri())
+        );
+        let decision = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(decision.decision, "ALLOW");
+    }
+
+    #[tokio::test]
+    async fn test_explain_and_has_reason() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/decide/inv-001/explain"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "summary": "fs.write is blocked in prod",
+                "matched_rules": ["deny-prod-writes"],
+                "suggested_remediation": "request an elevated session",
+            })))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let explanation = client.explain("inv-001").await.unwrap();
+        assert_eq!(explanation.matched_rules, vec!["deny-prod-writes"]);
+        assert_eq!(
+            explanation.suggested_remediation.as_deref(),
+            Some("request an elevated session")
+        );
+
+        let record = Client::degraded_allow("inv-001");
+        assert!(record.has_reason("enforcer_unavailable_fail_open"));
+        assert!(!record.has_reason("budget_exhausted"));
+    }
+
+    #[tokio::test]
+    async fn test_min_policy_version() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.min_policy_version = Some("1.2".into());
+        let client = Client::new(cfg);
+
+        let result = client.decide(sample_invocation()).await;
+        assert!(matches!(
+            result.map_err(Error::into_root),
+            Err(Error::PolicyVersionTooOld { ref got, ref required })
+                if got == "1.0.0" && required == "1.2"
+        ));
+
+        assert!(version_at_least("1.10.0", "1.9"));
+        assert!(version_at_least("v2.0.0-rc1", "2.0"));
+        assert!(!version_at_least("unknown", "1.0"));
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_and_default_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .and(header("User-Agent", USER_AGENT))
+            .and(header("X-Tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.default_headers = HashMap::from([("X-Tenant".into(), "acme".into())]);
+        Client::new(cfg.clone()).health().await.unwrap();
+
+        cfg.default_headers
+            .insert("authorization".into(), "Bearer spoofed".into());
+        assert!(matches!(Client::try_new(cfg), Err(Error::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_queue_overflow() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(decision_body())
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .mount(&server)
+            .await;
+
+        for overflow in [
+            Overflow::DropNewest,
+            Overflow::DropOldest,
+            Overflow::FailOpen,
+        ] {
+            let client = Client::new(
+                Config::builder()
+                    .sidecar_url(server.uri())
+                    .timeout(Duration::from_secs(2))
+                    .max_concurrent(1)
+                    .queue(1, overflow)
+                    .build(),
+            );
+            let after = |ms| {
+                let client = &client;
+                async move {
+                    tokio::time::sleep(Duration::from_millis(ms)).await;
+                    client.decide(sample_invocation()).await
+                }
+            };
+            let depth = async {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                client.queue_depth()
+            };
+            let (running, queued, overflowing, depth) =
+                futures::join!(after(0), after(20), after(40), depth);
+            assert_eq!(depth, 1, "{overflow:?}");
+            assert_eq!(running.unwrap().decision_code, "SG_ALLOW", "{overflow:?}");
+            match overflow {
+                Overflow::DropNewest => {
+                    assert!(queued.is_ok());
+                    assert_eq!(overflowing.unwrap_err().into_root(), Error::QueueFull);
+                }
+                Overflow::DropOldest => {
+                    assert_eq!(queued.unwrap_err().into_root(), Error::QueueFull);
+                    assert!(overflowing.is_ok());
+                }
+                _ => {
+                    assert!(queued.is_ok());
+                    let record = overflowing.unwrap();
+                    assert!(record.degraded);
+                    assert_eq!(record.reason_codes, ["queue_overflow_fail_open"]);
+                }
+            }
+            assert_eq!(client.queue_depth(), 0);
+        }
+
+        let cfg = Config::builder().queue(1, Overflow::Block).build();
+        assert!(matches!(Client::try_new(cfg), Err(Error::InvalidConfig(_))));
+        let blocking = |capacity| {
+            Config::builder()
+                .max_concurrent(1)
+                .queue(capacity, Overflow::Block)
+                .build()
+        };
+        assert!(matches!(
+            Client::try_new(blocking(0)),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(Client::try_new(blocking(1)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_decide_releases_permit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(decision_body())
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_secs(10);
+        cfg.max_concurrent = Some(2);
+        let client = Client::new(cfg);
+        let limiter = client.limiter.clone().unwrap();
+
+        let mut fut = Box::pin(client.decide(sample_invocation()));
+        let polled = tokio::time::timeout(Duration::from_millis(100), &mut fut).await;
+        assert!(polled.is_err());
+        assert_eq!(limiter.available_permits(), 1);
+
+        drop(fut);
+        assert_eq!(limiter.available_permits(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_error_classification() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_millis(20);
+        let err = Client::new(cfg.clone()).health().await.unwrap_err();
+        assert!(err.is_timeout());
+        assert!(err.is_transient());
+
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        let err = Client::new(cfg).health().await.unwrap_err();
+        assert!(err.is_connect());
+        assert!(!err.is_timeout());
+
+        let status_error = |status| Error::SidecarError {
+            status,
+            body: String::new(),
+            headers: HeaderMap::new(),
+        };
+        assert!(status_error(429).is_transient());
+        assert!(status_error(503).is_transient());
+        assert!(!status_error(400).is_transient());
+    }
+
+    #[test]
+    fn test_budget_helpers() {
+        let budget = BudgetStatus::new(5, 50);
+        assert_eq!(budget.used(), 45);
+        assert!((budget.fraction_remaining() - 0.1).abs() < f64::EPSILON);
+        assert!(!budget.is_exhausted());
+
+        let unlimited = BudgetStatus::new(0, 0);
+        assert_eq!(unlimited.fraction_remaining(), 1.0);
+        assert!(!unlimited.is_exhausted());
+
+        let mut record = Client::degraded_allow("inv-001");
+        record.budgets.insert("fs.write".into(), budget);
+        record.budgets.insert("net.http".into(), unlimited);
+        record
+            .budgets
+            .insert("fs.read".into(), BudgetStatus::new(0, 10));
+        assert_eq!(record.budgets_below(0.2), vec!["fs.read", "fs.write"]);
+        assert!(record.budgets["fs.read"].is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.sign_requests = true;
+        cfg.client_signing_key = Some("client-secret".into());
+        let client = Client::new(cfg);
+        client.decide(sample_invocation()).await.unwrap();
+        client.decide(sample_invocation()).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let nonce = |i: usize| {
+            requests[i].headers[NONCE_HEADER]
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_ne!(nonce(0), nonce(1));
+
+        let req = &requests[0];
+        let timestamp = req.headers[TIMESTAMP_HEADER].to_str().unwrap();
+        let body = std::str::from_utf8(&req.body).unwrap();
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"client-secret").unwrap();
+        mac.update(format!("{}.{timestamp}.{body}", nonce(0)).as_bytes());
+        assert_eq!(
+            req.headers[SIGNATURE_HEADER].to_str().unwrap(),
+            hex::encode(mac.finalize().into_bytes())
+        );
+    }
+
+    #[test]
+    fn test_extra_fields_preserved() {
+        let mut body = decision_body();
+        body["risk_score"] = 30.into();
+        body["rollout"] = serde_json::json!({"cohort": "beta"});
+        let record: DecisionRecord = serde_json::from_value(body).unwrap();
+        assert_eq!(record.extra("risk_score"), Some(&serde_json::json!(30)));
+        assert_eq!(record.extra("rollout").unwrap()["cohort"], "beta");
+        assert!(record.extra("decision").is_none());
+    }
+
+    #[test]
+    fn test_obligations() {
+        let mut body = decision_body();
+        body["obligations"] = serde_json::json!([
+            {"id": "siem-log", "action": "log", "params": {"sink": "splunk"}},
+            {"id": "redact-email", "action": "redact"},
+        ]);
+        let record: DecisionRecord = serde_json::from_value(body).unwrap();
+        assert_eq!(record.obligations().len(), 2);
+        assert!(record.has_obligation("siem-log"));
+        assert!(!record.has_obligation("notify"));
+        assert_eq!(record.obligations()[0].params["sink"], "splunk");
+        assert!(record.extra("obligations").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_register_tool_with_retry() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/registry/fs.read"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/registry/fs.read"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+        let metadata = HashMap::new();
+
+        client
+            .register_tool_with_retry("fs.read", &metadata, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let result = client
+            .register_tool_with_retry("fs.write", &metadata, Duration::from_millis(150))
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::SidecarError { status: 404, .. })
+        ));
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let cfg = Config::builder()
+            .sidecar_url("http://sidecar:8910")
+            .timeout(Duration::from_millis(75))
+            .fail_open(true)
+            .redact_param_keys(["token"])
+            .header("X-Tenant", "acme")
+            .sign_requests("secret")
+            .build();
+        assert_eq!(cfg.sidecar_url, "http://sidecar:8910");
+        assert_eq!(cfg.timeout, Duration::from_millis(75));
+        assert!(cfg.fail_open);
+        assert_eq!(cfg.redact_param_keys, vec!["token"]);
+        assert_eq!(cfg.default_headers["X-Tenant"], "acme");
+        assert!(cfg.sign_requests);
+        assert_eq!(cfg.stream_timeout, Config::from_env().stream_timeout);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_and_no_proxy() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        // Plain-HTTP requests are forwarded to the proxy with an absolute URI.
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://sidecar.invalid:8910".into();
+        cfg.proxy = Some(server.uri());
+        Client::new(cfg).health().await.unwrap();
+
+        // no_proxy keeps the local sidecar direct despite a dead proxy.
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.proxy = Some("http://127.0.0.1:19999".into());
+        cfg.no_proxy = Some("localhost,127.0.0.1".into());
+        Client::new(cfg).health().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_resource_refs() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.max_resource_refs = Some(2);
+        let client = Client::new(cfg.clone());
+
+        let mut invocation = sample_invocation();
+        invocation.request.resource_refs = ["a", "b", "a", "c", "d"].map(String::from).to_vec();
+        client.decide(invocation.clone()).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body["tool_invocation"]["request"]["resource_refs"],
+            serde_json::json!(["a", "b"])
+        );
+        assert_eq!(body["resource_refs_truncated"], 2);
+
+        cfg.resource_refs_overflow = RefsOverflow::Error;
+        let result = Client::new(cfg).decide(invocation).await;
+        assert!(matches!(
+            result.map_err(Error::into_root),
+            Err(Error::TooManyResourceRefs { count: 4, limit: 2 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_param_limits() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .redact_param_keys(["token"])
+                .param_limits(ParamLimits {
+                    max_param_bytes: Some(10),
+                    max_param_count: Some(3),
+                    max_total_bytes: Some(40),
+                })
+                .build(),
+        );
+        let with_params = |params: serde_json::Value| {
+            let mut invocation = sample_invocation();
+            invocation.request.params = serde_json::from_value(params).unwrap();
+            invocation
+        };
+
+        // Redacted values are measured as sent.
+        let ok = with_params(serde_json::json!({"path": "/tmp", "token": "x".repeat(100)}));
+        client.decide_ref(&ok).await.unwrap();
+
+        let err = client
+            .decide(with_params(serde_json::json!({"path": "/a/long/path"})))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.into_root(),
+            Error::ParamTooLarge {
+                key: "path".into(),
+                size: 14,
+                limit: 10
+            }
+        );
+        let err = client
+            .decide(with_params(
+                serde_json::json!({"a": 1, "b": 2, "c": 3, "d": 4}),
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_root(), Error::TooManyParams { count: 4, limit: 3 });
+        let err = client
+            .decide(with_params(
+                serde_json::json!({"a": "12345678", "b": "12345678", "c": "12345678"}),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            Error::ParamsTooLarge { limit: 40, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_decide_or_deny() {
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        let client = Client::new(cfg);
+
+        let decision = client.decide_or_deny(sample_invocation()).await;
+        assert_eq!(decision.decision, "DENY");
+        assert_eq!(decision.decision_code, "SG_DENY_CLIENT_ERROR");
+        assert_eq!(decision.invocation_id, "inv-001");
+        assert!(decision.has_reason("enforcer_unavailable_client_deny"));
+        assert!(decision.extra("client_error").is_some());
+        assert!(decision.degraded);
+        assert_eq!(decision.license_mode, "unknown");
+    }
+
+    #[tokio::test]
+    async fn test_decide_cached_or_uses_fallback_without_a_decision() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+        let fallback = || DecisionRecord::new("inv-001", "DENY", "APP_FALLBACK");
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let decision = Client::new(cfg)
+            .decide_cached_or(sample_invocation(), || panic!("sidecar answered"))
+            .await;
+        assert_eq!(decision.decision, "ALLOW");
+
+        for fail_open in [false, true] {
+            let mut cfg = Config::from_env();
+            cfg.sidecar_url = "http://127.0.0.1:19999".into();
+            cfg.timeout = Duration::from_millis(10);
+            cfg.fail_open = fail_open;
+            let decision = Client::new(cfg)
+                .decide_cached_or(sample_invocation(), fallback)
+                .await;
+            assert_eq!(decision.decision_code, "APP_FALLBACK");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_error_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "3")
+                    .insert_header("X-Policy-Version", "1.4.0")
+                    .set_body_string("x".repeat(500)),
+            )
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let err = Client::new(cfg)
+            .decide(sample_invocation())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), Some(429));
+        assert_eq!(err.header("retry-after"), Some("3"));
+        assert_eq!(err.header("X-Policy-Version"), Some("1.4.0"));
+        let Error::SidecarError { body, .. } = err.root() else {
+            panic!("expected SidecarError, got {err:?}");
+        };
+        assert_eq!(body.len(), 500);
+        assert!(err.to_string().len() < 300);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_allows_slow_handshake() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_millis(20);
+        let client = Client::new(cfg);
+
+        assert!(client.health().await.unwrap_err().is_timeout());
+        client.warmup().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_enforcer_trait_object() {
+        struct DenyAll;
+
+        #[async_trait]
+        impl Enforcer for DenyAll {
+            async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+                Ok(DecisionRecord::new(
+                    invocation.invocation_id,
+                    "DENY",
+                    "SG_DENY_TEST",
+                ))
+            }
+
+            async fn health(&self) -> Result<(), Error> {
+                Ok(())
+            }
+
+            async fn register_tool(
+                &self,
+                _tool_name: &str,
+                _metadata: &HashMap<String, serde_json::Value>,
+            ) -> bool {
+                true
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+
+        let enforcers: Vec<Arc<dyn Enforcer>> = vec![Arc::new(Client::new(cfg)), Arc::new(DenyAll)];
+        let mut decisions = Vec::new();
+        for enforcer in &enforcers {
+            decisions.push(enforcer.decide(sample_invocation()).await.unwrap().decision);
+        }
+        assert_eq!(decisions, vec!["ALLOW", "DENY"]);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        client.decide(sample_invocation()).await.unwrap();
+        let mut anonymous = sample_invocation();
+        anonymous.actor.workspace_id = String::new();
+        client.decide(anonymous).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            requests[0].headers[WORKSPACE_HEADER],
+            body["tool_invocation"]["actor"]["workspace_id"]
+                .as_str()
+                .unwrap()
+        );
+        assert!(!requests[1].headers.contains_key(WORKSPACE_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_flat_envelope() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.request_envelope = Envelope::Flat;
+        Client::new(cfg).decide(sample_invocation()).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["invocation_id"], "inv-001");
+        assert_eq!(body["tool"]["name"], "fs.read");
+        assert!(body.get("tool_invocation").is_none());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn test_msgpack_wire_format_with_json_fallback() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("content-type", "application/msgpack"))
+            .and(header("accept", "application/msgpack"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = rmp_serde::from_slice(&req.body).unwrap();
+                assert_eq!(body["tool_invocation"]["tool"]["name"], "fs.read");
+                ResponseTemplate::new(200).set_body_raw(
+                    rmp_serde::to_vec_named(&decision_body()).unwrap(),
+                    "application/msgpack",
+                )
+            })
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .wire_format(WireFormat::MsgPack)
+            .build();
+        let record = Client::new(cfg).decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        assert_eq!(record.evidence.key_id, "key1");
+
+        // A sidecar without msgpack support gets JSON after one 415.
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("content-type", "application/msgpack"))
+            .respond_with(ResponseTemplate::new(415))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("content-type", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .wire_format(WireFormat::MsgPack)
+            .build();
+        let client = Client::new(cfg);
+        for _ in 0..2 {
+            let record = client.decide(sample_invocation()).await.unwrap();
+            assert_eq!(record.decision, "ALLOW");
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    #[tokio::test]
+    async fn test_cbor_wire_format() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("content-type", "application/cbor"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = ciborium::from_reader(&req.body[..]).unwrap();
+                assert_eq!(body["invocation_id"], "inv-001");
+                let mut decision = Vec::new();
+                ciborium::into_writer(&decision_body(), &mut decision).unwrap();
+                ResponseTemplate::new(200).set_body_raw(decision, "application/cbor")
+            })
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .wire_format(WireFormat::Cbor)
+            .build();
+        let record = Client::new(cfg).decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.invocation_id, "inv-001");
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_formats() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut invocation = sample_invocation();
+        invocation.timestamp = "2026-01-01T00:00:01.250Z".parse().unwrap();
+        let cases = [
+            (
+                TimestampFormat::Rfc3339,
+                serde_json::json!("2026-01-01T00:00:01.250Z"),
+            ),
+            (
+                TimestampFormat::EpochMillis,
+                serde_json::json!(1_767_225_601_250_i64),
+            ),
+            (
+                TimestampFormat::EpochSeconds,
+                serde_json::json!(1_767_225_601_i64),
+            ),
+        ];
+        for (i, (format, expected)) in cases.into_iter().enumerate() {
+            let mut cfg = Config::from_env();
+            cfg.sidecar_url = server.uri();
+            cfg.timeout = Duration::from_secs(1);
+            cfg.timestamp_format = format;
+            let client = Client::new(cfg);
+            client.decide(invocation.clone()).await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&requests[i].body).unwrap();
+            assert_eq!(body["tool_invocation"]["timestamp"], expected, "{format:?}");
+            if format == TimestampFormat::Rfc3339 {
+                assert_eq!(
+                    body["tool_invocation"],
+                    serde_json::to_value(&invocation).unwrap()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decide_json() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.redact_param_keys = vec!["token".into()];
+        let client = Client::new(cfg);
+
+        let mut invocation = serde_json::to_value(sample_invocation()).unwrap();
+        invocation["request"]["params"]["token"] = "secret".into();
+        invocation["tenant_tier"] = "gold".into();
+        let decision = client.decide_json(invocation).await.unwrap();
+        assert_eq!(decision.decision, "ALLOW");
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["invocation_id"], "inv-001");
+        assert_eq!(body["tool_invocation"]["tenant_tier"], "gold");
+        assert_eq!(body["tool_invocation"]["request"]["params"]["token"], "***");
+        assert_eq!(requests[0].headers[WORKSPACE_HEADER], "ws-1");
+
+        let err = client
+            .decide_json(serde_json::json!([1]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.root(), Error::Json(_)));
+
+        // Untyped invocations can still fail open.
+        let mut cfg = client.cfg.clone();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.fail_open = true;
+        let decision = Client::new(cfg)
+            .decide_json(serde_json::json!({"invocation_id": "raw-1"}))
+            .await
+            .unwrap();
+        assert!(decision.degraded);
+        assert_eq!(decision.invocation_id, "raw-1");
+    }
+
+    #[tokio::test]
+    async fn test_list_tools_etag() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/registry"))
+            .and(header("If-None-Match", r#""v1""#))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/registry"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", r#""v1""#)
+                    .set_body_json(serde_json::json!({
+                        "tools": [{"name": "fs.read", "risk_class": "low"}],
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let RegistryListing::Changed(tools) = client.list_tools().await.unwrap() else {
+            panic!("expected a full listing");
+        };
+        assert_eq!(tools[0].name, "fs.read");
+        assert_eq!(tools[0].metadata["risk_class"], "low");
+        assert_eq!(
+            client.list_tools().await.unwrap(),
+            RegistryListing::Unchanged
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_differences() {
+        let server = MockServer::start().await;
+        let mut body = decision_body();
+        body["decision"] = "DENY".into();
+        body["decision_code"] = "SG_DENY".into();
+        body["policy_version"] = "2.0.0".into();
+        body["budgets"] = serde_json::json!({"fs.read": {"remaining": 0, "limit": 10}});
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header(DRY_RUN_HEADER, "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let low = Arc::new(AtomicBool::new(false));
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.budget_low_water = Some(0.5);
+        cfg.on_budget_low = Some({
+            let low = low.clone();
+            Arc::new(move |_: &str, _: &BudgetStatus| {
+                low.store(true, Ordering::SeqCst);
+            })
+        });
+        let client = Client::new(cfg);
+        let expected: DecisionRecord = serde_json::from_value(decision_body()).unwrap();
+
+        let result = client
+            .replay(&sample_invocation(), &expected)
+            .await
+            .unwrap();
+        assert!(!result.is_reproduced());
+        assert_eq!(result.replayed.decision, "DENY");
+        // A dry run leaves the client's budget state alone.
+        assert!(client.budget_tracker().unwrap().snapshot().is_empty());
+        assert!(!low.load(Ordering::SeqCst));
+        assert_eq!(
+            result.differences,
+            [
+                ReplayDifference::Decision {
+                    expected: "ALLOW".into(),
+                    actual: "DENY".into()
+                },
+                ReplayDifference::DecisionCode {
+                    expected: "SG_ALLOW".into(),
+                    actual: "SG_DENY".into()
+                },
+                ReplayDifference::PolicyVersion {
+                    expected: "1.0.0".into(),
+                    actual: "2.0.0".into()
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_watch_publishes_transitions() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+        let (mut rx, poller) = client.health_watch(Duration::from_millis(10));
+
+        rx.changed().await.unwrap();
+        assert!(rx.borrow_and_update().is_healthy());
+
+        server.reset().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        rx.changed().await.unwrap();
+        match &*rx.borrow_and_update() {
+            HealthState::Unhealthy(err) => assert_eq!(err.status(), Some(503)),
+            state => panic!("unexpected state {state:?}"),
+        }
+
+        // Repeated failures are not re-published.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!rx.has_changed().unwrap());
+
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(1), poller)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_summary() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/sessions/sess-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "session_id": "sess-1",
+                "allowed": 7,
+                "denied": 2,
+                "approval_required": 1,
+                "budget_usage": {"fs.read": 40},
+            })))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let client = Client::new(cfg);
+
+        let summary = client.session_summary("sess-1").await.unwrap();
+        assert_eq!(summary.total(), 10);
+        assert_eq!(summary.budget_usage["fs.read"], 40);
+
+        let err = client.session_summary("sess-2").await.unwrap_err();
+        assert_eq!(err, Error::NotFound("session sess-2".into()));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let log = std::env::temp_dir().join(format!("skillgate-audit-{}.jsonl", Uuid::new_v4()));
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        cfg.fail_open = true;
+        cfg.audit_log_path = Some(log.clone());
+        let client = Arc::new(Client::new(cfg));
+
+        let calls = (0..8).map(|i| {
+            let client = client.clone();
+            let mut invocation = sample_invocation();
+            invocation.invocation_id = format!("inv-{i}");
+            tokio::spawn(async move { client.decide(invocation).await })
+        });
+        for call in future::join_all(calls).await {
+            call.unwrap().unwrap();
+        }
+        client.flush_audit_log().await.unwrap();
+
+        let entries = read_audit_log(&log).unwrap();
+        assert_eq!(entries.len(), 8);
+        assert!(entries.iter().all(|e| e.degraded && e.decision == "ALLOW"));
+        std::fs::remove_file(log).unwrap();
+    }
+
+    #[test]
+    fn test_model_equality_and_hash() {
+        let mut a = sample_invocation();
+        let mut b = a.clone();
+        a.request.params.insert("x".into(), 1.into());
+        a.request.params.insert("y".into(), "two".into());
+        b.request.params.insert("y".into(), "two".into());
+        b.request.params.insert("x".into(), 1.into());
+        assert_eq!(a, b);
+
+        let mut seen = HashSet::new();
+        seen.insert(a);
+        assert!(seen.contains(&b));
+
+        let record: DecisionRecord = serde_json::from_value(decision_body()).unwrap();
+        assert_eq!(record, record.clone());
+        assert_ne!(record, Client::degraded_allow("inv-001"));
+
+        assert_eq!(
+            Error::InvalidConfig("x".into()),
+            Error::InvalidConfig("x".into())
+        );
+        assert_ne!(
+            Error::InvalidConfig("x".into()),
+            Error::OfflineBundle("x".into())
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_volatile_fields_and_ordering() {
+        let mut a = sample_invocation();
+        a.request.params.insert("path".into(), "/etc/hosts".into());
+        a.request.params.insert("mode".into(), "r".into());
+        a.request.resource_refs = vec!["b".into(), "a".into()];
+        let mut b = sample_invocation();
+        b.invocation_id = "inv-002".into();
+        b.timestamp = a.timestamp - chrono::Duration::minutes(5);
+        b.request.params.insert("mode".into(), "r".into());
+        b.request.params.insert("path".into(), "/etc/hosts".into());
+        b.request.resource_refs = vec!["a".into(), "b".into()];
+
+        let fingerprint = a.fingerprint();
+        assert_eq!(fingerprint.len(), 64);
+        assert_eq!(fingerprint, b.fingerprint());
+
+        b.request.params.insert("mode".into(), "w".into());
+        assert_ne!(fingerprint, b.fingerprint());
+    }
+
+    #[tokio::test]
+    async fn test_clock_offset_applied_to_timestamp() {
+        let server = MockServer::start().await;
+        let server_time = Utc::now() + chrono::Duration::minutes(10);
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(SERVER_TIME_HEADER, server_time.to_rfc3339().as_str()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_secs(1);
+        cfg.sync_clock = true;
+        let client = Client::new(cfg);
+        assert_eq!(client.clock_offset(), chrono::Duration::zero());
+
+        client.health().await.unwrap();
+        let offset = client.clock_offset();
+        assert!((offset - chrono::Duration::minutes(10)).num_seconds().abs() < 2);
+
+        let invocation = sample_invocation();
+        let local = invocation.timestamp;
+        client.decide(invocation).await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        let sent: DateTime<Utc> =
+            serde_json::from_value(body["tool_invocation"]["timestamp"].clone()).unwrap();
+        assert_eq!(sent - local, offset);
+    }
+
+    #[tokio::test]
+    async fn test_register_tools_bulk_and_fallback() {
+        let boms = vec![
+            ("fs.read".to_string(), HashMap::new()),
+            ("fs.write".to_string(), HashMap::new()),
+        ];
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/registry/bulk"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [{"name": "fs.read", "ok": true}, {"name": "fs.write", "ok": false}],
+            })))
+            .mount(&server)
+            .await;
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        let results = Client::new(cfg).register_tools(&boms).await.unwrap();
+        assert_eq!(
+            results,
+            vec![("fs.read".into(), true), ("fs.write".into(), false)]
+        );
+
+        let legacy = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/registry/fs.read"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&legacy)
+            .await;
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = legacy.uri();
+        let results = Client::new(cfg).register_tools(&boms).await.unwrap();
+        assert_eq!(
+            results,
+            vec![("fs.read".into(), true), ("fs.write".into(), false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_degraded_audit_delivery() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audit"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audit"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_millis(50);
+        cfg.fail_open = true;
+        cfg.degraded_audit = Some(DegradedAuditConfig {
+            capacity: 2,
+            ..Default::default()
+        });
+        let client = Client::new(cfg);
+
+        let decisions = future::join_all((0..3).map(|i| {
+            let mut invocation = sample_invocation();
+            invocation.invocation_id = format!("inv-{i}");
+            client.decide(invocation)
+        }))
+        .await;
+        assert!(decisions.iter().all(|d| d.as_ref().unwrap().degraded));
+
+        for _ in 0..50 {
+            if client.degraded_audit_stats().unwrap().buffered == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let stats = client.degraded_audit_stats().unwrap();
+        assert_eq!(stats.buffered, 0);
+        assert_eq!(stats.flushed + stats.dropped, 3);
+        assert_eq!(stats.dropped, 1);
+    }
+
+    #[test]
+    fn test_decision_ttl_expiry() {
+        let mut body = decision_body();
+        body["ttl_seconds"] = 30.into();
+        body["evaluated_at"] = "2026-01-01T00:00:00+00:00".into();
+        let record: DecisionRecord = serde_json::from_value(body).unwrap();
+        let evaluated_at: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+        assert_eq!(
+            record.expires_at(),
+            Some(evaluated_at + chrono::Duration::seconds(30))
+        );
+        assert!(!record.is_expired(evaluated_at + chrono::Duration::seconds(29)));
+        assert!(record.is_expired(evaluated_at + chrono::Duration::seconds(30)));
+        assert!(record.extra.is_empty());
+        assert!(String::from_utf8(canonical_decision_bytes(&record))
+            .unwrap()
+            .contains(r#""evaluated_at":"2026-01-01T00:00:00+00:00""#));
+
+        let mut record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        assert!(!record.is_expired(Utc::now()));
+        record.ttl_seconds = Some(30);
+        assert_eq!(record.expires_at(), None);
+        assert!(record.is_expired(Utc::now()));
+    }
+
+    #[test]
+    fn test_trust_tier_ordering_and_parsing() {
+        assert!(TrustTier::Untrusted < TrustTier::Standard);
+        assert!(TrustTier::Standard < TrustTier::Elevated);
+        assert!(TrustTier::Elevated < TrustTier::Trusted);
+
+        let agent = sample_invocation()
+            .agent
+            .with_trust_tier(TrustTier::Elevated);
+        assert_eq!(agent.trust_tier, "elevated");
+        assert!(agent.trust_tier_typed().unwrap() >= TrustTier::Elevated);
+        assert_eq!("Trusted".parse::<TrustTier>().unwrap(), TrustTier::Trusted);
+        assert_eq!(
+            serde_json::to_value(TrustTier::Untrusted).unwrap(),
+            serde_json::json!("untrusted")
+        );
+
+        let agent = Agent {
+            trust_tier: "root".into(),
+            ..agent
+        };
+        assert_eq!(
+            agent.trust_tier_typed(),
+            Err(Error::UnknownTrustTier("root".into()))
+        );
+    }
+
+    #[test]
+    fn test_license_mode_typed() {
+        let mut record: DecisionRecord = serde_json::from_value(decision_body()).unwrap();
+        assert_eq!(record.license_mode_typed(), LicenseMode::Online);
+        assert!(!record.is_degraded_license());
+
+        record.license_mode = "Grace".into();
+        assert_eq!(record.license_mode_typed(), LicenseMode::Grace);
+        assert!(record.is_degraded_license());
+
+        record.license_mode = "trial".into();
+        assert_eq!(
+            record.license_mode_typed(),
+            LicenseMode::Unknown("trial".into())
+        );
+        assert!(Client::degraded_allow("inv-001").is_degraded_license());
+    }
+
+    #[test]
+    fn test_decision_code_category() {
+        let category = |code: &str| code.parse::<DecisionCode>().unwrap().category();
+        assert_eq!(category("SG_ALLOW"), DecisionCategory::Allow);
+        assert_eq!(
+            category("SG_ALLOW_OFFLINE_LOCAL_EVAL"),
+            DecisionCategory::Allow
+        );
+        assert_eq!(category("SG_DENY_SOMETHING_NEW"), DecisionCategory::Deny);
+        assert_eq!(category("SG_FAIL_POLICY_LOAD"), DecisionCategory::Fail);
+        assert_eq!(
+            category("SG_REQUIRE_APPROVAL_HIGH_RISK"),
+            DecisionCategory::RequireApproval
+        );
+        assert_eq!(
+            category("SG_PENDING_EXTERNAL_CHECK"),
+            DecisionCategory::Pending
+        );
+        assert_eq!(category("SG_DENYLIST_HIT"), DecisionCategory::Other);
+        assert_eq!(category("allow"), DecisionCategory::Other);
+
+        let code = Client::degraded_allow("inv-001").code();
+        assert!(code.is_degraded());
+        assert_eq!(code.to_string(), "SG_ALLOW_DEGRADED_AUDIT_ASYNC");
+        assert!(!DecisionCode::new("SG_DENY_UNDEGRADED").is_degraded());
+    }
+
+    #[test]
+    fn test_decision_typed() {
+        let mut record: DecisionRecord = serde_json::from_value(decision_body()).unwrap();
+        assert_eq!(record.decision_typed(), Decision::Allow);
+        assert!(record.decision_typed().is_allowed());
+        assert!(record.is_allowed());
+        assert!(!record.is_blocking());
+
+        record.decision = "REQUIRE_APPROVAL".into();
+        let decision = record.decision_typed();
+        assert!(decision.requires_approval());
+        assert!(decision.is_blocking());
+        assert!(record.requires_approval());
+        assert!(record.is_blocking());
+
+        let unknown: Decision = serde_json::from_str(r#""QUARANTINE""#).unwrap();
+        assert_eq!(unknown, Decision::Unknown("QUARANTINE".into()));
+        assert!(unknown.is_blocking());
+        assert_eq!(serde_json::to_string(&unknown).unwrap(), r#""QUARANTINE""#);
+        assert_eq!(
+            serde_json::to_string(&Decision::RequireApproval).unwrap(),
+            r#""REQUIRE_APPROVAL""#
+        );
+        assert_eq!(Decision::parse("allow"), Decision::Unknown("allow".into()));
+    }
+
+    #[tokio::test]
+    async fn test_degraded_record_reuses_last_known_budgets() {
+        let server = MockServer::start().await;
+        let mut body = decision_body();
+        body["budgets"] = serde_json::json!({"fs.read": {"remaining": 7, "limit": 10}});
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_millis(100);
+        cfg.fail_open = true;
+        let client = Client::new(cfg);
+        client.decide(sample_invocation()).await.unwrap();
+
+        let degraded = client.decide(sample_invocation()).await.unwrap();
+        assert!(degraded.degraded);
+        assert_eq!(degraded.budgets["fs.read"].remaining, 7);
+        assert_eq!(
+            degraded.extra("budgets_stale"),
+            Some(&serde_json::json!(true))
+        );
+
+        let mut other = sample_invocation();
+        other.actor.workspace_id = "ws-2".into();
+        let degraded = client.decide(other).await.unwrap();
+        assert!(degraded.budgets.is_empty());
+        assert!(degraded.extra("budgets_stale").is_none());
+    }
+
+    #[test]
+    fn test_decision_record_new_defaults() {
+        let record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        assert_eq!(record.invocation_id, "inv-1");
+        assert!(!record.degraded);
+        assert_eq!(
+            record.license_mode_typed(),
+            LicenseMode::Unknown("unknown".into())
+        );
+        assert!(record.reason_codes.is_empty() && record.obligations().is_empty());
+
+        let json = serde_json::to_value(&record).unwrap();
+        let back: DecisionRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(back, record);
+    }
+
+    #[tokio::test]
+    async fn test_large_params_are_streamed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.stream_body_threshold = Some(1024);
+        let client = Client::new(cfg);
+        let blob = "x".repeat(200 * 1024);
+        let mut large = sample_invocation();
+        large
+            .request
+            .params
+            .insert("blob".into(), blob.clone().into());
+        client.decide(large).await.unwrap();
+        client.decide(sample_invocation()).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["invocation_id"], "inv-001");
+        assert_eq!(body["tool_invocation"]["request"]["params"]["blob"], blob);
+        assert!(!requests[0].headers.contains_key("content-length"));
+        assert!(requests[1].headers.contains_key("content-length"));
+    }
+
+    #[tokio::test]
+    async fn test_response_too_large() {
+        let server = MockServer::start().await;
+        let mut body = decision_body();
+        body["padding"] = "x".repeat(4096).into();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.max_response_bytes = Some(1024);
+        let err = Client::new(cfg.clone())
+            .decide(sample_invocation())
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_root(), Error::ResponseTooLarge { limit: 1024 });
+
+        cfg.max_response_bytes = Some(64 * 1024);
+        let record = Client::new(cfg).decide(sample_invocation()).await.unwrap();
+        assert_eq!(
+            record.extra("padding").unwrap().as_str().unwrap().len(),
+            4096
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_limit_covers_errors_and_registry() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("e".repeat(4096)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/registry/bulk"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [{"name": "x".repeat(4096), "ok": true}],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "keys": [],
+                "padding": "x".repeat(4096),
+            })))
+            .mount(&server)
+            .await;
+
+        let keyring = Arc::new(Keyring::from_sidecar());
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.max_response_bytes = Some(1024);
+        cfg.retry = None;
+        cfg.verify_evidence = Some(keyring.clone());
+        let client = Client::new(cfg);
+
+        // Error bodies are cut off at the limit rather than failing.
+        let err = client.decide(sample_invocation()).await.unwrap_err();
+        let Error::SidecarError { status, body, .. } = err.into_root() else {
+            panic!("expected a sidecar error");
+        };
+        assert_eq!(status, 500);
+        assert_eq!(body, "e".repeat(1024));
+
+        let boms = vec![("fs.read".to_string(), HashMap::new())];
+        let err = client.register_tools(&boms).await.unwrap_err();
+        assert_eq!(err, Error::ResponseTooLarge { limit: 1024 });
+
+        let err = keyring.refresh().await.unwrap_err();
+        assert_eq!(err, Error::ResponseTooLarge { limit: 1024 });
+    }
+
+    #[tokio::test]
+    async fn test_noop_client() {
+        let client: Arc<dyn Enforcer> = Arc::new(NoopClient::new(Config::from_env()));
+        let record = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        assert_eq!(record.decision_code, ENFORCEMENT_DISABLED_CODE);
+        assert_eq!(record.invocation_id, "inv-001");
+        assert!(!record.degraded);
+        client.health().await.unwrap();
+        assert!(client.register_tool("fs.read", &HashMap::new()).await);
+    }
+
+    #[tokio::test]
+    async fn test_decide_error_carries_invocation_id() {
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(10);
+        let err = Client::new(cfg)
+            .decide(sample_invocation())
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.invocation_id(), Some("inv-001"));
+        assert!(err.is_connect());
+        assert!(err
+            .to_string()
+            .starts_with("invocation inv-001 (session sess-1): sidecar unreachable"));
+        assert!(matches!(err.root(), Error::EnforcerUnavailable { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_error_source_chain() {
+        use std::error::Error as _;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = "http://127.0.0.1:19999".into();
+        cfg.timeout = Duration::from_millis(100);
+        let err = Client::new(cfg)
+            .decide(sample_invocation())
+            .await
+            .unwrap_err();
+
+        let unavailable = err.source().unwrap();
+        assert!(unavailable.to_string().starts_with("sidecar unreachable"));
+        let transport = unavailable.source().unwrap();
+        let transport = transport.downcast_ref::<reqwest::Error>().unwrap();
+        assert!(transport.is_connect());
+
+        let gated = Error::EnforcerUnavailable {
+            reason: "sidecar marked unhealthy by health gate".into(),
+            source: None,
+        };
+        assert!(gated.source().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decide_many_preserves_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+                let mut decision = decision_body();
+                decision["invocation_id"] = body["invocation_id"].clone();
+                // Later invocations answer first.
+                let n: u64 = body["invocation_id"].as_str().unwrap()[4..]
+                    .parse()
+                    .unwrap();
+                ResponseTemplate::new(200)
+                    .set_body_json(decision)
+                    .set_delay(Duration::from_millis(40 - n * 10))
+            })
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_secs(1);
+        let invocations = (0..4).map(|i| {
+            let mut invocation = sample_invocation();
+            invocation.invocation_id = format!("inv-{i}");
+            invocation
+        });
+        let results = Client::new(cfg).decide_many(invocations, 4).await;
+
+        let ids: Vec<_> = results
+            .into_iter()
+            .map(|r| r.unwrap().invocation_id)
+            .collect();
+        assert_eq!(ids, ["inv-0", "inv-1", "inv-2", "inv-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_decide_batch_partial() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide/batch"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+                assert_eq!(body["partial"], true);
+                let results: Vec<_> = body["invocations"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|item| match item["invocation_id"].as_str().unwrap() {
+                        "inv-1" => serde_json::json!({
+                            "error": {"code": "SG_INVALID", "message": "unknown tool"},
+                        }),
+                        id => {
+                            let mut decision = decision_body();
+                            decision["invocation_id"] = id.into();
+                            serde_json::json!({ "ok": decision })
+                        }
+                    })
+                    .collect();
+                ResponseTemplate::new(200)
+                    .insert_header(REQUEST_ID_HEADER, "req-9")
+                    .set_body_json(serde_json::json!({ "results": results }))
+            })
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.max_resource_refs = Some(1);
+        cfg.resource_refs_overflow = RefsOverflow::Error;
+        let invocations = (0..4)
+            .map(|i| {
+                let mut invocation = sample_invocation();
+                invocation.invocation_id = format!("inv-{i}");
+                if i == 2 {
+                    invocation.request.resource_refs = vec!["a".into(), "b".into()];
+                }
+                invocation
+            })
+            .collect();
+        let results = Client::new(cfg)
+            .decide_batch_partial(invocations)
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 4);
+        let record = results[0].as_ref().unwrap();
+        assert_eq!(record.invocation_id, "inv-0");
+        assert_eq!(record.server_request_id.as_deref(), Some("req-9"));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.invocation_id(), Some("inv-1"));
+        assert_eq!(
+            err.root(),
+            &Error::BatchItem {
+                index: 1,
+                message: "unknown tool".into()
+            }
+        );
+        assert!(matches!(
+            results[2].as_ref().unwrap_err().root(),
+            Error::TooManyResourceRefs { count: 2, limit: 1 }
+        ));
+        assert_eq!(results[3].as_ref().unwrap().invocation_id, "inv-3");
+    }
+
+    #[tokio::test]
+    async fn test_decide_batch_falls_back_to_single_calls() {
+        let invocations = || {
+            (0..3)
+                .map(|i| {
+                    let mut invocation = sample_invocation();
+                    invocation.invocation_id = format!("inv-{i}");
+                    invocation
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide/batch"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+                let mut decision = decision_body();
+                decision["invocation_id"] = body["invocation_id"].clone();
+                ResponseTemplate::new(200).set_body_json(decision)
+            })
+            .expect(6)
+            .mount(&server)
+            .await;
+        let client = Client::new(Config::builder().sidecar_url(server.uri()).build());
+        for _ in 0..2 {
+            let ids: Vec<_> = client
+                .decide_batch(invocations())
+                .await
+                .into_iter()
+                .map(|r| r.unwrap().invocation_id)
+                .collect();
+            assert_eq!(ids, ["inv-0", "inv-1", "inv-2"]);
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide/batch"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .mount(&server)
+            .await;
+        let client = Client::new(Config::builder().sidecar_url(server.uri()).build());
+        let results = client.decide_batch(invocations()).await;
+        assert_eq!(results.len(), 3);
+        for (i, result) in results.iter().enumerate() {
+            let err = result.as_ref().unwrap_err();
+            assert_eq!(err.invocation_id(), Some(format!("inv-{i}").as_str()));
+            assert_eq!(err.status(), Some(429));
+            assert_eq!(err.header("Retry-After"), Some("1"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accept_language_and_message() {
+        let server = MockServer::start().await;
+        let mut body = decision_body();
+        body["message"] = "Outil refusé".into();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(header("Accept-Language", "fr-FR"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .accept_language("fr-FR")
+            .build();
+        let record = Client::new(cfg).decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.message.as_deref(), Some("Outil refusé"));
+        assert!(record.extra("message").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_api_version_mismatch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200).insert_header(API_VERSION_HEADER, "2.0"))
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder().sidecar_url(server.uri()).build();
+        let client = Client::new(cfg.clone());
+        assert_eq!(client.server_api_version(), None);
+        client.health().await.unwrap();
+        assert_eq!(client.server_api_version().as_deref(), Some("2.0"));
+
+        let strict = Client::new(Config {
+            strict_version: true,
+            ..cfg
+        });
+        assert_eq!(
+            strict.health().await.unwrap_err(),
+            Error::ApiVersionUnsupported {
+                got: "2.0".into(),
+                supported: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decide_prepared_matches_full_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.redact_param_keys = vec!["token".into()];
+        let client = Client::new(cfg);
+        let mut invocation = sample_invocation();
+        invocation
+            .request
+            .params
+            .insert("token".into(), "secret".into());
+        invocation.request.resource_refs = vec!["a".into(), "a".into()];
+        let prepared = PreparedInvocation::new(
+            invocation.actor.clone(),
+            invocation.agent.clone(),
+            invocation.context.clone(),
+        )
+        .unwrap();
+        client
+            .decide_prepared(
+                &prepared,
+                invocation.tool.clone(),
+                invocation.request.clone(),
+            )
+            .await
+            .unwrap();
+        client.decide(invocation).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let mut prepared_body: serde_json::Value =
+            serde_json::from_slice(&requests[0].body).unwrap();
+        let full_body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(requests[0].headers[WORKSPACE_HEADER], "ws-1");
+        assert_eq!(
+            prepared_body["tool_invocation"]["request"]["params"]["token"],
+            "***"
+        );
+        let id = prepared_body["invocation_id"].take();
+        assert_eq!(id, prepared_body["tool_invocation"]["invocation_id"].take());
+        assert!(Uuid::parse_str(id.as_str().unwrap()).is_ok());
+        prepared_body["tool_invocation"]["timestamp"] =
+            full_body["tool_invocation"]["timestamp"].clone();
+        prepared_body["invocation_id"] = full_body["invocation_id"].clone();
+        prepared_body["tool_invocation"]["invocation_id"] = full_body["invocation_id"].clone();
+        assert_eq!(prepared_body, full_body);
+    }
+
+    #[tokio::test]
+    async fn test_on_degrade_hook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let seen = calls.clone();
+        let mut cfg = Config::builder()
+            .sidecar_url("http://127.0.0.1:19999")
+            .timeout(Duration::from_millis(10))
+            .fail_open(true)
+            .on_degrade(move |invocation, err| {
+                seen.lock()
+                    .unwrap()
+                    .push((invocation.invocation_id.clone(), err.is_connect()));
+            })
+            .build();
+        assert!(format!("{cfg:?}").contains("on_degrade: Some(\"<fn>\")"));
+
+        let record = Client::new(cfg.clone())
+            .decide(sample_invocation())
+            .await
+            .unwrap();
+        assert!(record.degraded);
+
+        cfg.fail_open = false;
+        Client::new(cfg.clone())
+            .decide_or_deny(sample_invocation())
+            .await;
+        cfg.sidecar_url = server.uri();
+        Client::new(cfg).decide_or_deny(sample_invocation()).await;
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                ("inv-001".to_string(), true),
+                ("inv-001".to_string(), true),
+                ("inv-001".to_string(), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_capability() {
+        let server = MockServer::start().await;
+        let mut deny = decision_body();
+        deny["decision"] = "DENY".into();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .and(wiremock::matchers::body_partial_json(serde_json::json!({
+                "tool_invocation": {"tool": {"capabilities": ["fs.write"]}}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(deny))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let defaults = InvocationDefaults {
+            actor: sample_invocation().actor,
+            agent: sample_invocation().agent,
+            context: sample_invocation().context,
+            tool_provider: "local".into(),
+            risk_class: "medium".into(),
+        };
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .invocation_defaults(defaults)
+            .build();
+        let client = Client::new(cfg);
+        assert!(client
+            .check_capability("fs.read", "other-repo")
+            .await
+            .unwrap());
+        assert!(!client
+            .check_capability("fs.write", "other-repo")
+            .await
+            .unwrap());
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let invocation = &body["tool_invocation"];
+        assert_eq!(invocation["context"]["repo"], "other-repo");
+        assert_eq!(invocation["context"]["environment"], "dev");
+        assert_eq!(invocation["actor"]["workspace_id"], "ws-1");
+        assert_eq!(invocation["tool"]["name"], "fs.read");
+    }
+
+    #[test]
+    fn test_matched_policies() {
+        let record: DecisionRecord = serde_json::from_value(decision_body()).unwrap();
+        assert!(record.matched_policies.is_empty());
+        assert!(serde_json::to_value(&record)
+            .unwrap()
+            .get("matched_policies")
+            .is_none());
+
+        let mut body = decision_body();
+        body["decision"] = "DENY".into();
+        body["matched_policies"] = serde_json::json!([
+            {"id": "r1", "name": "allow-reads", "effect": "ALLOW"},
+            {"id": "r2", "name": "no-prod-writes", "effect": "DENY"},
+        ]);
+        let record: DecisionRecord = serde_json::from_value(body).unwrap();
+        let denied: Vec<_> = record.denied_by().iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(denied, ["r2"]);
+        assert!(record.extra("matched_policies").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_health_gate_skips_sidecar_after_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(decision_body())
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .timeout(Duration::from_millis(50))
+            .fail_open(true)
+            .health_gate(Duration::from_millis(300))
+            .build();
+        let client = Client::new(cfg);
+
+        assert!(client.decide(sample_invocation()).await.unwrap().degraded);
+        let started = Instant::now();
+        assert!(client.decide(sample_invocation()).await.unwrap().degraded);
+        assert!(started.elapsed() < Duration::from_millis(20));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!client.decide(sample_invocation()).await.unwrap().degraded);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(decision_body())
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .timeout(Duration::from_millis(50))
+                .fail_open(true)
+                .circuit_breaker(CircuitBreakerConfig::new(2, Duration::from_millis(200)))
+                .build(),
+        );
+        for _ in 0..2 {
+            assert!(client.decide(sample_invocation()).await.unwrap().degraded);
+        }
+        assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+        let started = Instant::now();
+        assert!(client.decide(sample_invocation()).await.unwrap().degraded);
+        assert!(started.elapsed() < Duration::from_millis(20));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        // Starts the probe and still fails fast.
+        assert!(client.decide(sample_invocation()).await.unwrap().degraded);
+        for _ in 0..50 {
+            if client.circuit_state() == Some(CircuitState::Closed) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+        assert!(!client.decide(sample_invocation()).await.unwrap().degraded);
+        let decides = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|r| r.url.path() == "/v1/decide")
+            .count();
+        assert_eq!(decides, 3);
     }
 
-    fn decision_body() -> serde_json::Value {
-        serde_json::json!({
-            "invocation_id": "inv-001",
-            "decision": "ALLOW",
-            "decision_code": "SG_ALLOW",
-            "reason_codes": [],
-            "policy_version": "1.0.0",
-            "budgets": {},
-            "evidence": {"hash": "abc", "signature": "sig", "key_id": "key1"},
-            "degraded": false,
-            "entitlement_version": "1.0",
-            "license_mode": "online",
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_sidecar() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = std::env::temp_dir().join(format!("skillgate-uds-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let socket = dir.join("sidecar.sock");
+        let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // Headers, then the Content-Length bytes of body after them.
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "connection closed mid-request");
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                let Some(end) = text.find("\r\n\r\n") else {
+                    continue;
+                };
+                let length: usize = text
+                    .lines()
+                    .find_map(|l| {
+                        l.to_ascii_lowercase()
+                            .strip_prefix("content-length:")?
+                            .trim()
+                            .parse()
+                            .ok()
+                    })
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break;
+                }
+            }
+            let body = decision_body().to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(format!("unix://{}", socket.display()))
+                .timeout(Duration::from_secs(5))
+                .build(),
+        );
+        let record = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.invocation_id, "inv-001");
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("POST /v1/decide HTTP/1.1\r\n"),
+            "{request}"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let relative = Config::builder().sidecar_url("unix://sidecar.sock").build();
+        assert!(matches!(
+            Client::try_new(relative),
+            Err(Error::InvalidConfig(_))
+        ));
+        let shadowed = Config::builder()
+            .sidecar_url("unix:///var/run/skillgate.sock")
+            .shadow_url("http://shadow:8910")
+            .build();
+        assert!(matches!(
+            Client::try_new(shadowed),
+            Err(Error::InvalidConfig(m)) if m.contains("shadow_url")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_evidence_fails_closed() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut signed = DecisionRecord::new("inv-001", "DENY", "SG_DENY");
+        signed.evidence.hash = hex::encode(Sha256::digest(canonical_decision_bytes(&signed)));
+        signed.evidence.signature =
+            hex::encode(key.sign(signed.evidence.hash.as_bytes()).to_bytes());
+        signed.evidence.key_id = "k1".into();
+        let mut tampered = signed.clone();
+        tampered.decision = "ALLOW".into();
+        tampered.decision_code = "SG_ALLOW".into();
+
+        let keyring = Arc::new(Keyring::new());
+        keyring.insert("k1", key.verifying_key());
+        for (record, verified) in [(signed, true), (tampered, false)] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/v1/decide"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(&record))
+                .mount(&server)
+                .await;
+            let client = Client::new(
+                Config::builder()
+                    .sidecar_url(server.uri())
+                    .fail_open(true)
+                    .verify_evidence(keyring.clone())
+                    .build(),
+            );
+            let result = client.decide(sample_invocation()).await;
+            if verified {
+                assert_eq!(result.unwrap().decision, "DENY");
+            } else {
+                assert!(matches!(
+                    result.unwrap_err().root(),
+                    Error::InvalidEvidence(_)
+                ));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sidecar_keyring_fetches_and_rotates() {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let keys = [
+            SigningKey::from_bytes(&[7u8; 32]),
+            SigningKey::from_bytes(&[9u8; 32]),
+        ];
+        let jwks = |n: usize| {
+            let keys: Vec<_> = keys[..n]
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    serde_json::json!({
+                        "kty": "OKP",
+                        "crv": "Ed25519",
+                        "kid": format!("k{}", i + 1),
+                        "x": URL_SAFE_NO_PAD.encode(key.verifying_key().as_bytes()),
+                    })
+                })
+                .collect();
+            serde_json::json!({ "keys": keys })
+        };
+        let signed = |i: usize| {
+            let mut record = DecisionRecord::new("inv-001", "ALLOW", "SG_ALLOW");
+            record.evidence.hash = hex::encode(Sha256::digest(canonical_decision_bytes(&record)));
+            record.evidence.signature =
+                hex::encode(keys[i].sign(record.evidence.hash.as_bytes()).to_bytes());
+            record.evidence.key_id = format!("k{}", i + 1);
+            record
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/skillgate/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(1)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/skillgate/v1/keys"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(jwks(2)))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/skillgate/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(signed(0)))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/skillgate/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(signed(1)))
+            .mount(&server)
+            .await;
+
+        let keyring = Arc::new(Keyring::from_sidecar().min_refetch_interval(Duration::ZERO));
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .path_prefix("skillgate")
+                .verify_evidence(keyring.clone())
+                .build(),
+        );
+        let key_fetches = || async {
+            server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .filter(|r| r.url.path().ends_with("/keys"))
+                .count()
+        };
+        for _ in 0..2 {
+            client.decide(sample_invocation()).await.unwrap();
+        }
+        assert_eq!(key_fetches().await, 1);
+
+        // A decision signed with a rotated-in key refetches the keys once.
+        client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(key_fetches().await, 2);
+        assert!(keyring.get("k2").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_and_check_budgets() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/budgets"))
+            .and(query_param("workspace_id", "ws-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "budgets": {
+                    "fs.write": {
+                        "remaining": 3,
+                        "limit": 10,
+                        "window_seconds": 3600,
+                        "resets_at": "2026-01-01T13:00:00+00:00",
+                    },
+                },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/budgets"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/budgets/check"))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+                assert_eq!(body["workspace_id"], "ws-1");
+                assert_eq!(body["capability"], "fs.write");
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "allowed": body["cost"].as_u64().unwrap() <= 3,
+                    "budget": {"remaining": 3, "limit": 10},
+                }))
+            })
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::builder().sidecar_url(server.uri()).build();
+        cfg.invocation_defaults.actor.workspace_id = "ws-1".into();
+        let client = Client::new(cfg);
+        let budgets = client.get_budgets("ws-1").await.unwrap();
+        let budget = &budgets["fs.write"];
+        assert_eq!(budget.used(), 7);
+        assert_eq!(budget.window_seconds, Some(3600));
+        assert_eq!(
+            budget.next_reset(),
+            Some("2026-01-01T13:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            client.get_budgets("ws-2").await.unwrap_err(),
+            Error::NotFound("workspace ws-2".into())
+        );
+
+        let check = client.check_budget("fs.write", 2).await.unwrap();
+        assert!(check.allowed);
+        assert_eq!(check.budget, Some(BudgetStatus::new(3, 10)));
+        assert!(!client.check_budget("fs.write", 5).await.unwrap().allowed);
+    }
+
+    #[tokio::test]
+    async fn test_budget_tracker_follows_decisions() {
+        let server = MockServer::start().await;
+        for remaining in [4, 1] {
+            let mut body = decision_body();
+            body["budgets"] =
+                serde_json::json!({"fs.write": {"remaining": remaining, "limit": 10}});
+            Mock::given(method("POST"))
+                .and(path("/v1/decide"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+
+        let low = Arc::new(Mutex::new(Vec::new()));
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .budget_low_water(0.2)
+                .on_budget_low({
+                    let low = low.clone();
+                    move |capability, budget| {
+                        low.lock()
+                            .unwrap()
+                            .push((capability.to_string(), budget.remaining))
+                    }
+                })
+                .build(),
+        );
+        let tracker = client.budget_tracker().unwrap();
+        client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(tracker.remaining("fs.write"), Some(4));
+        assert!(low.lock().unwrap().is_empty());
+        client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(tracker.remaining("fs.write"), Some(1));
+        assert_eq!(*low.lock().unwrap(), [("fs.write".to_string(), 1)]);
+
+        assert!(Client::new(Config::default()).budget_tracker().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decision_cache() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        let mut updated = decision_body();
+        updated["policy_version"] = "2.0.0".into();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(updated))
+            .mount(&server)
+            .await;
+
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .cache(CacheConfig::new(Duration::from_millis(300), 100))
+                .build(),
+        );
+        let with_param = |value: &str| {
+            let mut invocation = sample_invocation();
+            invocation
+                .request
+                .params
+                .insert("path".into(), value.into());
+            invocation
+        };
+        client.decide(sample_invocation()).await.unwrap();
+        let mut retry = sample_invocation();
+        retry.invocation_id = "inv-002".into();
+        let cached = client.decide(retry).await.unwrap();
+        assert_eq!(cached.invocation_id, "inv-001");
+        client.decide(with_param("a")).await.unwrap();
+        assert_eq!(
+            client.cache_stats(),
+            Some(CacheStats {
+                hits: 1,
+                misses: 2,
+                entries: 2
+            })
+        );
+
+        // A new policy version empties the cache.
+        client.decide(with_param("b")).await.unwrap();
+        assert_eq!(client.cache_stats().unwrap().entries, 1);
+        client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(server.received_requests().await.unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_config_env_overrides() {
+        let env: HashMap<&str, &str> = [
+            ("SKILLGATE_TIMEOUT_MS", "250"),
+            ("SKILLGATE_FAIL_MODE", "open"),
+            ("SKILLGATE_SIDECAR_URLS", "http://a:1, http://b:2,"),
+            ("SKILLGATE_MAX_RESPONSE_BYTES", "0"),
+            ("SKILLGATE_SYNC_CLOCK", "TRUE"),
+            ("SKILLGATE_MAX_RETRIES", "2"),
+        ]
+        .into();
+        let var = |name: &str| {
+            env.get(name)
+                .map(|v| v.to_string())
+                .ok_or(std::env::VarError::NotPresent)
+        };
+        let mut cfg = Config::default();
+        cfg.apply_env(var, &mut Err).unwrap();
+        assert_eq!(cfg.timeout, Duration::from_millis(250));
+        assert!(cfg.fail_open);
+        assert_eq!(cfg.sidecar_urls, ["http://a:1", "http://b:2"]);
+        assert_eq!(cfg.max_response_bytes, None);
+        assert!(cfg.sync_clock);
+        assert_eq!(cfg.retry, Some(RetryPolicy::new(3)));
+
+        let mut env = env.clone();
+        env.insert("SKILLGATE_TIMEOUT_MS", "fast");
+        let var = |name: &str| {
+            env.get(name)
+                .map(|v| v.to_string())
+                .ok_or(std::env::VarError::NotPresent)
+        };
+        let err = Config::default().apply_env(var, &mut Err).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"invalid config: SKILLGATE_TIMEOUT_MS="fast": invalid digit found in string"#
+        );
+
+        // Lenient mode skips the bad value and applies the rest.
+        let mut cfg = Config::default();
+        let mut skipped = Vec::new();
+        cfg.apply_env(var, &mut |e| {
+            skipped.push(e);
+            Ok(())
         })
+        .unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(cfg.timeout, Config::default().timeout);
+        assert!(cfg.fail_open);
+
+        let var = |name: &str| match name {
+            "SKILLGATE_MAX_RETRIES" => Ok("-1".to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        };
+        let err = Config::default().apply_env(var, &mut Err).unwrap_err();
+        assert!(matches!(err, Error::InvalidConfig(m) if m.starts_with("SKILLGATE_MAX_RETRIES=")));
+    }
+
+    #[test]
+    fn test_config_default_ignores_env() {
+        let cfg = Config {
+            timeout: Duration::from_millis(75),
+            ..Default::default()
+        };
+        assert_eq!(cfg.sidecar_url, "http://localhost:8910");
+        assert_eq!(cfg.timeout, Duration::from_millis(75));
+        assert!(!cfg.fail_open);
+        assert!(cfg.slt.is_none());
+        assert_eq!(
+            format!("{:?}", Config::default()),
+            format!(
+                "{:?}",
+                Config {
+                    sidecar_url: "http://localhost:8910".into(),
+                    slt: None,
+                    ..Config::from_env()
+                }
+            )
+        );
     }
 
     #[tokio::test]
-    async fn test_decide_allow() {
+    async fn test_decide_ref_matches_decide() {
         let server = MockServer::start().await;
         Mock::given(method("POST"))
             .and(path("/v1/decide"))
@@ -352,35 +8145,388 @@ mod tests {
 
         let mut cfg = Config::from_env();
         cfg.sidecar_url = server.uri();
+        cfg.redact_param_keys = vec!["token".into()];
         let client = Client::new(cfg);
+        let mut invocation = sample_invocation();
+        invocation
+            .request
+            .params
+            .insert("token".into(), "secret".into());
+        invocation.request.resource_refs = vec!["a".into(), "a".into()];
 
-        let decision = client.decide(sample_invocation()).await.unwrap();
-        assert_eq!(decision.decision, "ALLOW");
-        assert_eq!(decision.decision_code, "SG_ALLOW");
+        let record = client.decide_ref(&invocation).await.unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        // The caller's copy is untouched.
+        assert_eq!(invocation.request.params["token"], "secret");
+        assert_eq!(invocation.request.resource_refs.len(), 2);
+        client.decide(invocation).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].body, requests[1].body);
     }
 
     #[tokio::test]
-    async fn test_fail_closed() {
+    async fn test_approval_callback() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/approvals/inv-001/callback"))
+            .and(wiremock::matchers::body_json(serde_json::json!({
+                "callback_url": "https://agent.example/approvals"
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
         let mut cfg = Config::from_env();
-        cfg.sidecar_url = "http://127.0.0.1:19999".into();
-        cfg.timeout = Duration::from_millis(10);
-        cfg.fail_open = false;
+        cfg.sidecar_url = server.uri();
+        Client::new(cfg)
+            .register_approval_callback("inv-001", "https://agent.example/approvals")
+            .await
+            .unwrap();
+
+        let body = br#"{"invocation_id":"inv-001","decision":"ALLOW"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"hook-key").unwrap();
+        mac.update(body);
+        let signature = hex::encode(mac.finalize().into_bytes());
+        assert!(verify_callback_signature(body, &signature, "hook-key"));
+        assert!(verify_callback_signature(
+            body,
+            &format!("sha256={signature}"),
+            "hook-key"
+        ));
+        assert!(!verify_callback_signature(body, &signature, "other-key"));
+        assert!(!verify_callback_signature(b"{}", &signature, "hook-key"));
+        assert!(!verify_callback_signature(body, "not-hex", "hook-key"));
+    }
+
+    #[tokio::test]
+    async fn test_path_ids_are_single_segments() {
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::any())
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .path_prefix("skillgate")
+                .build(),
+        );
+
+        let _ = client
+            .upload_resource("dir/file?x=1", b"hello", "text/plain")
+            .await;
+        let _ = client.session_summary("sess/1").await;
+        let _ = client.explain("inv/1").await;
+        let _ = client
+            .wait_for_approval("appr/1", Duration::from_millis(10))
+            .await;
+
+        let paths: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| (r.url.path().to_string(), r.url.query().map(str::to_string)))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("/skillgate/v1/resources/dir%2Ffile%3Fx=1".to_string(), None),
+                ("/skillgate/v1/sessions/sess%2F1".to_string(), None),
+                ("/skillgate/v1/decide/inv%2F1/explain".to_string(), None),
+                ("/skillgate/v1/approvals/appr%2F1".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_resource_dedupes_by_hash() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/resources/file-1"))
+            .and(header("Content-Type", "text/plain"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
         let client = Client::new(cfg);
+        client
+            .upload_resource("file-1", b"hello", "text/plain")
+            .await
+            .unwrap();
+        client
+            .upload_resource("file-1", b"hello", "text/plain")
+            .await
+            .unwrap();
+        client
+            .upload_resource("file-1", b"hello!", "text/plain")
+            .await
+            .unwrap();
 
-        let result = client.decide(sample_invocation()).await;
-        assert!(matches!(result, Err(Error::EnforcerUnavailable(_))));
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, b"hello");
+        assert_eq!(
+            requests[0].headers[CONTENT_SHA256_HEADER],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_outcome() {
+        let mut record = DecisionRecord::new("inv-1", "ALLOW", "SG_ALLOW");
+        assert!(
+            matches!(record.outcome(), Outcome::Allowed { obligations, .. } if obligations.is_empty())
+        );
+
+        record.decision = "DENY".into();
+        record.reason_codes = vec!["risk_too_high".into()];
+        assert!(matches!(
+            record.outcome(),
+            Outcome::Denied { reason_codes: [code], .. } if code == "risk_too_high"
+        ));
+
+        record.decision = "REQUIRE_APPROVAL".into();
+        record.extra.insert("approval_id".into(), "apr-7".into());
+        assert_eq!(
+            record.outcome(),
+            Outcome::ApprovalRequired {
+                approval_id: Some("apr-7")
+            }
+        );
+
+        record.decision = "PENDING".into();
+        assert!(matches!(record.outcome(), Outcome::Failed { .. }));
     }
 
     #[tokio::test]
-    async fn test_fail_open() {
+    async fn test_retry_policy_resends_same_invocation() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/registry/fs.read"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::new(3)
+        };
+        let client = Client::new(
+            Config::builder()
+                .sidecar_url(server.uri())
+                .retry(policy)
+                .build(),
+        );
+        let record = client.decide(sample_invocation()).await.unwrap();
+        assert_eq!(record.decision, "ALLOW");
+        let ids: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| r.body_json::<serde_json::Value>().unwrap()["invocation_id"].clone())
+            .collect();
+        assert_eq!(ids, ["inv-001", "inv-001", "inv-001"]);
+
+        // 400 is not retryable.
+        assert!(!client.register_tool("fs.read", &HashMap::new()).await);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .rate_limit(RateLimit::new(10.0, 2).mode(RateLimitMode::Reject))
+            .build();
+        // Two clients from one config share the bucket.
+        let a = Client::new(cfg.clone());
+        let b = Client::new(cfg.clone());
+        a.decide(sample_invocation()).await.unwrap();
+        b.decide(sample_invocation()).await.unwrap();
+        let err = a.decide(sample_invocation()).await.unwrap_err();
+        assert!(
+            matches!(err.root(), Error::RateLimited { retry_after } if *retry_after <= Duration::from_millis(100))
+        );
+
+        let cfg = Config {
+            timeout: Duration::from_millis(500),
+            rate_limit: Some(RateLimit::new(20.0, 1)),
+            ..cfg
+        };
+        let client = Client::new(cfg);
+        let started = Instant::now();
+        for _ in 0..3 {
+            client.decide(sample_invocation()).await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_request_interceptor() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .request_interceptor(|invocation| {
+                invocation
+                    .request
+                    .params
+                    .insert("deployment_id".into(), "dep-42".into());
+            })
+            .build();
+        let client = Client::new(cfg);
+        let invocation = sample_invocation();
+        client.decide_ref(&invocation).await.unwrap();
+        assert!(invocation.request.params.is_empty());
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body["tool_invocation"]["request"]["params"]["deployment_id"],
+            "dep-42"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decide_with_context_override() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(decision_body()))
+            .mount(&server)
+            .await;
+
+        let sample = sample_invocation();
+        let cfg = Config::builder()
+            .sidecar_url(server.uri())
+            .invocation_defaults(InvocationDefaults {
+                actor: sample.actor.clone(),
+                agent: sample.agent.clone(),
+                ..Default::default()
+            })
+            .build();
+        let staging = ExecutionContext {
+            environment: "staging".into(),
+            ..sample.context.clone()
+        };
+        Client::new(cfg)
+            .decide_with_context_override(sample.tool.clone(), ToolRequest::default(), staging)
+            .await
+            .unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let invocation = &body["tool_invocation"];
+        assert_eq!(invocation["context"]["environment"], "staging");
+        assert_eq!(invocation["actor"]["id"], "agent-1");
+        assert_eq!(invocation["agent"]["name"], "my-agent");
+        assert_eq!(invocation["tool"]["name"], "fs.read");
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_schemas_list_required_fields() {
+        let required =
+            |schema: schemars::schema::RootSchema| schema.schema.object.unwrap().required;
+        let invocation = required(invocation_schema());
+        for field in [
+            "invocation_id",
+            "timestamp",
+            "actor",
+            "agent",
+            "tool",
+            "request",
+            "context",
+        ] {
+            assert!(invocation.contains(field), "{field}");
+        }
+        let decision = required(decision_schema());
+        for field in [
+            "invocation_id",
+            "decision",
+            "decision_code",
+            "evidence",
+            "degraded",
+        ] {
+            assert!(decision.contains(field), "{field}");
+        }
+        assert!(!decision.contains("obligations"));
+    }
+
+    #[tokio::test]
+    async fn test_empty_decision() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .mount(&server)
+            .await;
+
         let mut cfg = Config::from_env();
-        cfg.sidecar_url = "http://127.0.0.1:19999".into();
-        cfg.timeout = Duration::from_millis(10);
+        cfg.sidecar_url = server.uri();
+        let err = Client::new(cfg.clone())
+            .decide(sample_invocation())
+            .await
+            .unwrap_err();
+        assert_eq!(err.into_root(), Error::EmptyDecision);
+
         cfg.fail_open = true;
-        let client = Client::new(cfg);
+        let record = Client::new(cfg).decide(sample_invocation()).await.unwrap();
+        assert!(record.degraded);
+        assert_eq!(record.decision, "ALLOW");
 
-        let decision = client.decide(sample_invocation()).await.unwrap();
-        assert!(decision.degraded);
-        assert_eq!(decision.decision_code, "SG_ALLOW_DEGRADED_AUDIT_ASYNC");
+        assert!(is_empty_decision(b""));
+        assert!(is_empty_decision(b" null\n"));
+        assert!(is_empty_decision(b"{ }"));
+        assert!(!is_empty_decision(b"{\"decision\":\"ALLOW\"}"));
+    }
+
+    #[tokio::test]
+    async fn test_ping_falls_back_to_health() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(30)))
+            .mount(&server)
+            .await;
+
+        let mut cfg = Config::from_env();
+        cfg.sidecar_url = server.uri();
+        cfg.timeout = Duration::from_secs(1);
+        let rtt = Client::new(cfg).ping().await.unwrap();
+        assert!(rtt >= Duration::from_millis(30));
+
+        let requests = server.received_requests().await.unwrap();
+        let paths: Vec<_> = requests.iter().map(|r| r.url.path()).collect();
+        assert_eq!(paths, ["/v1/ping", "/v1/health"]);
     }
 }
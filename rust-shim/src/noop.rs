@@ -0,0 +1,109 @@
+//! A stand-in for [`Client`](crate::Client) for builds with enforcement
+//! compiled out.
+//!
+//! [`NoopClient`] mirrors the `Client` constructors and enforcement methods
+//! but never touches the network, so call sites are the same in both builds:
+//!
+//! ```
+//! #[cfg(not(feature = "dev"))]
+//! type SkillGate = skillgate::Client;
+//! #[cfg(feature = "dev")]
+//! type SkillGate = skillgate::NoopClient;
+//!
+//! let client = SkillGate::new(skillgate::Config::from_env());
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{Config, DecisionRecord, Enforcer, Error, ToolInvocation};
+
+/// Decision code of every record returned by [`NoopClient`].
+pub const ENFORCEMENT_DISABLED_CODE: &str = "SG_ALLOW_ENFORCEMENT_DISABLED";
+
+/// Client that allows every invocation without contacting a sidecar.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopClient;
+
+impl NoopClient {
+    /// Accepts and ignores `cfg`, matching [`Client::new`](crate::Client::new).
+    pub fn new(_cfg: Config) -> Self {
+        Self
+    }
+
+    /// Never fails; matches [`Client::try_new`](crate::Client::try_new).
+    pub fn try_new(_cfg: Config) -> Result<Self, Error> {
+        Ok(Self)
+    }
+
+    /// Returns an ALLOW with decision code `SG_ALLOW_ENFORCEMENT_DISABLED`.
+    pub async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+        Ok(self.decide_or_deny(invocation).await)
+    }
+
+    /// Same as [`NoopClient::decide`]; it cannot fail.
+    pub async fn decide_or_deny(&self, invocation: ToolInvocation) -> DecisionRecord {
+        let mut record =
+            DecisionRecord::new(invocation.invocation_id, "ALLOW", ENFORCEMENT_DISABLED_CODE);
+        record.reason_codes = vec!["enforcement_disabled".into()];
+        record
+    }
+
+    /// Always reports success.
+    pub async fn register_tool(
+        &self,
+        _tool_name: &str,
+        _metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool {
+        true
+    }
+
+    /// Always succeeds immediately.
+    pub async fn register_tool_with_retry(
+        &self,
+        _tool_name: &str,
+        _metadata: &HashMap<String, serde_json::Value>,
+        _max_wait: Duration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Reports every tool as registered.
+    pub async fn register_tools(
+        &self,
+        boms: &[(String, HashMap<String, serde_json::Value>)],
+    ) -> Result<Vec<(String, bool)>, Error> {
+        Ok(boms.iter().map(|(name, _)| (name.clone(), true)).collect())
+    }
+
+    /// Always healthy.
+    pub async fn health(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Always ready.
+    pub async fn warmup(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Enforcer for NoopClient {
+    async fn decide(&self, invocation: ToolInvocation) -> Result<DecisionRecord, Error> {
+        NoopClient::decide(self, invocation).await
+    }
+
+    async fn health(&self) -> Result<(), Error> {
+        NoopClient::health(self).await
+    }
+
+    async fn register_tool(
+        &self,
+        tool_name: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> bool {
+        NoopClient::register_tool(self, tool_name, metadata).await
+    }
+}
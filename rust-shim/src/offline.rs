@@ -0,0 +1,243 @@
+//! Local evaluation of a static policy bundle for when the sidecar is unreachable.
+//!
+//! A bundle is a JSON document with an ordered rule list. The first rule whose
+//! populated fields all match the invocation wins; `default` applies when no
+//! rule matches.
+//!
+//! ```json
+//! {
+//!   "policy_version": "2026.03.1",
+//!   "rules": [
+//!     { "id": "deny-high", "risk_class": "high", "decision": "DENY" },
+//!     { "id": "allow-low", "risk_class": "low", "decision": "ALLOW" }
+//!   ],
+//!   "default": "DENY"
+//! }
+//! ```
+//!
+//! Every bundle carries a detached signature over its exact bytes in a file
+//! next to it, with `.sig` appended to the name (`bundle.json.sig`):
+//!
+//! ```json
+//! { "key_id": "policy-2026", "signature": "<hex>" }
+//! ```
+//!
+//! The key is looked up in
+//! [`Config::offline_bundle_keys`](crate::Config::offline_bundle_keys).
+//! Ed25519 signs the bundle bytes directly; ECDSA P-256 signs their SHA-256
+//! digest. A bundle that is unsigned, signed with an unknown key or altered
+//! after signing is rejected before any of its rules are used.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{DecisionRecord, Error, Keyring, ToolInvocation};
+
+/// Static rules evaluated locally when the sidecar cannot be reached.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineBundle {
+    pub policy_version: String,
+    #[serde(default)]
+    pub rules: Vec<OfflineRule>,
+    /// Decision applied when no rule matches. When absent, the client falls
+    /// back to its regular fail-open / fail-closed behaviour.
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// A single offline rule. Unset match fields match anything.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineRule {
+    pub id: String,
+    #[serde(default)]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub risk_class: Option<String>,
+    #[serde(default)]
+    pub capability: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// "ALLOW" | "DENY"
+    pub decision: String,
+}
+
+/// Contents of a bundle's `.sig` file.
+#[derive(Deserialize)]
+struct BundleSignature {
+    key_id: String,
+    signature: String,
+}
+
+impl OfflineRule {
+    fn matches(&self, invocation: &ToolInvocation) -> bool {
+        let field = |want: &Option<String>, got: &str| want.as_deref().is_none_or(|w| w == got);
+        field(&self.tool, &invocation.tool.name)
+            && field(&self.risk_class, &invocation.tool.risk_class)
+            && field(&self.environment, &invocation.context.environment)
+            && self
+                .capability
+                .as_ref()
+                .is_none_or(|c| invocation.tool.capabilities.contains(c))
+    }
+}
+
+impl OfflineBundle {
+    /// Read a bundle from a JSON file, check its signature against `keys`
+    /// and parse it. Fails with [`Error::OfflineBundle`] when the signature
+    /// file is missing or does not verify, or a rule's decision is not
+    /// exactly `ALLOW` or `DENY`.
+    pub fn load(path: &Path, keys: &Keyring) -> Result<Self, Error> {
+        let read = |path: &Path| {
+            std::fs::read(path)
+                .map_err(|e| Error::OfflineBundle(format!("{}: {e}", path.display())))
+        };
+        let raw = read(path)?;
+        let mut sig_path = PathBuf::from(path).into_os_string();
+        sig_path.push(".sig");
+        let sig_path = PathBuf::from(sig_path);
+        let signature: BundleSignature = serde_json::from_slice(&read(&sig_path)?)
+            .map_err(|e| Error::OfflineBundle(format!("{}: {e}", sig_path.display())))?;
+        let key = keys.get(&signature.key_id).ok_or_else(|| {
+            Error::OfflineBundle(format!(
+                "{}: unknown signing key {:?}",
+                path.display(),
+                signature.key_id
+            ))
+        })?;
+        let verified =
+            hex::decode(&signature.signature).is_ok_and(|signature| key.verifies(&raw, &signature));
+        if !verified {
+            return Err(Error::OfflineBundle(format!(
+                "{}: signature does not verify",
+                path.display()
+            )));
+        }
+
+        let bundle: Self = serde_json::from_slice(&raw)?;
+        let decisions = bundle
+            .rules
+            .iter()
+            .map(|rule| (format!("rule {:?}", rule.id), &rule.decision))
+            .chain(bundle.default.iter().map(|d| ("default".to_string(), d)));
+        for (what, decision) in decisions {
+            if !matches!(decision.as_str(), "ALLOW" | "DENY") {
+                return Err(Error::OfflineBundle(format!(
+                    "{}: {what} has decision {decision:?}, expected ALLOW or DENY",
+                    path.display()
+                )));
+            }
+        }
+        Ok(bundle)
+    }
+
+    /// Evaluate the invocation against the bundle.
+    ///
+    /// Returns `None` when neither a rule nor the bundle default applies.
+    pub fn evaluate(&self, invocation: &ToolInvocation) -> Option<DecisionRecord> {
+        let (decision, reason) = match self.rules.iter().find(|r| r.matches(invocation)) {
+            Some(rule) => (rule.decision.as_str(), format!("offline_rule:{}", rule.id)),
+            None => (self.default.as_deref()?, "offline_default".to_string()),
+        };
+        let code = format!("SG_{decision}_OFFLINE_LOCAL_EVAL");
+        let mut record = DecisionRecord::new(&invocation.invocation_id, decision, code);
+        record.reason_codes = vec!["enforcer_unavailable_local_eval".into(), reason];
+        record.policy_version = self.policy_version.clone();
+        record.degraded = true;
+        record.license_mode = "offline".into();
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("skillgate-bundle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    /// Write `bundle` to `dir/name` and, when `sign_with` is given, its
+    /// signature next to it; returns the bundle path.
+    fn write_bundle(
+        dir: &Path,
+        name: &str,
+        bundle: &serde_json::Value,
+        sign_with: Option<&SigningKey>,
+    ) -> PathBuf {
+        let path = dir.join(name);
+        let raw = bundle.to_string();
+        std::fs::write(&path, &raw).unwrap();
+        if let Some(key) = sign_with {
+            let signature = serde_json::json!({
+                "key_id": "k1",
+                "signature": hex::encode(key.sign(raw.as_bytes()).to_bytes()),
+            });
+            std::fs::write(dir.join(format!("{name}.sig")), signature.to_string()).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_load_requires_a_valid_signature() {
+        let dir = temp_dir();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let keys = Keyring::new();
+        keys.insert("k1", key.verifying_key());
+        let bundle = serde_json::json!({
+            "policy_version": "2026.1",
+            "rules": [{"id": "allow-low", "risk_class": "low", "decision": "ALLOW"}],
+            "default": "DENY",
+        });
+
+        let path = write_bundle(&dir, "signed.json", &bundle, Some(&key));
+        let loaded = OfflineBundle::load(&path, &keys).unwrap();
+        assert_eq!(loaded.rules.len(), 1);
+
+        // Tampered: the fail-closed default is flipped after signing.
+        let tampered = bundle.to_string().replace("\"DENY\"", "\"ALLOW\"");
+        std::fs::write(&path, tampered).unwrap();
+        let err = OfflineBundle::load(&path, &keys).unwrap_err();
+        assert!(matches!(err, Error::OfflineBundle(m) if m.contains("does not verify")));
+
+        let unsigned = write_bundle(&dir, "unsigned.json", &bundle, None);
+        let err = OfflineBundle::load(&unsigned, &keys).unwrap_err();
+        assert!(matches!(err, Error::OfflineBundle(m) if m.contains("unsigned.json.sig")));
+
+        let other_key = SigningKey::from_bytes(&[8u8; 32]);
+        let path = write_bundle(&dir, "other.json", &bundle, Some(&other_key));
+        assert!(matches!(
+            OfflineBundle::load(&path, &keys),
+            Err(Error::OfflineBundle(_))
+        ));
+        assert!(matches!(
+            OfflineBundle::load(&path, &Keyring::new()),
+            Err(Error::OfflineBundle(m)) if m.contains("unknown signing key")
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_decisions() {
+        let dir = temp_dir();
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let keys = Keyring::new();
+        keys.insert("k1", key.verifying_key());
+        for (rule, default) in [("DENNY", "DENY"), ("allow ", "DENY"), ("ALLOW", "allow")] {
+            let bundle = serde_json::json!({
+                "policy_version": "2026.1",
+                "rules": [{"id": "r1", "decision": rule}],
+                "default": default,
+            });
+            let path = write_bundle(&dir, "bundle.json", &bundle, Some(&key));
+            assert!(matches!(
+                OfflineBundle::load(&path, &keys),
+                Err(Error::OfflineBundle(m)) if m.contains("expected ALLOW or DENY")
+            ));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,16 @@
+//! The types most call sites need, for glob import:
+//!
+//! ```
+//! use skillgate::prelude::*;
+//! ```
+//!
+//! Only the client, its configuration, the invocation and decision models
+//! with their builders and the [`Enforcer`] trait are included; everything
+//! else stays at the crate root. Items are added here sparingly and not
+//! removed outside a major release.
+
+pub use crate::{
+    Actor, ActorBuilder, Agent, AgentBuilder, Client, Config, ConfigBuilder, Decision,
+    DecisionRecord, Enforcer, Error, ExecutionContext, ExecutionContextBuilder, Outcome, Tool,
+    ToolBuilder, ToolInvocation, ToolInvocationBuilder, ToolRequest, TrustTier,
+};
@@ -0,0 +1,108 @@
+//! Invocations with a pre-serialized, per-session part.
+//!
+//! For a given agent session the actor, agent and execution context do not
+//! change between calls. [`PreparedInvocation`] renders them to JSON once;
+//! [`Client::decide_prepared`](crate::Client::decide_prepared) then only
+//! serializes the per-call tool and request and splices them in.
+
+use chrono::{DateTime, Utc};
+
+use crate::wire::WireTimestamp;
+use crate::{
+    Actor, Agent, Envelope, Error, ExecutionContext, TimestampFormat, Tool, ToolInvocation,
+    ToolRequest,
+};
+
+/// The stable part of a [`ToolInvocation`], serialized ahead of time.
+#[derive(Debug, Clone)]
+pub struct PreparedInvocation {
+    actor: Actor,
+    agent: Agent,
+    context: ExecutionContext,
+    /// `"actor":{..},"agent":{..},"context":{..}`
+    segment: String,
+}
+
+impl PreparedInvocation {
+    /// Serialize the session-constant fields.
+    pub fn new(actor: Actor, agent: Agent, context: ExecutionContext) -> Result<Self, Error> {
+        let segment = format!(
+            r#""actor":{},"agent":{},"context":{}"#,
+            serde_json::to_string(&actor)?,
+            serde_json::to_string(&agent)?,
+            serde_json::to_string(&context)?,
+        );
+        Ok(Self {
+            actor,
+            agent,
+            context,
+            segment,
+        })
+    }
+
+    pub fn actor(&self) -> &Actor {
+        &self.actor
+    }
+
+    pub fn agent(&self) -> &Agent {
+        &self.agent
+    }
+
+    pub fn context(&self) -> &ExecutionContext {
+        &self.context
+    }
+
+    /// The full invocation, for paths that need the structured form
+    /// (signing, streaming, offline evaluation, degraded audit).
+    pub(crate) fn invocation(
+        &self,
+        invocation_id: String,
+        timestamp: DateTime<Utc>,
+        tool: Tool,
+        request: ToolRequest,
+    ) -> ToolInvocation {
+        ToolInvocation {
+            invocation_id,
+            timestamp,
+            actor: self.actor.clone(),
+            agent: self.agent.clone(),
+            tool,
+            request,
+            context: self.context.clone(),
+        }
+    }
+
+    /// Render a `decide` body equivalent to
+    /// [`DecideBody`](crate::wire::DecideBody) for the assembled invocation.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn render(
+        &self,
+        envelope: Envelope,
+        timestamp_format: TimestampFormat,
+        invocation_id: &str,
+        timestamp: &DateTime<Utc>,
+        tool: &Tool,
+        request: &ToolRequest,
+        truncated: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let id = serde_json::to_string(invocation_id)?;
+        let tool = serde_json::to_string(tool)?;
+        let request = serde_json::to_string(request)?;
+        let timestamp = serde_json::to_string(&WireTimestamp(timestamp, timestamp_format))?;
+        let invocation = format!(
+            r#""invocation_id":{id},"timestamp":{timestamp},{},"tool":{tool},"request":{request}"#,
+            self.segment
+        );
+        let truncated = match truncated {
+            0 => String::new(),
+            n => format!(r#","resource_refs_truncated":{n}"#),
+        };
+        let body = match envelope {
+            Envelope::Legacy => {
+                format!(r#"{{"invocation_id":{id},"tool_invocation":{{{invocation}}}{truncated}}}"#)
+            }
+            Envelope::Flat => format!("{{{invocation}{truncated}}}"),
+        };
+        Ok(body.into_bytes())
+    }
+}
@@ -0,0 +1,146 @@
+//! Bounded wait queue in front of the [`Config::max_concurrent`](crate::Config::max_concurrent)
+//! limit.
+//!
+//! `decide` calls that find every permit taken wait in the queue; the
+//! permit holders act as the worker pool draining it in FIFO order. When the
+//! queue is full, [`Overflow`] decides who gives way.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use futures::future::{self, Either};
+use tokio::sync::{oneshot, Notify, Semaphore, SemaphorePermit};
+
+use crate::Error;
+
+/// Settings for [`Config::queue`](crate::Config::queue).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Calls allowed to wait for a permit at once.
+    pub capacity: usize,
+    pub overflow: Overflow,
+}
+
+impl QueueConfig {
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        Self { capacity, overflow }
+    }
+}
+
+/// What happens to a `decide` call that arrives while the queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait for room in the queue.
+    #[default]
+    Block,
+    /// Fail the new call with [`Error::QueueFull`].
+    DropNewest,
+    /// Fail the longest-waiting call with [`Error::QueueFull`] and queue the
+    /// new one.
+    DropOldest,
+    /// Answer the new call with a degraded ALLOW, as if the sidecar were
+    /// unreachable with [`Config::fail_open`](crate::Config::fail_open) set.
+    FailOpen,
+}
+
+#[derive(Debug)]
+pub(crate) struct DecideQueue {
+    cfg: QueueConfig,
+    state: Mutex<State>,
+    /// Signalled whenever a waiter leaves the queue.
+    room: Notify,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_id: u64,
+    /// Waiting calls in arrival order, with the sender that evicts them.
+    waiting: VecDeque<(u64, oneshot::Sender<()>)>,
+}
+
+/// A call's place in the queue; leaving it (by dropping) makes room.
+struct Slot<'a> {
+    queue: &'a DecideQueue,
+    id: u64,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if let Some(pos) = state.waiting.iter().position(|(id, _)| *id == self.id) {
+            state.waiting.remove(pos);
+        }
+        drop(state);
+        self.queue.room.notify_one();
+    }
+}
+
+impl DecideQueue {
+    pub fn new(cfg: QueueConfig) -> Self {
+        Self {
+            cfg,
+            state: Mutex::default(),
+            room: Notify::new(),
+        }
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.cfg.overflow
+    }
+
+    /// Calls currently waiting for a permit.
+    pub fn depth(&self) -> usize {
+        self.state.lock().unwrap().waiting.len()
+    }
+
+    /// Take a permit from `limiter`, queueing while none is free. Fails with
+    /// [`Error::QueueFull`] when the call overflows or is evicted.
+    pub async fn acquire<'a>(&self, limiter: &'a Semaphore) -> Result<SemaphorePermit<'a>, Error> {
+        if self.depth() == 0 {
+            if let Ok(permit) = limiter.try_acquire() {
+                return Ok(permit);
+            }
+        }
+        let (slot, evicted) = self.enter().await?;
+        let acquire = std::pin::pin!(limiter.acquire());
+        match future::select(acquire, evicted).await {
+            Either::Left((permit, _)) => {
+                drop(slot);
+                Ok(permit.expect("limiter is never closed"))
+            }
+            Either::Right(_) => Err(Error::QueueFull),
+        }
+    }
+
+    async fn enter(&self) -> Result<(Slot<'_>, oneshot::Receiver<()>), Error> {
+        loop {
+            let room = self.room.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                let full = state.waiting.len() >= self.cfg.capacity;
+                let admit = match self.cfg.overflow {
+                    _ if !full => true,
+                    Overflow::Block => false,
+                    Overflow::DropNewest | Overflow::FailOpen => return Err(Error::QueueFull),
+                    Overflow::DropOldest => match state.waiting.pop_front() {
+                        // An evicted call that already won its permit ignores
+                        // this and proceeds.
+                        Some((_, evict)) => {
+                            let _ = evict.send(());
+                            true
+                        }
+                        None => return Err(Error::QueueFull),
+                    },
+                };
+                if admit {
+                    let id = state.next_id;
+                    state.next_id += 1;
+                    let (evict, evicted) = oneshot::channel();
+                    state.waiting.push_back((id, evict));
+                    return Ok((Slot { queue: self, id }, evicted));
+                }
+            }
+            room.await;
+        }
+    }
+}
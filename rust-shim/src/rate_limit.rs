@@ -0,0 +1,136 @@
+//! Client-side token bucket for `decide` calls.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What `decide` does when the bucket is empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Wait for a token, up to [`Config::timeout`](crate::Config::timeout),
+    /// then fail with [`Error::RateLimited`](crate::Error::RateLimited).
+    #[default]
+    Wait,
+    /// Fail with [`Error::RateLimited`](crate::Error::RateLimited) at once.
+    Reject,
+}
+
+/// Settings for [`Config::rate_limit`](crate::Config::rate_limit).
+///
+/// Clones share one bucket, so every client built from (a clone of) the
+/// same config draws from the same budget.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    bucket: Arc<TokenBucket>,
+    mode: RateLimitMode,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    permits_per_sec: f64,
+    burst: u32,
+    /// Available tokens (negative while waiters hold reservations) and the
+    /// time they were last refilled.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimit {
+    /// A bucket refilling at `permits_per_sec` that holds at most `burst`
+    /// tokens (minimum 1), starting full.
+    pub fn new(permits_per_sec: f64, burst: u32) -> Self {
+        let burst = burst.max(1);
+        Self {
+            bucket: Arc::new(TokenBucket {
+                permits_per_sec,
+                burst,
+                state: Mutex::new((f64::from(burst), Instant::now())),
+            }),
+            mode: RateLimitMode::Wait,
+        }
+    }
+
+    /// Set the empty-bucket behaviour.
+    pub fn mode(mut self, mode: RateLimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn permits_per_sec(&self) -> f64 {
+        self.bucket.permits_per_sec
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.bucket.burst
+    }
+
+    /// Take a token, waiting at most `max_wait` in [`RateLimitMode::Wait`].
+    /// On failure returns how long until a token would be available.
+    pub(crate) async fn acquire(&self, max_wait: Duration) -> Result<(), Duration> {
+        let wait = {
+            let mut state = self.bucket.state.lock().unwrap();
+            let (tokens, refilled) = &mut *state;
+            let now = Instant::now();
+            let rate = self.bucket.permits_per_sec;
+            *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * rate)
+                .min(f64::from(self.bucket.burst));
+            *refilled = now;
+            if *tokens >= 1.0 {
+                *tokens -= 1.0;
+                return Ok(());
+            }
+            let wait = if rate > 0.0 {
+                Duration::try_from_secs_f64((1.0 - *tokens) / rate).unwrap_or(Duration::MAX)
+            } else {
+                Duration::MAX
+            };
+            if self.mode == RateLimitMode::Reject || wait > max_wait {
+                return Err(wait);
+            }
+            // Reserve the token now so concurrent waiters queue up behind us.
+            *tokens -= 1.0;
+            wait
+        };
+        let reservation = Reservation(&self.bucket);
+        tokio::time::sleep(wait).await;
+        std::mem::forget(reservation);
+        Ok(())
+    }
+}
+
+/// A token reserved by a waiting [`RateLimit::acquire`], handed back if the
+/// wait is cancelled.
+struct Reservation<'a>(&'a TokenBucket);
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        state.0 = (state.0 + 1.0).min(f64::from(self.0.burst));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tiny_rate_does_not_panic() {
+        let limit = RateLimit::new(1e-20, 1);
+        limit.acquire(Duration::ZERO).await.unwrap();
+        assert_eq!(limit.acquire(Duration::ZERO).await, Err(Duration::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_returns_its_token() {
+        let limit = RateLimit::new(10.0, 1);
+        limit.acquire(Duration::ZERO).await.unwrap();
+        // Waits about 100 ms for the next token, but is dropped before.
+        let waiting = limit.acquire(Duration::from_secs(1));
+        assert!(tokio::time::timeout(Duration::from_millis(10), waiting)
+            .await
+            .is_err());
+
+        // Without the reservation returned, this would be about 190 ms.
+        let reject = limit.clone().mode(RateLimitMode::Reject);
+        let wait = reject.acquire(Duration::ZERO).await.unwrap_err();
+        assert!(wait <= Duration::from_millis(100), "{wait:?}");
+    }
+}
@@ -0,0 +1,85 @@
+//! Retries of transient sidecar failures; see [`RetryPolicy`].
+
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+
+/// Settings for [`Config::retry`](crate::Config::retry).
+///
+/// A request is retried when it could not be sent (connection refused or
+/// reset, timeout) or the sidecar answered with one of `retry_statuses`.
+/// Every attempt resends the same request, so a retried `decide` keeps its
+/// `invocation_id` and the sidecar can deduplicate it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first. 1 disables retrying.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubling for each one after.
+    pub base_delay: Duration,
+    /// Upper bound on any single delay, including one asked for with
+    /// `Retry-After`.
+    pub max_delay: Duration,
+    /// Share of each delay, in `0.0..=1.0`, replaced with a random amount
+    /// so that clients failing together do not retry in lockstep.
+    pub jitter: f64,
+    /// Response statuses worth retrying.
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    /// 3 attempts, 50 ms doubling up to 1 s, half of it jittered, retrying
+    /// 429, 502, 503 and 504.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+            jitter: 0.5,
+            retry_statuses: vec![429, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The default policy with `max_attempts` attempts.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait after failed attempt number `attempt` (from 1),
+    /// or `None` if the failure is not retried.
+    pub(crate) fn delay_after(
+        &self,
+        attempt: u32,
+        result: &Result<reqwest::Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let retry_after = match result {
+            Err(e) if e.is_builder() => return None,
+            Err(_) => None,
+            Ok(resp) if self.retry_statuses.contains(&resp.status().as_u16()) => resp
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok()?.trim().parse().ok())
+                .map(Duration::from_secs),
+            Ok(_) => return None,
+        };
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::thread_rng().gen::<f64>();
+        let backoff = backoff.mul_f64(1.0 - jitter);
+        Some(
+            retry_after
+                .map_or(backoff, |r| r.max(backoff))
+                .min(self.max_delay),
+        )
+    }
+}
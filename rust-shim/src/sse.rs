@@ -0,0 +1,85 @@
+//! Minimal incremental parser for `text/event-stream` bodies.
+
+/// A single server-sent event.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SseEvent {
+    pub id: Option<String>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Accumulates raw body chunks and yields complete events.
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    /// Bytes of the incomplete last line. Lines are decoded only once
+    /// complete, so a character split across chunks stays intact.
+    buf: Vec<u8>,
+    current: SseEvent,
+    has_data: bool,
+}
+
+impl SseParser {
+    /// Feed a chunk of the response body, returning every event it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buf.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                if self.has_data {
+                    events.push(std::mem::take(&mut self.current));
+                }
+                self.current = SseEvent::default();
+                self.has_data = false;
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "data" => {
+                    if self.has_data {
+                        self.current.data.push('\n');
+                    }
+                    self.current.data.push_str(value);
+                    self.has_data = true;
+                }
+                "id" => self.current.id = Some(value.to_string()),
+                "event" => self.current.event = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// Bytes held for the event currently being received.
+    pub fn pending_len(&self) -> usize {
+        self.buf.len() + self.current.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_split_across_chunks() {
+        let body = "data: {\"message\": \"zugriff verweigert \u{2014} \u{1F512}\"}\n\n";
+        let split = body.find('\u{1F512}').unwrap() + 2;
+        let mut parser = SseParser::default();
+        assert!(parser.push(&body.as_bytes()[..split]).is_empty());
+        let events = parser.push(&body.as_bytes()[split..]);
+        assert_eq!(
+            events,
+            [SseEvent {
+                data: "{\"message\": \"zugriff verweigert \u{2014} \u{1F512}\"}".into(),
+                ..SseEvent::default()
+            }]
+        );
+        assert_eq!(parser.pending_len(), 0);
+    }
+}
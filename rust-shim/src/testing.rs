@@ -0,0 +1,235 @@
+//! An in-memory sidecar for end-to-end tests of SkillGate-aware code.
+//! Requires the `test-util` feature.
+//!
+//! [`FakeSidecar`] serves `/v1/decide`, `/v1/health` and `/v1/registry` from
+//! a real local HTTP server, so the [`Client`](crate::Client) under test runs
+//! its full request path:
+//!
+//! ```no_run
+//! # async fn run() {
+//! use skillgate::testing::FakeSidecar;
+//!
+//! let sidecar = FakeSidecar::start().await;
+//! sidecar.deny_tool("fs.write").set_budget("fs.read", 10);
+//!
+//! let client = skillgate::Config::builder()
+//!     .sidecar_url(sidecar.url())
+//!     .build();
+//! # }
+//! ```
+//!
+//! Every tool is allowed unless denied. A tool with a budget is allowed
+//! until the budget is used up, one unit per ALLOW, and denied with
+//! [`BUDGET_EXHAUSTED_CODE`] after that.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+use crate::{BudgetStatus, DecisionRecord};
+
+/// Policy version reported by every [`FakeSidecar`] decision.
+pub const FAKE_POLICY_VERSION: &str = "fake";
+
+/// Decision code of a DENY for a tool passed to [`FakeSidecar::deny_tool`].
+pub const TOOL_DENIED_CODE: &str = "SG_DENY_TOOL";
+
+/// Decision code of a DENY for a tool whose budget is used up.
+pub const BUDGET_EXHAUSTED_CODE: &str = "SG_DENY_BUDGET_EXHAUSTED";
+
+/// A local HTTP server answering like a sidecar with a programmable policy.
+/// The policy can be changed at any time; the server stops when dropped.
+pub struct FakeSidecar {
+    server: MockServer,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    denied: HashSet<String>,
+    budgets: HashMap<String, BudgetStatus>,
+    registry: HashMap<String, serde_json::Value>,
+    decisions: Vec<DecisionRecord>,
+}
+
+impl FakeSidecar {
+    /// Start a server on a free local port that allows every tool.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        Mock::given(method("GET"))
+            .and(path("/v1/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "ok",
+            })))
+            .mount(&server)
+            .await;
+        let shared = state.clone();
+        Mock::given(method("POST"))
+            .and(path("/v1/decide"))
+            .respond_with(move |req: &Request| shared.lock().unwrap().decide(req))
+            .mount(&server)
+            .await;
+        let shared = state.clone();
+        Mock::given(method("GET"))
+            .and(path("/v1/registry"))
+            .respond_with(move |_: &Request| {
+                let state = shared.lock().unwrap();
+                let tools: Vec<_> = state
+                    .registry
+                    .iter()
+                    .map(|(name, metadata)| {
+                        let mut tool = metadata.clone();
+                        if let Some(fields) = tool.as_object_mut() {
+                            fields.insert("name".into(), name.clone().into());
+                        }
+                        tool
+                    })
+                    .collect();
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "tools": tools }))
+            })
+            .mount(&server)
+            .await;
+        let shared = state.clone();
+        Mock::given(method("PUT"))
+            .and(path_regex("^/v1/registry/[^/]+$"))
+            .respond_with(move |req: &Request| {
+                let name = req.url.path().trim_start_matches("/v1/registry/");
+                let Ok(metadata) = serde_json::from_slice(&req.body) else {
+                    return ResponseTemplate::new(400);
+                };
+                shared
+                    .lock()
+                    .unwrap()
+                    .registry
+                    .insert(name.to_string(), metadata);
+                ResponseTemplate::new(200)
+            })
+            .mount(&server)
+            .await;
+
+        Self { server, state }
+    }
+
+    /// Base URL for [`Config::sidecar_url`](crate::Config::sidecar_url).
+    pub fn url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// Deny every invocation of `tool`.
+    pub fn deny_tool(&self, tool: impl Into<String>) -> &Self {
+        self.state.lock().unwrap().denied.insert(tool.into());
+        self
+    }
+
+    /// Undo [`deny_tool`](Self::deny_tool).
+    pub fn allow_tool(&self, tool: &str) -> &Self {
+        self.state.lock().unwrap().denied.remove(tool);
+        self
+    }
+
+    /// Allow `tool` `limit` more times, replacing any earlier budget.
+    pub fn set_budget(&self, tool: impl Into<String>, limit: u64) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .budgets
+            .insert(tool.into(), BudgetStatus::new(limit, limit));
+        self
+    }
+
+    /// Every decision returned so far, oldest first.
+    pub fn decisions(&self) -> Vec<DecisionRecord> {
+        self.state.lock().unwrap().decisions.clone()
+    }
+}
+
+impl State {
+    fn decide(&mut self, req: &Request) -> ResponseTemplate {
+        let Ok(body) = serde_json::from_slice::<serde_json::Value>(&req.body) else {
+            return ResponseTemplate::new(400);
+        };
+        // Accept both the legacy envelope and flat bodies.
+        let invocation = body.get("tool_invocation").unwrap_or(&body);
+        let text = |pointer| invocation.pointer(pointer).and_then(|v| v.as_str());
+        let (Some(invocation_id), Some(tool)) = (text("/invocation_id"), text("/tool/name")) else {
+            return ResponseTemplate::new(422);
+        };
+
+        let budget = self.budgets.get_mut(tool);
+        let mut record = if self.denied.contains(tool) {
+            let mut record = DecisionRecord::new(invocation_id, "DENY", TOOL_DENIED_CODE);
+            record.reason_codes = vec!["tool_denied".into()];
+            record
+        } else if budget.as_ref().is_some_and(|b| b.remaining == 0) {
+            let mut record = DecisionRecord::new(invocation_id, "DENY", BUDGET_EXHAUSTED_CODE);
+            record.reason_codes = vec!["budget_exhausted".into()];
+            record
+        } else {
+            DecisionRecord::new(invocation_id, "ALLOW", "SG_ALLOW")
+        };
+        if let Some(budget) = budget {
+            if record.is_allowed() {
+                budget.remaining -= 1;
+            }
+            record.budgets.insert(tool.to_string(), budget.clone());
+        }
+        record.policy_version = FAKE_POLICY_VERSION.into();
+        record.license_mode = "online".into();
+        self.decisions.push(record.clone());
+        ResponseTemplate::new(200).set_body_json(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Config, Tool, ToolInvocation};
+
+    #[tokio::test]
+    async fn test_fake_sidecar_policy() {
+        let sidecar = FakeSidecar::start().await;
+        sidecar.deny_tool("fs.write").set_budget("fs.read", 2);
+        let client = Client::new(Config::builder().sidecar_url(sidecar.url()).build());
+        client.health().await.unwrap();
+
+        let invocation = |tool: &str| ToolInvocation {
+            invocation_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now(),
+            actor: Default::default(),
+            agent: Default::default(),
+            tool: Tool {
+                name: tool.into(),
+                provider: "local".into(),
+                capabilities: vec![tool.into()],
+                risk_class: "low".into(),
+            },
+            request: Default::default(),
+            context: Default::default(),
+        };
+        let write = client.decide(invocation("fs.write")).await.unwrap();
+        assert_eq!(write.decision_code, TOOL_DENIED_CODE);
+
+        let mut codes = Vec::new();
+        for _ in 0..3 {
+            codes.push(
+                client
+                    .decide(invocation("fs.read"))
+                    .await
+                    .unwrap()
+                    .decision_code,
+            );
+        }
+        assert_eq!(codes, ["SG_ALLOW", "SG_ALLOW", BUDGET_EXHAUSTED_CODE]);
+        assert_eq!(sidecar.decisions().len(), 4);
+
+        assert!(client.register_tool("fs.read", &HashMap::new()).await);
+        let crate::RegistryListing::Changed(tools) = client.list_tools().await.unwrap() else {
+            panic!("expected a tool list");
+        };
+        assert_eq!(tools[0].name, "fs.read");
+    }
+}
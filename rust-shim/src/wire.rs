@@ -0,0 +1,266 @@
+//! Encoding of `decide` request bodies.
+//!
+//! Small bodies are built as a `serde_json::Value` (needed for signing).
+//! Bodies whose params exceed [`Config::stream_body_threshold`](crate::Config::stream_body_threshold)
+//! are serialized on a blocking thread straight into fixed-size chunks that
+//! feed the HTTP body, so the full JSON never exists in memory alongside the
+//! invocation.
+//!
+//! With a binary [`Config::wire_format`](crate::Config::wire_format) the body
+//! is encoded in one piece instead.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use futures::stream;
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use tokio::sync::mpsc;
+
+use crate::{Envelope, Error, TimestampFormat, ToolInvocation, WireFormat};
+
+/// Size of each streamed body chunk.
+const CHUNK: usize = 64 * 1024;
+
+/// Chunks buffered between the serializer and the connection.
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum DecideBody<'a> {
+    Legacy {
+        invocation_id: &'a str,
+        tool_invocation: WireInvocation<'a>,
+        #[serde(skip_serializing_if = "is_zero")]
+        resource_refs_truncated: usize,
+    },
+    Flat {
+        #[serde(flatten)]
+        invocation: WireInvocation<'a>,
+        #[serde(skip_serializing_if = "is_zero")]
+        resource_refs_truncated: usize,
+    },
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl<'a> DecideBody<'a> {
+    pub fn new(
+        envelope: Envelope,
+        timestamp_format: TimestampFormat,
+        invocation: &'a ToolInvocation,
+        truncated: usize,
+    ) -> Self {
+        let wire = WireInvocation {
+            invocation,
+            timestamp_format,
+        };
+        match envelope {
+            Envelope::Legacy => Self::Legacy {
+                invocation_id: &invocation.invocation_id,
+                tool_invocation: wire,
+                resource_refs_truncated: truncated,
+            },
+            Envelope::Flat => Self::Flat {
+                invocation: wire,
+                resource_refs_truncated: truncated,
+            },
+        }
+    }
+}
+
+/// A [`ToolInvocation`] with its timestamp in the configured
+/// [`TimestampFormat`]. Field order and names match the derived
+/// `Serialize` of `ToolInvocation`.
+pub(crate) struct WireInvocation<'a> {
+    invocation: &'a ToolInvocation,
+    timestamp_format: TimestampFormat,
+}
+
+impl Serialize for WireInvocation<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ToolInvocation {
+            invocation_id,
+            timestamp,
+            actor,
+            agent,
+            tool,
+            request,
+            context,
+        } = self.invocation;
+        let mut s = serializer.serialize_struct("ToolInvocation", 7)?;
+        s.serialize_field("invocation_id", invocation_id)?;
+        s.serialize_field(
+            "timestamp",
+            &WireTimestamp(timestamp, self.timestamp_format),
+        )?;
+        s.serialize_field("actor", actor)?;
+        s.serialize_field("agent", agent)?;
+        s.serialize_field("tool", tool)?;
+        s.serialize_field("request", request)?;
+        s.serialize_field("context", context)?;
+        s.end()
+    }
+}
+
+/// A timestamp in the given [`TimestampFormat`].
+pub(crate) struct WireTimestamp<'a>(pub &'a DateTime<Utc>, pub TimestampFormat);
+
+impl Serialize for WireTimestamp<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.1 {
+            TimestampFormat::Rfc3339 => self.0.serialize(serializer),
+            TimestampFormat::EpochMillis => serializer.serialize_i64(self.0.timestamp_millis()),
+            TimestampFormat::EpochSeconds => serializer.serialize_i64(self.0.timestamp()),
+        }
+    }
+}
+
+impl WireFormat {
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => "application/msgpack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "application/cbor",
+        }
+    }
+
+    /// The format of a response with this `Content-Type`, if it is one this
+    /// build can decode.
+    pub(crate) fn from_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        let is = |names: &[&str]| names.iter().any(|n| n.eq_ignore_ascii_case(essence));
+        if is(&["application/json"]) {
+            return Some(Self::Json);
+        }
+        #[cfg(feature = "msgpack")]
+        if is(&[
+            "application/msgpack",
+            "application/x-msgpack",
+            "application/vnd.msgpack",
+        ]) {
+            return Some(Self::MsgPack);
+        }
+        #[cfg(feature = "cbor")]
+        if is(&["application/cbor"]) {
+            return Some(Self::Cbor);
+        }
+        None
+    }
+
+    pub(crate) fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            // Named fields, so the sidecar sees the same keys as in JSON.
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => {
+                rmp_serde::to_vec_named(value).map_err(|e| Error::Codec(e.to_string()))
+            }
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(value, &mut buf).map_err(|e| Error::Codec(e.to_string()))?;
+                Ok(buf)
+            }
+        }
+    }
+
+    pub(crate) fn decode<T: DeserializeOwned>(self, body: &[u8]) -> Result<T, Error> {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(body)?),
+            #[cfg(feature = "msgpack")]
+            Self::MsgPack => rmp_serde::from_slice(body).map_err(|e| Error::Codec(e.to_string())),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => ciborium::from_reader(body).map_err(|e| Error::Codec(e.to_string())),
+        }
+    }
+}
+
+/// Serialized JSON length of `value`, computed without allocating the output.
+pub(crate) fn serialized_len<T: Serialize + ?Sized>(value: &T) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    match serde_json::to_writer(&mut counter, value) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+/// A chunked request body that serializes the invocation as it is sent.
+///
+/// Must be called from within a Tokio runtime.
+pub(crate) fn streaming_body(
+    envelope: Envelope,
+    timestamp_format: TimestampFormat,
+    invocation: Arc<ToolInvocation>,
+    truncated: usize,
+) -> reqwest::Body {
+    let (tx, rx) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    tokio::task::spawn_blocking(move || {
+        let mut writer = ChunkWriter {
+            buf: Vec::with_capacity(CHUNK),
+            tx,
+        };
+        let body = DecideBody::new(envelope, timestamp_format, &invocation, truncated);
+        let result = serde_json::to_writer(&mut writer, &body)
+            .map_err(io::Error::from)
+            .and_then(|()| writer.flush());
+        if let Err(e) = result {
+            // Fails only if the request was dropped, in which case nobody is
+            // left to report to.
+            let _ = writer.tx.blocking_send(Err(e));
+        }
+    });
+    reqwest::Body::wrap_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+struct ChunkWriter {
+    buf: Vec<u8>,
+    tx: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl ChunkWriter {
+    fn send(&mut self) -> io::Result<()> {
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK));
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "request body dropped"))
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= CHUNK {
+            self.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        self.send()
+    }
+}